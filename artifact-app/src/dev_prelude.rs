@@ -23,6 +23,9 @@ pub use ergo::*;
 #[allow(unused_imports)]
 pub use quicli::prelude::*;
 
+#[cfg(test)]
+pub use artifact_test::artifact::{art, names};
+
 #[macro_export]
 macro_rules! work_dir { [$cmd:expr] => {{
     match $cmd.work_dir {
@@ -64,6 +67,11 @@ pub fn set_log_verbosity(pkg: &str, verbosity: u64) -> Result<()> {
     Ok(())
 }
 
+/// Format a completion ratio as a percentage, i.e. `50.0%`.
+pub fn percent(ratio: f32) -> String {
+    format!("{:.1}%", ratio * 100.0)
+}
+
 /// Find the project repo directory.
 pub fn find_repo(initial: &PathDir) -> Result<PathDir> {
     let mut dir = initial.clone();
@@ -0,0 +1,651 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.diff
+//! Semantic diff of the artifacts between two revisions of a project.
+
+use difference::{Changeset, Difference};
+use std::io;
+
+use crate::dev_prelude::*;
+use crate::tempdir::TempDir;
+use artifact_data::git;
+use artifact_data::*;
+use termstyle::Color::*;
+use termstyle::{El, Text};
+
+macro_rules! t { [$t:expr] => {{
+    Text::new($t.into())
+}}}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "diff")]
+/// Show how the artifacts changed between two git revisions or directories.
+pub struct Diff {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "REV1")]
+    /// The original git revision or project directory.
+    pub rev1: String,
+
+    #[structopt(name = "REV2")]
+    /// The changed git revision or project directory [default: the working directory]
+    pub rev2: Option<String>,
+
+    #[structopt(long = "format", default_value = "human")]
+    /// Format of the output from [human, json, markdown]
+    pub format: String,
+
+    #[structopt(long = "plain")]
+    /// Do not display color in the output.
+    pub plain: bool,
+}
+
+/// Run the `art diff` command
+pub fn run(cmd: Diff) -> Result<i32> {
+    let mut w = io::stdout();
+
+    set_log_verbosity!(cmd);
+    let repo = find_repo(&work_dir!(cmd))?;
+    info!("Running art-diff in repo {}", repo.display());
+    let format = DiffFormat::from_str(&cmd.format)?;
+
+    // the temporary checkouts must live until the projects are loaded
+    let (_old_tmp, old) = load_revision(&repo, &cmd.rev1)?;
+    let (_new_tmp, new) = match cmd.rev2 {
+        Some(ref rev) => load_revision(&repo, rev)?,
+        None => (None, read_project(repo.clone())?.1),
+    };
+
+    let diff = diff_artifacts(
+        &old.artifacts,
+        &old.settings.base,
+        &new.artifacts,
+        &new.settings.base,
+    );
+    match format {
+        DiffFormat::Human => {
+            for mut el in diff.human_style() {
+                if cmd.plain {
+                    el.set_plain();
+                }
+                el.paint(&mut w)?;
+            }
+        }
+        DiffFormat::Json => write!(w, "{}", expect!(json::to_string_pretty(&diff.to_json())))?,
+        DiffFormat::Markdown => write!(w, "{}", diff.to_markdown())?,
+    }
+    Ok(0)
+}
+
+/// Load the project at `rev`, which is either a directory or a git revision.
+///
+/// A git revision is exported into a temporary directory, which is returned
+/// so that it lives as long as it is needed.
+fn load_revision(repo: &PathDir, rev: &str) -> Result<(Option<TempDir>, Project)> {
+    if let Ok(dir) = PathDir::new(rev) {
+        let dir = find_repo(&dir)?;
+        let (_, project) = read_project(dir)?;
+        return Ok((None, project));
+    }
+
+    let (tmp, dir) = git::export_revision(repo, rev)?;
+    info!("Loading revision {} from {}", rev, dir.display());
+    let (_, project) = read_project(dir)?;
+    Ok((Some(tmp), project))
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum DiffFormat {
+    Human,
+    Json,
+    Markdown,
+}
+
+impl DiffFormat {
+    fn from_str(s: &str) -> Result<DiffFormat> {
+        Ok(match s {
+            "human" => DiffFormat::Human,
+            "json" => DiffFormat::Json,
+            "markdown" => DiffFormat::Markdown,
+            _ => bail!(
+                "Invalid format {:?}, must be one of human, json or markdown",
+                s
+            ),
+        })
+    }
+}
+
+/// The differences between two sets of artifacts.
+#[derive(Debug, Default)]
+struct ProjectDiff {
+    added: Vec<Name>,
+    removed: Vec<Name>,
+    /// `(old, new)` names of renamed artifacts, detected by their identical text.
+    renamed: Vec<(Name, Name)>,
+    changed: Vec<ArtifactDiff>,
+}
+
+/// The changes to an artifact which exists in both projects.
+#[derive(Debug)]
+struct ArtifactDiff {
+    name: Name,
+    /// The old name, if it was renamed.
+    old_name: Option<Name>,
+    file: Option<(String, String)>,
+    text: Option<Vec<Difference>>,
+    partof_added: Vec<Name>,
+    partof_removed: Vec<Name>,
+    impl_: Option<(String, String)>,
+    spc: Option<(f32, f32)>,
+    tst: Option<(f32, f32)>,
+}
+
+/// Compute the differences between the `old` and `new` artifacts. Files are
+/// compared relative to the base of their project.
+fn diff_artifacts(
+    old: &IndexMap<Name, Artifact>,
+    old_base: &Path,
+    new: &IndexMap<Name, Artifact>,
+    new_base: &Path,
+) -> ProjectDiff {
+    let mut out = ProjectDiff::default();
+    let mut removed: Vec<&Name> = old.keys().filter(|n| !new.contains_key(*n)).collect();
+    let mut added: Vec<&Name> = new.keys().filter(|n| !old.contains_key(*n)).collect();
+    removed.sort();
+    added.sort();
+
+    // An added artifact with the same text as a removed one was renamed.
+    let mut pairs: Vec<(&Name, &Name)> = old
+        .keys()
+        .filter(|n| new.contains_key(*n))
+        .map(|n| (n, n))
+        .collect();
+    for old_name in removed {
+        let text = old[old_name].text.trim();
+        let renamed = if text.is_empty() {
+            None
+        } else {
+            added
+                .iter()
+                .position(|n| new[*n].text.trim() == text)
+                .map(|i| added.remove(i))
+        };
+        match renamed {
+            Some(new_name) => {
+                out.renamed.push((old_name.clone(), new_name.clone()));
+                pairs.push((old_name, new_name));
+            }
+            None => out.removed.push(old_name.clone()),
+        }
+    }
+    out.added = added.into_iter().cloned().collect();
+
+    pairs.sort_by(|a, b| a.1.cmp(b.1));
+    for (old_name, new_name) in pairs {
+        let diff = diff_artifact(&old[old_name], old_base, &new[new_name], new_base);
+        if let Some(mut diff) = diff {
+            if old_name != new_name {
+                diff.old_name = Some(old_name.clone());
+            }
+            out.changed.push(diff);
+        }
+    }
+    out
+}
+
+/// Compute the differences of an artifact, returning `None` if it did not change.
+fn diff_artifact(
+    old: &Artifact,
+    old_base: &Path,
+    new: &Artifact,
+    new_base: &Path,
+) -> Option<ArtifactDiff> {
+    let changed = |a: &str, b: &str| {
+        if a == b {
+            None
+        } else {
+            Some((a.to_string(), b.to_string()))
+        }
+    };
+    let ratio = |a: f32, b: f32| {
+        if (a - b).abs() < 0.0005 {
+            None
+        } else {
+            Some((a, b))
+        }
+    };
+    let text = if old.text.trim() == new.text.trim() {
+        None
+    } else {
+        Some(Changeset::new(old.text.trim(), new.text.trim(), " ").diffs)
+    };
+    let mut partof_added: Vec<Name> = new.partof.difference(&old.partof).cloned().collect();
+    let mut partof_removed: Vec<Name> = old.partof.difference(&new.partof).cloned().collect();
+    partof_added.sort();
+    partof_removed.sort();
+
+    let out = ArtifactDiff {
+        name: new.name.clone(),
+        old_name: None,
+        file: changed(
+            &relative_path(&old.file, old_base),
+            &relative_path(&new.file, new_base),
+        ),
+        text: text,
+        partof_added: partof_added,
+        partof_removed: partof_removed,
+        impl_: changed(
+            &impl_str(&old.impl_, old_base),
+            &impl_str(&new.impl_, new_base),
+        ),
+        spc: ratio(old.completed.spc, new.completed.spc),
+        tst: ratio(old.completed.tst, new.completed.tst),
+    };
+    if out.file.is_none()
+        && out.text.is_none()
+        && out.partof_added.is_empty()
+        && out.partof_removed.is_empty()
+        && out.impl_.is_none()
+        && out.spc.is_none()
+        && out.tst.is_none()
+    {
+        None
+    } else {
+        Some(out)
+    }
+}
+
+/// Describe how the artifact is implemented. Line numbers are ignored since
+/// they change with unrelated edits.
+fn impl_str(impl_: &Impl, base: &Path) -> String {
+    match *impl_ {
+        Impl::Done(ref d) => format!("done: {}", d),
+        Impl::Code(ref code) => {
+            let mut locs = Vec::new();
+            if let Some(ref loc) = code.primary {
+                locs.push(relative_path(&loc.file, base));
+            }
+            for (sub, loc) in code.secondary.iter() {
+                locs.push(format!(
+                    "{}: {}",
+                    sub.as_str(),
+                    relative_path(&loc.file, base)
+                ));
+            }
+            format!("code: {}", locs.join(", "))
+        }
+        Impl::NotImpl => "not implemented".to_string(),
+    }
+}
+
+fn delta(change: (f32, f32)) -> String {
+    format!("{:+.1}", (change.1 - change.0) * 100.0)
+}
+
+impl ProjectDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.changed.is_empty()
+    }
+
+    /// #SPC-cli.diff_human
+    fn human_style(&self) -> Vec<El> {
+        let mut out = Vec::new();
+        macro_rules! line { [ $( $x:expr ),* ] => {{
+            $( out.push(El::Text($x)); )*
+            out.push(El::Text(t!("\n")));
+        }}}
+
+        if self.is_empty() {
+            line![t!("No artifacts changed")];
+        }
+        for name in self.added.iter() {
+            line![t!(format!("+ {}", name.as_str())).color(Green)];
+        }
+        for name in self.removed.iter() {
+            line![t!(format!("- {}", name.as_str())).color(Red)];
+        }
+        for (old, new) in self.renamed.iter() {
+            line![t!(format!("~ {} -> {}", old.as_str(), new.as_str())).color(Yellow)];
+        }
+        for diff in self.changed.iter() {
+            line![t!(format!("~ {}", diff.name.as_str())).bold()];
+            if let Some(ref old) = diff.old_name {
+                line![t!(format!("    renamed from {}", old.as_str()))];
+            }
+            if let Some((ref old, ref new)) = diff.file {
+                line![t!(format!("    file: {} -> {}", old, new))];
+            }
+            if !diff.partof_added.is_empty() || !diff.partof_removed.is_empty() {
+                out.push(El::Text(t!("    partof:")));
+                for n in diff.partof_added.iter() {
+                    out.push(El::Text(t!(format!(" +{}", n.as_str())).color(Green)));
+                }
+                for n in diff.partof_removed.iter() {
+                    out.push(El::Text(t!(format!(" -{}", n.as_str())).color(Red)));
+                }
+                line![];
+            }
+            if let Some((ref old, ref new)) = diff.impl_ {
+                line![t!(format!("    impl: {} -> {}", old, new))];
+            }
+            for (title, change) in [("spc", diff.spc), ("tst", diff.tst)].iter() {
+                if let Some(c) = *change {
+                    let color = if c.1 >= c.0 { Green } else { Red };
+                    line![
+                        t!(format!(
+                            "    {}: {} -> {} ",
+                            title,
+                            percent(c.0),
+                            percent(c.1)
+                        )),
+                        t!(format!("({})", delta(c))).color(color)
+                    ];
+                }
+            }
+            if let Some(ref text) = diff.text {
+                out.push(El::Text(t!("    text: ")));
+                for d in text.iter() {
+                    out.push(El::Text(match *d {
+                        Difference::Same(ref s) => t!(format!("{} ", s)),
+                        Difference::Add(ref s) => t!(format!("{{+{}+}} ", s)).color(Green),
+                        Difference::Rem(ref s) => t!(format!("[-{}-] ", s)).color(Red),
+                    }));
+                }
+                line![];
+            }
+        }
+        out
+    }
+
+    /// #SPC-cli.diff_json
+    fn to_json(&self) -> json::Value {
+        let names = |names: &[Name]| -> json::Value {
+            names
+                .iter()
+                .map(|n| json::Value::from(n.as_str()))
+                .collect()
+        };
+        let pair = |old: json::Value, new: json::Value| -> json::Value {
+            let mut out = json::Map::new();
+            out.insert("old".into(), old);
+            out.insert("new".into(), new);
+            json::Value::Object(out)
+        };
+        let opt = |v: Option<json::Value>| v.unwrap_or(json::Value::Null);
+
+        let changed: Vec<json::Value> = self
+            .changed
+            .iter()
+            .map(|diff| {
+                let mut out = json::Map::new();
+                out.insert("name".into(), diff.name.as_str().into());
+                out.insert(
+                    "old_name".into(),
+                    opt(diff.old_name.as_ref().map(|n| n.as_str().into())),
+                );
+                out.insert(
+                    "file".into(),
+                    opt(diff
+                        .file
+                        .as_ref()
+                        .map(|f| pair(f.0.clone().into(), f.1.clone().into()))),
+                );
+                let text = diff.text.as_ref().map(|text| {
+                    text.iter()
+                        .map(|d| {
+                            let (op, s) = match *d {
+                                Difference::Same(ref s) => ("same", s),
+                                Difference::Add(ref s) => ("add", s),
+                                Difference::Rem(ref s) => ("remove", s),
+                            };
+                            let mut out = json::Map::new();
+                            out.insert("op".into(), op.into());
+                            out.insert("text".into(), s.clone().into());
+                            json::Value::Object(out)
+                        })
+                        .collect()
+                });
+                out.insert("text".into(), opt(text));
+                let mut partof = json::Map::new();
+                partof.insert("added".into(), names(&diff.partof_added));
+                partof.insert("removed".into(), names(&diff.partof_removed));
+                out.insert("partof".into(), json::Value::Object(partof));
+                out.insert(
+                    "impl".into(),
+                    opt(diff
+                        .impl_
+                        .as_ref()
+                        .map(|i| pair(i.0.clone().into(), i.1.clone().into()))),
+                );
+                out.insert(
+                    "spc".into(),
+                    opt(diff.spc.map(|c| pair(c.0.into(), c.1.into()))),
+                );
+                out.insert(
+                    "tst".into(),
+                    opt(diff.tst.map(|c| pair(c.0.into(), c.1.into()))),
+                );
+                json::Value::Object(out)
+            })
+            .collect();
+
+        let mut out = json::Map::new();
+        out.insert("added".into(), names(&self.added));
+        out.insert("removed".into(), names(&self.removed));
+        out.insert(
+            "renamed".into(),
+            self.renamed
+                .iter()
+                .map(|(old, new)| pair(old.as_str().into(), new.as_str().into()))
+                .collect(),
+        );
+        out.insert("changed".into(), json::Value::Array(changed));
+        json::Value::Object(out)
+    }
+
+    /// #SPC-cli.diff_markdown
+    fn to_markdown(&self) -> String {
+        let mut out = String::from("## Artifact changes\n\n");
+        if self.is_empty() {
+            out.push_str("No artifacts changed.\n");
+            return out;
+        }
+        let mut section = |title: &str, lines: Vec<String>| {
+            if !lines.is_empty() {
+                out.push_str(&format!("### {}\n", title));
+                for l in lines {
+                    out.push_str(&format!("- {}\n", l));
+                }
+                out.push('\n');
+            }
+        };
+        section(
+            "Added",
+            self.added
+                .iter()
+                .map(|n| format!("`{}`", n.as_str()))
+                .collect(),
+        );
+        section(
+            "Removed",
+            self.removed
+                .iter()
+                .map(|n| format!("`{}`", n.as_str()))
+                .collect(),
+        );
+        section(
+            "Renamed",
+            self.renamed
+                .iter()
+                .map(|(o, n)| format!("`{}` → `{}`", o.as_str(), n.as_str()))
+                .collect(),
+        );
+
+        for diff in self.changed.iter() {
+            let mut lines = Vec::new();
+            if let Some(ref old) = diff.old_name {
+                lines.push(format!("renamed from `{}`", old.as_str()));
+            }
+            if let Some((ref old, ref new)) = diff.file {
+                lines.push(format!("file: `{}` → `{}`", old, new));
+            }
+            let code = |names: &[Name]| {
+                names
+                    .iter()
+                    .map(|n| format!("`{}`", n.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            if !diff.partof_added.is_empty() {
+                lines.push(format!("partof added: {}", code(&diff.partof_added)));
+            }
+            if !diff.partof_removed.is_empty() {
+                lines.push(format!("partof removed: {}", code(&diff.partof_removed)));
+            }
+            if let Some((ref old, ref new)) = diff.impl_ {
+                lines.push(format!("impl: {} → {}", old, new));
+            }
+            for (title, change) in [("spc", diff.spc), ("tst", diff.tst)].iter() {
+                if let Some(c) = *change {
+                    lines.push(format!(
+                        "{}: {} → {} ({})",
+                        title,
+                        percent(c.0),
+                        percent(c.1),
+                        delta(c)
+                    ));
+                }
+            }
+            if let Some(ref text) = diff.text {
+                let words: Vec<String> = text
+                    .iter()
+                    .map(|d| match *d {
+                        Difference::Same(ref s) => markdown_words(s),
+                        Difference::Add(ref s) => format!("**{}**", markdown_words(s)),
+                        Difference::Rem(ref s) => format!("~~{}~~", markdown_words(s)),
+                    })
+                    .collect();
+                lines.push(format!("text: {}", words.join(" ")));
+            }
+            section(&format!("Changed `{}`", diff.name.as_str()), lines);
+        }
+        out
+    }
+}
+
+/// Put the words on a single line so that they can be in a list item.
+fn markdown_words(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+fn test_artifact(name: &str, file: &str, partof: &[&str], text: &str, spc: f32) -> Artifact {
+    Artifact {
+        text: text.into(),
+        ..art(name, file, partof, spc, 0.0)
+    }
+}
+
+#[test]
+fn test_diff_artifacts() {
+    let to_map = |arts: Vec<Artifact>| -> IndexMap<Name, Artifact> {
+        arts.into_iter().map(|a| (a.name.clone(), a)).collect()
+    };
+    let old = to_map(vec![
+        test_artifact("REQ-a", "/old/design/a.md", &[], "the original text", 0.5),
+        test_artifact("REQ-b", "/old/design/a.md", &[], "b text", 0.0),
+        test_artifact("SPC-same", "/old/design/a.md", &["REQ-a"], "same", 1.0),
+        test_artifact(
+            "SPC-old",
+            "/old/design/a.md",
+            &["REQ-b"],
+            "moved around",
+            0.0,
+        ),
+        test_artifact("SPC-gone", "/old/design/a.md", &[], "gone", 0.0),
+    ]);
+    let new = to_map(vec![
+        test_artifact("REQ-a", "/new/design/b.md", &[], "the changed text", 0.75),
+        test_artifact("REQ-b", "/new/design/a.md", &[], "b text", 0.0),
+        test_artifact("SPC-same", "/new/design/a.md", &["REQ-a"], "same", 1.0),
+        test_artifact(
+            "SPC-new",
+            "/new/design/a.md",
+            &["REQ-a"],
+            "moved around",
+            0.0,
+        ),
+        test_artifact("TST-added", "/new/design/a.md", &[], "", 0.0),
+    ]);
+
+    let diff = diff_artifacts(&old, Path::new("/old"), &new, Path::new("/new"));
+    assert_eq!(diff.added, vec![name!("TST-added")]);
+    assert_eq!(diff.removed, vec![name!("SPC-gone")]);
+    assert_eq!(diff.renamed, vec![(name!("SPC-old"), name!("SPC-new"))]);
+    assert_eq!(diff.changed.len(), 2);
+
+    let req = &diff.changed[0];
+    assert_eq!(req.name, name!("REQ-a"));
+    assert_eq!(req.old_name, None);
+    assert_eq!(
+        req.file,
+        Some(("design/a.md".to_string(), "design/b.md".to_string()))
+    );
+    assert_eq!(req.spc, Some((0.5, 0.75)));
+    assert_eq!(req.tst, None);
+    assert_eq!(
+        req.text,
+        Some(vec![
+            Difference::Same("the".into()),
+            Difference::Rem("original".into()),
+            Difference::Add("changed".into()),
+            Difference::Same("text".into()),
+        ])
+    );
+
+    let spc = &diff.changed[1];
+    assert_eq!(spc.name, name!("SPC-new"));
+    assert_eq!(spc.old_name, Some(name!("SPC-old")));
+    assert_eq!(spc.partof_added, vec![name!("REQ-a")]);
+    assert_eq!(spc.partof_removed, vec![name!("REQ-b")]);
+    assert_eq!(spc.text, None);
+
+    let md = diff.to_markdown();
+    assert!(md.contains("### Added\n- `TST-added`\n"));
+    assert!(md.contains("### Renamed\n- `SPC-old` → `SPC-new`\n"));
+    assert!(md.contains("- text: the ~~original~~ **changed** text\n"));
+    assert!(md.contains("- spc: 50.0% → 75.0% (+25.0)\n"));
+
+    let value = diff.to_json();
+    assert_eq!(value["removed"], json::Value::from(vec!["SPC-gone"]));
+    assert_eq!(
+        value["changed"][1]["old_name"],
+        json::Value::from("SPC-old")
+    );
+
+    let same = diff_artifacts(&old, Path::new("/old"), &old, Path::new("/old"));
+    assert!(same.is_empty());
+}
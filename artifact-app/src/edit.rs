@@ -0,0 +1,462 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.edit
+//! Commands which modify existing artifacts: `link`, `unlink`, `set`, `mv`,
+//! `rm` and `edit` (which reads a batch of edits from stdin).
+//!
+//! All of them are resolved with `artifact_data::edit` and go through
+//! `modify_project`.
+
+use std::io::{self, Read};
+
+use crate::dev_prelude::*;
+use artifact_data::edit::{resolve_edits, EditOp};
+use artifact_data::*;
+use artifact_lib::query::Query;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "link")]
+/// Add to the `partof` of an artifact.
+pub struct Link {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "CHILD")]
+    /// The artifact to modify.
+    pub child: String,
+
+    #[structopt(name = "PARENT", raw(required = "true"))]
+    /// The artifacts to add to the `partof` of CHILD.
+    pub parents: Vec<String>,
+
+    #[structopt(long = "expect-id")]
+    /// Fail if the id of CHILD is not this (it was changed by someone else).
+    pub expect_id: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "unlink")]
+/// Remove from the `partof` of an artifact.
+pub struct Unlink {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "CHILD")]
+    /// The artifact to modify.
+    pub child: String,
+
+    #[structopt(name = "PARENT", raw(required = "true"))]
+    /// The artifacts to remove from the `partof` of CHILD.
+    pub parents: Vec<String>,
+
+    #[structopt(long = "expect-id")]
+    /// Fail if the id of CHILD is not this (it was changed by someone else).
+    pub expect_id: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "set")]
+/// Set the attributes of an artifact.
+pub struct Set {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "NAME")]
+    /// The artifact to modify.
+    pub name: String,
+
+    #[structopt(short = "t", long = "text")]
+    /// Replace the text, `-` reads it from stdin.
+    pub text: Option<String>,
+
+    #[structopt(short = "p", long = "partof")]
+    /// Replace the `partof`. Can be given multiple times.
+    pub partof: Vec<String>,
+
+    #[structopt(long = "done")]
+    /// Define the artifact as done, with this explanation.
+    pub done: Option<String>,
+
+    #[structopt(long = "not-done", conflicts_with = "done")]
+    /// Remove the `done` attribute.
+    pub not_done: bool,
+
+    #[structopt(long = "expect-id")]
+    /// Fail if the id of NAME is not this (it was changed by someone else).
+    pub expect_id: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "mv")]
+/// Move artifacts to another file.
+///
+/// The artifacts are selected by NAME, by `--query` and by the files they are
+/// in (`--from`). `--split` instead splits a file into one file per top level
+/// name prefix, i.e. `REQ-net` and `SPC-net-tcp` are moved to `net.md`.
+pub struct Mv {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "NAME")]
+    /// The artifacts to move.
+    pub names: Vec<String>,
+
+    #[structopt(short = "f", long = "file")]
+    /// The file to move the artifacts to.
+    pub file: Option<String>,
+
+    #[structopt(short = "q", long = "query")]
+    /// Move the artifacts matching the query (see `art ls --help`).
+    pub query: Option<String>,
+
+    #[structopt(long = "from")]
+    /// Move all artifacts in this file, merging the files. Can be given multiple times.
+    pub from: Vec<String>,
+
+    #[structopt(long = "split", conflicts_with = "file")]
+    /// Split this file into one file per top level name prefix.
+    pub split: Option<String>,
+
+    #[structopt(long = "dir", requires = "split")]
+    /// The directory to create the split files in [default: the directory of the split file]
+    pub dir: Option<String>,
+
+    #[structopt(long = "expect-id")]
+    /// Fail if the id of NAME is not this. Only valid with a single NAME.
+    pub expect_id: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "rm")]
+/// Delete artifacts.
+///
+/// The deleted artifacts are also removed from the `partof` of the remaining
+/// artifacts.
+pub struct Rm {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "NAME", raw(required = "true"))]
+    /// The artifacts to delete.
+    pub names: Vec<String>,
+
+    #[structopt(short = "r", long = "recursive")]
+    /// Also delete all artifacts which are (recursively) part of NAME.
+    pub recursive: bool,
+
+    #[structopt(long = "expect-id")]
+    /// Fail if the id of NAME is not this. Only valid with a single NAME.
+    pub expect_id: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "edit",
+    about = "\
+Apply a batch of edits read from stdin as a yaml or json list.
+
+Every edit has an `op` and the `name` of the artifact. The current id of the
+artifact is looked up automatically, or checked against `expect_id` if given.
+Files are relative to the project base.
+
+- op: new
+  name: SPC-foo
+  file: design/foo.md
+  partof: [REQ-foo]
+  text: the text
+- op: link       # or unlink
+  name: SPC-foo
+  partof: [REQ-bar]
+- op: set        # text, partof, done and not_done are optional
+  name: SPC-foo
+  done: by definition
+- op: mv
+  name: SPC-foo
+  file: design/bar.md
+- op: rm
+  name: SPC-old
+  recursive: true
+- op: merge
+  from: [design/a.md, design/b.md]
+  file: design/ab.md
+- op: split
+  file: design/big.md
+"
+)]
+pub struct Edit {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+}
+
+/// Run the `art link` command
+pub fn run_link(cmd: Link) -> Result<i32> {
+    set_log_verbosity!(cmd);
+    let edit = EditOp::Link {
+        name: Name::from_str(&cmd.child)?,
+        partof: parse_names(&cmd.parents)?,
+        expect_id: parse_id(&cmd.expect_id)?,
+    };
+    modify(&work_dir!(cmd), |_| Ok(vec![edit]))
+}
+
+/// Run the `art unlink` command
+pub fn run_unlink(cmd: Unlink) -> Result<i32> {
+    set_log_verbosity!(cmd);
+    let edit = EditOp::Unlink {
+        name: Name::from_str(&cmd.child)?,
+        partof: parse_names(&cmd.parents)?,
+        expect_id: parse_id(&cmd.expect_id)?,
+    };
+    modify(&work_dir!(cmd), |_| Ok(vec![edit]))
+}
+
+/// Run the `art set` command
+pub fn run_set(cmd: Set) -> Result<i32> {
+    set_log_verbosity!(cmd);
+    let text = match cmd.text {
+        Some(ref t) if t == "-" => {
+            let mut t = String::new();
+            io::stdin().read_to_string(&mut t)?;
+            Some(t)
+        }
+        t => t,
+    };
+    let partof = if cmd.partof.is_empty() {
+        None
+    } else {
+        Some(parse_names(&cmd.partof)?)
+    };
+    let edit = EditOp::Set {
+        name: Name::from_str(&cmd.name)?,
+        text: text,
+        partof: partof,
+        done: cmd.done,
+        not_done: cmd.not_done,
+        expect_id: parse_id(&cmd.expect_id)?,
+    };
+    modify(&work_dir!(cmd), |_| Ok(vec![edit]))
+}
+
+/// Run the `art mv` command
+pub fn run_mv(cmd: Mv) -> Result<i32> {
+    set_log_verbosity!(cmd);
+    let work_dir = work_dir!(cmd);
+    let expect_id = single_expect_id(&cmd.names, &cmd.expect_id)?;
+    let abs = |f: &str| work_dir.join(f).to_string_lossy().to_string();
+
+    let mut edits = Vec::new();
+    if let Some(ref split) = cmd.split {
+        ensure!(
+            cmd.names.is_empty() && cmd.query.is_none() && cmd.from.is_empty(),
+            "--split cannot be combined with NAME, --query or --from"
+        );
+        edits.push(EditOp::Split {
+            file: abs(split),
+            dir: cmd.dir.as_ref().map(|d| abs(d)),
+        });
+        return modify(&work_dir, |_| Ok(edits));
+    }
+
+    let file = match cmd.file {
+        Some(ref f) => abs(f),
+        None => bail!("--file is required unless --split is used"),
+    };
+    ensure!(
+        !cmd.names.is_empty() || cmd.query.is_some() || !cmd.from.is_empty(),
+        "nothing to move: pass NAME, --query or --from"
+    );
+    for name in cmd.names.iter() {
+        edits.push(EditOp::Mv {
+            name: Name::from_str(name)?,
+            file: file.clone(),
+            expect_id: expect_id,
+        });
+    }
+    if !cmd.from.is_empty() {
+        edits.push(EditOp::Merge {
+            from: cmd.from.iter().map(|f| abs(f)).collect(),
+            file: file.clone(),
+        });
+    }
+    let query = match cmd.query {
+        Some(ref q) => Some(Query::from_str(q)?),
+        None => None,
+    };
+    modify(&work_dir, |project| {
+        if let Some(query) = query {
+            for name in query.filter(project) {
+                edits.push(EditOp::Mv {
+                    name: name,
+                    file: file.clone(),
+                    expect_id: None,
+                });
+            }
+        }
+        Ok(edits)
+    })
+}
+
+/// Run the `art rm` command
+pub fn run_rm(cmd: Rm) -> Result<i32> {
+    set_log_verbosity!(cmd);
+    let expect_id = single_expect_id(&cmd.names, &cmd.expect_id)?;
+    let mut edits = Vec::new();
+    for name in cmd.names.iter() {
+        edits.push(EditOp::Rm {
+            name: Name::from_str(name)?,
+            recursive: cmd.recursive,
+            expect_id: expect_id,
+        });
+    }
+    modify(&work_dir!(cmd), |_| Ok(edits))
+}
+
+/// Run the `art edit` command
+pub fn run_edit(cmd: Edit) -> Result<i32> {
+    set_log_verbosity!(cmd);
+    let mut text = String::new();
+    io::stdin().read_to_string(&mut text)?;
+    let edits = parse_edits(&text)?;
+    modify(&work_dir!(cmd), |_| Ok(edits))
+}
+
+/// Parse the edits from a json or yaml list.
+fn parse_edits(text: &str) -> Result<Vec<EditOp>> {
+    if text.trim_start().starts_with('[') {
+        Ok(json::from_str(text)?)
+    } else {
+        Ok(yaml::from_str(text)?)
+    }
+}
+
+fn parse_names(raw: &[String]) -> Result<IndexSet<Name>> {
+    let mut out = IndexSet::new();
+    for r in raw.iter() {
+        out.extend(Names::from_str(r)?.iter().cloned());
+    }
+    Ok(out)
+}
+
+fn parse_id(raw: &Option<String>) -> Result<Option<HashIm>> {
+    match *raw {
+        Some(ref r) => match json::from_value(json::Value::from(r.trim_matches('"'))) {
+            Ok(id) => Ok(Some(id)),
+            Err(e) => bail!("invalid --expect-id {:?}: {}", r, e),
+        },
+        None => Ok(None),
+    }
+}
+
+fn single_expect_id(names: &[String], raw: &Option<String>) -> Result<Option<HashIm>> {
+    ensure!(
+        raw.is_none() || names.len() == 1,
+        "--expect-id can only be used with a single NAME"
+    );
+    parse_id(raw)
+}
+
+/// Build the edits from the current project, resolve them and modify the
+/// project.
+fn modify<F>(work_dir: &PathDir, build: F) -> Result<i32>
+where
+    F: FnOnce(&Project) -> Result<Vec<EditOp>>,
+{
+    let repo = find_repo(work_dir)?;
+    info!("Modifying artifacts in repo {}", repo.display());
+    let (_, project) = read_project(repo.clone())?;
+    let edits = build(&project)?;
+    let ops = resolve_edits(&project, edits)?;
+    let summary: Vec<String> = ops.iter().map(op_summary).collect();
+
+    let (lints, _) = modify_project(&repo, ops)?;
+    if !lints.is_empty() {
+        eprintln!("{}", lints);
+    }
+    for line in summary {
+        println!("{}", line);
+    }
+    Ok(0)
+}
+
+fn op_summary(op: &ArtifactOp) -> String {
+    match *op {
+        ArtifactOp::Create { ref artifact } => format!("created {}", artifact.name),
+        ArtifactOp::Update { ref artifact, .. } => format!("updated {}", artifact.name),
+        ArtifactOp::Delete { ref name, .. } => format!("deleted {}", name),
+    }
+}
+
+#[test]
+fn test_parse_edits() {
+    let yaml = "\
+- op: link
+  name: SPC-a
+  partof: [REQ-a]
+- op: rm
+  name: SPC-b
+";
+    let expected = vec![
+        EditOp::Link {
+            name: name!("SPC-a"),
+            partof: indexset! {name!("REQ-a")},
+            expect_id: None,
+        },
+        EditOp::Rm {
+            name: name!("SPC-b"),
+            recursive: false,
+            expect_id: None,
+        },
+    ];
+    assert_eq!(parse_edits(yaml).unwrap(), expected);
+
+    let json = r#"[
+        {"op": "link", "name": "SPC-a", "partof": ["REQ-a"]},
+        {"op": "rm", "name": "SPC-b"}
+    ]"#;
+    assert_eq!(parse_edits(json).unwrap(), expected);
+    assert!(parse_edits("- op: unknown\n  name: SPC-a\n").is_err());
+}
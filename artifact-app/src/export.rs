@@ -17,9 +17,11 @@
 
 use crate::dev_prelude::*;
 use artifact_data::*;
+use artifact_lib::query::Query;
 use std::io;
 
 use crate::frontend;
+use crate::matrix::{self, MatrixFormat, Trace};
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -50,7 +52,7 @@ pub struct Export {
     #[structopt(
         name = "TYPE",
         help = "\
-                The type of value to export. Supported values: [html, md]\n"
+                The type of value to export. Supported values: [html, md, matrix]\n"
     )]
     pub ty: String,
 
@@ -58,9 +60,22 @@ pub struct Export {
         name = "PATH",
         help = "\
                 The destination to export the data to.\n\n\
-                html: this will be the directory that is created.\n"
+                html: this will be the directory that is created.\n\
+                matrix: the file to write, or `-` for stdout.\n"
     )]
     pub path: String,
+
+    #[structopt(long = "format", default_value = "csv")]
+    /// matrix: the format of the matrix from [csv, xlsx-csv, html, md]
+    pub format: String,
+
+    #[structopt(long = "backward")]
+    /// matrix: trace the specifications and tests back to their requirements.
+    pub backward: bool,
+
+    #[structopt(short = "q", long = "query")]
+    /// matrix: only include rows for the artifacts matching the query.
+    pub query: Option<String>,
 }
 
 fn export_html(cmd: &Export, project_ser: ProjectSer) -> io::Result<()> {
@@ -89,6 +104,35 @@ fn export_markdown(cmd: &Export, project_ser: ProjectSer) -> io::Result<()> {
     Ok(())
 }
 
+fn export_matrix(cmd: &Export, project: &Project) -> Result<()> {
+    let format = MatrixFormat::from_str(&cmd.format)?;
+    let trace = if cmd.backward {
+        Trace::Backward
+    } else {
+        Trace::Forward
+    };
+    let selected = match cmd.query {
+        Some(ref q) => Some(Query::from_str(q)?.filter(project)),
+        None => None,
+    };
+    let rows = matrix::matrix_rows(
+        &project.artifacts,
+        &project.settings.base,
+        project.settings.code_url.as_ref().map(String::as_str),
+        trace,
+        selected.as_ref(),
+    )?;
+    let out = matrix::render_matrix(&rows, trace, format);
+    if cmd.path == "-" {
+        print!("{}", out);
+    } else {
+        let mut f = FileEdit::create(&cmd.path)?;
+        f.write_all(out.as_bytes())?;
+        f.flush()?;
+    }
+    Ok(())
+}
+
 lazy_static! {
     static ref REPLACE_TEXT_RE: Regex = expect!(Regex::new(
         r#"(?xim)
@@ -120,6 +164,10 @@ pub fn run(cmd: Export) -> Result<i32> {
     info!("Running art-export in repo {}", repo.display());
 
     let (_, project) = read_project(repo)?;
+    if cmd.ty.eq_ignore_ascii_case("matrix") {
+        export_matrix(&cmd, &project)?;
+        return Ok(0);
+    }
     let project_ser = project.to_ser();
 
     let result = match cmd.ty.to_ascii_lowercase().as_str() {
@@ -0,0 +1,524 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.graph
+//! Render the family of artifacts as DOT, Mermaid, PlantUML or JSON.
+
+use std::io;
+
+use crate::dev_prelude::*;
+use artifact_data::*;
+use artifact_lib::query::Query;
+use artifact_ser::markdown::{completed_color, SerMarkdown, GRAY};
+use artifact_ser::md_graph;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "graph")]
+/// Render a graph of the artifacts and their family.
+pub struct Graph {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "QUERY")]
+    /// The query selecting the focus artifacts (see `art ls -h`) [default: all artifacts]
+    pub query: Option<String>,
+
+    #[structopt(short = "d", long = "depth", default_value = "1")]
+    /// How many steps of the family to include around the focus artifacts.
+    pub depth: usize,
+
+    #[structopt(long = "direction", default_value = "both")]
+    /// Which family to include from [parents, children, both]
+    pub direction: String,
+
+    #[structopt(short = "S", long = "subnames")]
+    /// Include the subnames of the artifacts.
+    pub subnames: bool,
+
+    #[structopt(long = "format", default_value = "dot")]
+    /// Format of the output from [dot, mermaid, plantuml, json]
+    pub format: String,
+}
+
+/// Run the `art graph` command
+pub fn run(cmd: Graph) -> Result<i32> {
+    let mut w = io::stdout();
+
+    set_log_verbosity!(cmd);
+    let repo = find_repo(&work_dir!(cmd))?;
+    info!("Running art-graph in repo {}", repo.display());
+
+    let format = GraphFormat::from_str(&cmd.format)?;
+    let direction = Direction::from_str(&cmd.direction)?;
+    let (_, project) = read_project(repo)?;
+
+    let graph = match cmd.query {
+        Some(ref q) => {
+            let focus = Query::from_str(q)?.filter(&project);
+            select_graph(&project.artifacts, focus, cmd.depth, direction)
+        }
+        None => {
+            let all = project.artifacts.keys().cloned().collect();
+            select_graph(&project.artifacts, all, 0, direction)
+        }
+    };
+
+    let ser = project.to_ser();
+    let out = match format {
+        GraphFormat::Dot => render_dot(
+            &SerMarkdown::new(&ser),
+            &project.artifacts,
+            &graph,
+            cmd.subnames,
+        ),
+        GraphFormat::Mermaid => render_mermaid(&ser, &project.artifacts, &graph, cmd.subnames),
+        GraphFormat::PlantUml => render_plantuml(&ser, &project.artifacts, &graph, cmd.subnames),
+        GraphFormat::Json => render_json(&ser, &project.artifacts, &graph, cmd.subnames),
+    };
+    write!(w, "{}", out)?;
+    Ok(0)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+    PlantUml,
+    Json,
+}
+
+impl GraphFormat {
+    fn from_str(s: &str) -> Result<GraphFormat> {
+        Ok(match s {
+            "dot" => GraphFormat::Dot,
+            "mermaid" => GraphFormat::Mermaid,
+            "plantuml" => GraphFormat::PlantUml,
+            "json" => GraphFormat::Json,
+            _ => bail!(
+                "Invalid format {:?}, must be one of dot, mermaid, plantuml or json",
+                s
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Direction {
+    Parents,
+    Children,
+    Both,
+}
+
+impl Direction {
+    fn from_str(s: &str) -> Result<Direction> {
+        Ok(match s {
+            "parents" => Direction::Parents,
+            "children" => Direction::Children,
+            "both" => Direction::Both,
+            _ => bail!(
+                "Invalid direction {:?}, must be one of parents, children or both",
+                s
+            ),
+        })
+    }
+}
+
+/// The artifacts and connections to render.
+#[derive(Debug, Default, PartialEq)]
+struct SelectedGraph {
+    /// The artifacts selected directly.
+    focus: IndexSet<Name>,
+    /// All artifacts in the graph (including the focus), sorted.
+    nodes: Vec<Name>,
+    /// The `(partof, part)` connections between nodes, sorted.
+    edges: Vec<(Name, Name)>,
+}
+
+/// Select the focus artifacts and their family up to `depth` steps away.
+fn select_graph(
+    artifacts: &IndexMap<Name, Artifact>,
+    focus: IndexSet<Name>,
+    depth: usize,
+    direction: Direction,
+) -> SelectedGraph {
+    let mut nodes: IndexSet<Name> = focus.clone();
+    let mut current: Vec<Name> = focus.iter().cloned().collect();
+    for _ in 0..depth {
+        let mut next = Vec::new();
+        for name in current.iter() {
+            let art = match artifacts.get(name) {
+                Some(a) => a,
+                None => continue,
+            };
+            if direction != Direction::Children {
+                next.extend(art.partof.iter().filter(|n| !nodes.contains(*n)).cloned());
+            }
+            if direction != Direction::Parents {
+                next.extend(art.parts.iter().filter(|n| !nodes.contains(*n)).cloned());
+            }
+        }
+        nodes.extend(next.iter().cloned());
+        current = next;
+    }
+
+    let mut nodes: Vec<Name> = nodes.into_iter().collect();
+    nodes.sort();
+
+    let mut edges = Vec::new();
+    for name in nodes.iter() {
+        if let Some(art) = artifacts.get(name) {
+            for part in art.parts.iter() {
+                if nodes.contains(part) {
+                    edges.push((name.clone(), part.clone()));
+                }
+            }
+        }
+    }
+    edges.sort();
+
+    SelectedGraph {
+        focus: focus,
+        nodes: nodes,
+        edges: edges,
+    }
+}
+
+/// The subnames to render for the artifact.
+fn node_subnames<'a>(
+    artifacts: &'a IndexMap<Name, Artifact>,
+    name: &Name,
+    subnames: bool,
+) -> Vec<&'a SubName> {
+    match artifacts.get(name) {
+        Some(art) if subnames => art.subnames.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// #SPC-cli.graph_dot
+fn render_dot(
+    md: &SerMarkdown<'_>,
+    artifacts: &IndexMap<Name, Artifact>,
+    graph: &SelectedGraph,
+    subnames: bool,
+) -> String {
+    let mut dot = String::new();
+    for name in graph.nodes.iter() {
+        dot.push_str(&md_graph::name_dot(md, name, graph.focus.contains(name)));
+        for sub in node_subnames(artifacts, name, subnames) {
+            dot.push_str(&md_graph::artifact_subname_dot(md, name, sub));
+            dot.push_str(&md_graph::connect_subname_dot(name, sub));
+        }
+    }
+    for (from, to) in graph.edges.iter() {
+        dot.push_str(&md_graph::connect_names_dot(from, to));
+    }
+    md_graph::wrap_dot(&dot, true)
+}
+
+/// The colors of a node, the same as `md_graph::name_dot`.
+fn node_colors(artifacts: &IndexMap<Name, Artifact>, name: &Name) -> (&'static str, &'static str) {
+    match artifacts.get(name) {
+        Some(art) => (completed_color(&art.completed), GRAY),
+        None => ("black", "pink"),
+    }
+}
+
+/// #SPC-cli.graph_mermaid
+fn render_mermaid(
+    project: &ProjectSer,
+    artifacts: &IndexMap<Name, Artifact>,
+    graph: &SelectedGraph,
+    subnames: bool,
+) -> String {
+    let ids = node_ids(graph);
+    let mut out = String::from("graph LR\n");
+    let mut styles = String::new();
+    for (i, name) in graph.nodes.iter().enumerate() {
+        let id = &ids[name];
+        let (color, fill) = node_colors(artifacts, name);
+        out.push_str(&format!("    {}[\"{}\"]\n", id, name.as_str()));
+        if graph.focus.contains(name) && artifacts.contains_key(name) {
+            styles.push_str(&format!(
+                "    style {} color:{},stroke-width:3px\n",
+                id, color
+            ));
+        } else {
+            styles.push_str(&format!("    style {} color:{},fill:{}\n", id, color, fill));
+        }
+        for (j, sub) in node_subnames(artifacts, name, subnames).iter().enumerate() {
+            let sub_id = format!("n{}_{}", i, j);
+            out.push_str(&format!("    {}>\"{}\"]\n", sub_id, sub.as_str()));
+            out.push_str(&format!("    {} --> {}\n", id, sub_id));
+            styles.push_str(&format!(
+                "    style {} color:{}\n",
+                sub_id,
+                md_graph::subname_color(project, name, sub)
+            ));
+        }
+    }
+    for (from, to) in graph.edges.iter() {
+        out.push_str(&format!("    {} --> {}\n", ids[from], ids[to]));
+    }
+    out.push_str(&styles);
+    out
+}
+
+/// #SPC-cli.graph_plantuml
+fn render_plantuml(
+    project: &ProjectSer,
+    artifacts: &IndexMap<Name, Artifact>,
+    graph: &SelectedGraph,
+    subnames: bool,
+) -> String {
+    let ids = node_ids(graph);
+    let mut out = String::from("@startuml\nleft to right direction\n");
+    for (i, name) in graph.nodes.iter().enumerate() {
+        let id = &ids[name];
+        let (color, fill) = node_colors(artifacts, name);
+        let style = if graph.focus.contains(name) && artifacts.contains_key(name) {
+            format!("#line.bold;text:{}", color.trim_start_matches('#'))
+        } else {
+            format!(
+                "#{};text:{}",
+                fill.trim_start_matches('#'),
+                color.trim_start_matches('#')
+            )
+        };
+        out.push_str(&format!(
+            "rectangle \"{}\" as {} {}\n",
+            name.as_str(),
+            id,
+            style
+        ));
+        for (j, sub) in node_subnames(artifacts, name, subnames).iter().enumerate() {
+            let sub_id = format!("n{}_{}", i, j);
+            let color = md_graph::subname_color(project, name, sub).trim_start_matches('#');
+            out.push_str(&format!(
+                "card \"{}\" as {} #line:{};text:{}\n",
+                sub.as_str(),
+                sub_id,
+                color,
+                color
+            ));
+            out.push_str(&format!("{} --> {}\n", id, sub_id));
+        }
+    }
+    for (from, to) in graph.edges.iter() {
+        out.push_str(&format!("{} --> {}\n", ids[from], ids[to]));
+    }
+    out.push_str("@enduml\n");
+    out
+}
+
+/// Identifiers for the nodes which are valid in all formats.
+fn node_ids(graph: &SelectedGraph) -> IndexMap<&Name, String> {
+    graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name, format!("n{}", i)))
+        .collect()
+}
+
+/// #SPC-cli.graph_json
+fn render_json(
+    project: &ProjectSer,
+    artifacts: &IndexMap<Name, Artifact>,
+    graph: &SelectedGraph,
+    subnames: bool,
+) -> String {
+    let node = |id: &str, name: &Name, sub: Option<&SubName>, focus: bool| {
+        let art = artifacts.get(name);
+        let (completed, color) = match sub {
+            Some(s) => (json::Value::Null, md_graph::subname_color(project, name, s)),
+            None => (
+                expect!(json::to_value(art.map(|a| &a.completed))),
+                node_colors(artifacts, name).0,
+            ),
+        };
+        let mut out: IndexMap<&str, json::Value> = IndexMap::new();
+        out.insert("id", id.into());
+        out.insert("name", name.as_str().into());
+        out.insert(
+            "subname",
+            match sub {
+                Some(s) => s.as_str().into(),
+                None => json::Value::Null,
+            },
+        );
+        out.insert("focus", focus.into());
+        out.insert("exists", art.is_some().into());
+        out.insert("completed", completed);
+        out.insert("color", color.into());
+        out
+    };
+    let edge = |from: &str, to: &str| -> IndexMap<&str, json::Value> {
+        let mut out = IndexMap::new();
+        out.insert("from", from.into());
+        out.insert("to", to.into());
+        out
+    };
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for name in graph.nodes.iter() {
+        nodes.push(node(name.as_str(), name, None, graph.focus.contains(name)));
+        for sub in node_subnames(artifacts, name, subnames) {
+            let id = format!("{}{}", name.as_str(), sub.as_str());
+            nodes.push(node(&id, name, Some(sub), false));
+            edges.push(edge(name.as_str(), &id));
+        }
+    }
+    for (from, to) in graph.edges.iter() {
+        edges.push(edge(from.as_str(), to.as_str()));
+    }
+
+    let mut out: IndexMap<&str, json::Value> = IndexMap::new();
+    out.insert("nodes", expect!(json::to_value(&nodes)));
+    out.insert("edges", expect!(json::to_value(&edges)));
+    expect!(json::to_string_pretty(&out))
+}
+
+#[cfg(test)]
+fn test_artifacts() -> IndexMap<Name, Artifact> {
+    let art = |name: &str, partof: &[&str], parts: &[&str], spc: f32| Artifact {
+        parts: names(parts),
+        ..art(name, "/fake", partof, spc, 0.0)
+    };
+    let mut out: IndexMap<Name, Artifact> = vec![
+        art("REQ-a", &[], &["REQ-b"], 0.0),
+        art("REQ-b", &["REQ-a"], &["SPC-b"], 0.0),
+        art("SPC-b", &["REQ-b"], &["TST-b"], 1.0),
+        art("TST-b", &["SPC-b"], &[], 0.0),
+    ]
+    .into_iter()
+    .map(|a| (a.name.clone(), a))
+    .collect();
+    out[&name!("SPC-b")].subnames = indexset! {subname!(".sub")};
+    out
+}
+
+#[test]
+fn test_select_graph() {
+    let artifacts = test_artifacts();
+    let select = |focus: &str, depth: usize, direction: &str| -> (Vec<String>, usize) {
+        let graph = select_graph(
+            &artifacts,
+            indexset! {name!(focus)},
+            depth,
+            expect!(Direction::from_str(direction)),
+        );
+        let nodes = graph.nodes.iter().map(|n| n.as_str().to_string()).collect();
+        (nodes, graph.edges.len())
+    };
+
+    assert_eq!(select("REQ-b", 0, "both"), (vec!["REQ-b".to_string()], 0));
+    assert_eq!(
+        select("REQ-b", 1, "both"),
+        (strs(&["REQ-a", "REQ-b", "SPC-b"]), 2)
+    );
+    assert_eq!(
+        select("REQ-b", 1, "parents"),
+        (strs(&["REQ-a", "REQ-b"]), 1)
+    );
+    assert_eq!(
+        select("REQ-b", 5, "children"),
+        (strs(&["REQ-b", "SPC-b", "TST-b"]), 2)
+    );
+    assert_eq!(
+        select("TST-b", 2, "both"),
+        (strs(&["REQ-b", "SPC-b", "TST-b"]), 2)
+    );
+    assert!(Direction::from_str("up").is_err());
+    assert!(GraphFormat::from_str("svg").is_err());
+}
+
+/// The project used to color the subnames: `.sub` of `SPC-b` is implemented
+/// if `implemented`.
+#[cfg(test)]
+fn test_project_ser(implemented: bool) -> ProjectSer {
+    let settings = r#"{
+        "base": "/fake",
+        "settings_path": "/fake/.art/settings.toml",
+        "code_paths": [],
+        "exclude_code_paths": [],
+        "artifact_paths": [],
+        "exclude_artifact_paths": [],
+        "code_url": null
+    }"#;
+    let mut code_impls = IndexMap::new();
+    if implemented {
+        let code = ImplCodeSer {
+            primary: None,
+            secondary: indexmap! {
+                subname!(".sub") => CodeLocSer { file: "/fake/lib.rs".into(), line: 1 },
+            },
+        };
+        code_impls.insert(name!("SPC-b"), code);
+    }
+    ProjectSer {
+        settings: expect!(json::from_str(settings)),
+        code_impls: code_impls,
+        artifacts: IndexMap::new(),
+    }
+}
+
+#[cfg(test)]
+fn strs(s: &[&str]) -> Vec<String> {
+    s.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_render_mermaid() {
+    let artifacts = test_artifacts();
+    let graph = select_graph(&artifacts, indexset! {name!("SPC-b")}, 1, Direction::Both);
+    let project = test_project_ser(false);
+    assert_eq!(
+        render_mermaid(&project, &artifacts, &graph, true),
+        "\
+graph LR
+    n0[\"REQ-b\"]
+    n1[\"SPC-b\"]
+    n1_0>\".sub\"]
+    n1 --> n1_0
+    n2[\"TST-b\"]
+    n0 --> n1
+    n1 --> n2
+    style n0 color:#FF4136,fill:#DCDEE2
+    style n1 color:#0074D9,stroke-width:3px
+    style n1_0 color:#FF4136
+    style n2 color:#FF4136,fill:#DCDEE2
+"
+    );
+    let plantuml = render_plantuml(&project, &artifacts, &graph, false);
+    assert!(plantuml.starts_with("@startuml\n"));
+    assert!(plantuml.contains("rectangle \"SPC-b\" as n1 #line.bold;text:0074D9\n"));
+    assert!(plantuml.contains("n1 --> n2\n"));
+    assert!(plantuml.ends_with("@enduml\n"));
+
+    // the subnames are colored like in the dot graphs
+    let project = test_project_ser(true);
+    let mermaid = render_mermaid(&project, &artifacts, &graph, true);
+    assert!(mermaid.contains("    style n1_0 color:#0074D9\n"));
+    let plantuml = render_plantuml(&project, &artifacts, &graph, true);
+    assert!(plantuml.contains("card \".sub\" as n1_0 #line:0074D9;text:0074D9\n"));
+}
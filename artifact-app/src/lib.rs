@@ -49,12 +49,22 @@ use quicli::prelude::*;
 mod dev_prelude;
 
 mod check;
+mod diff;
+mod edit;
 mod export;
 mod fmt;
 mod frontend;
+mod graph;
 mod init;
 mod ls;
+mod matrix;
+mod new;
+mod recover;
 mod serve;
+mod snapshot;
+mod stats;
+mod tree;
+mod trend;
 
 /// #SPC-cli
 pub fn run() -> Result<i32> {
@@ -66,7 +76,21 @@ pub fn run() -> Result<i32> {
         .subcommand(init::Init::clap())
         .subcommand(check::Check::clap())
         .subcommand(fmt::Fmt::clap())
+        .subcommand(recover::Recover::clap())
+        .subcommand(new::New::clap())
+        .subcommand(edit::Link::clap())
+        .subcommand(edit::Unlink::clap())
+        .subcommand(edit::Set::clap())
+        .subcommand(edit::Mv::clap())
+        .subcommand(edit::Rm::clap())
+        .subcommand(edit::Edit::clap())
         .subcommand(ls::Ls::clap())
+        .subcommand(tree::Tree::clap())
+        .subcommand(graph::Graph::clap())
+        .subcommand(diff::Diff::clap())
+        .subcommand(stats::Stats::clap())
+        .subcommand(snapshot::Snapshot::clap())
+        .subcommand(trend::Trend::clap())
         .subcommand(serve::Serve::clap())
         .subcommand(export::Export::clap());
 
@@ -76,7 +100,21 @@ pub fn run() -> Result<i32> {
         ("init", Some(args)) => init::run(init::Init::from_clap(&args)),
         ("check", Some(args)) => check::run(check::Check::from_clap(&args)),
         ("fmt", Some(args)) => fmt::run(fmt::Fmt::from_clap(&args)),
+        ("recover", Some(args)) => recover::run(recover::Recover::from_clap(&args)),
+        ("new", Some(args)) => new::run(new::New::from_clap(&args)),
+        ("link", Some(args)) => edit::run_link(edit::Link::from_clap(&args)),
+        ("unlink", Some(args)) => edit::run_unlink(edit::Unlink::from_clap(&args)),
+        ("set", Some(args)) => edit::run_set(edit::Set::from_clap(&args)),
+        ("mv", Some(args)) => edit::run_mv(edit::Mv::from_clap(&args)),
+        ("rm", Some(args)) => edit::run_rm(edit::Rm::from_clap(&args)),
+        ("edit", Some(args)) => edit::run_edit(edit::Edit::from_clap(&args)),
         ("ls", Some(args)) => ls::run(ls::Ls::from_clap(&args)),
+        ("tree", Some(args)) => tree::run(tree::Tree::from_clap(&args)),
+        ("graph", Some(args)) => graph::run(graph::Graph::from_clap(&args)),
+        ("diff", Some(args)) => diff::run(diff::Diff::from_clap(&args)),
+        ("stats", Some(args)) => stats::run(stats::Stats::from_clap(&args)),
+        ("snapshot", Some(args)) => snapshot::run(snapshot::Snapshot::from_clap(&args)),
+        ("trend", Some(args)) => trend::run(trend::Trend::from_clap(&args)),
         ("serve", Some(args)) => serve::run(serve::Serve::from_clap(&args)),
         ("export", Some(args)) => export::run(export::Export::from_clap(&args)),
         ("", _) => {
@@ -15,10 +15,12 @@
  * be dual licensed as above, without any additional terms or conditions.
  * */
 //! #SPC-cli-ls
+use std::cmp;
 use std::io;
 
 use crate::dev_prelude::*;
 use artifact_data::*;
+use artifact_lib::query::Query;
 use termstyle::Color::*;
 use termstyle::{self, Color, El, Table, Text};
 
@@ -75,16 +77,45 @@ https://doc.rust-lang.org/regex/regex/index.html#syntax.\n\n    ")]
 
     #[structopt(short="s", long="spc", default_value=">0", help = "\
 Filter by spc (specification) completeness
-- `-s \"<45\"`: show only items with spc <= 45%.
-- `-s \">45\"`: show only items with spc >= 45%.
-- `-s \"<\"`  : show only items with spc <=  0%.
-- `-s \">\"`  : show only items with spc >=100%\n\n    ")]
+- `-s \"<45\"`   : show only items with spc <= 45%.
+- `-s \">45\"`   : show only items with spc >= 45%.
+- `-s \"<\"`     : show only items with spc <=  0%.
+- `-s \">\"`     : show only items with spc >=100%
+- `-s \"30..70\"`: show only items with 30% <= spc <= 70%.
+- `-s \"45\"`    : show only items with spc == 45%.\n\n    ")]
     pub spc: String,
 
     #[structopt(short="t", long="tst", default_value=">0")]
     /// Filter by tst (test) completeness. See `-s/--spc` for format.
     pub tst: String,
 
+    #[structopt(long="art-type", value_name="TYPES")]
+    /// Only show artifacts of the types, i.e. `SPC` or `REQ,TST`.
+    pub art_type: Option<String>,
+
+    #[structopt(short="q", long="query", value_name="QUERY", help = "\
+Only show artifacts matching the QUERY. For example:
+
+    art ls -q 'type:SPC and spc<50% and not impl:done'
+
+A QUERY combines FIELD:VALUE predicates with `and`, `or`, `not` and
+parentheses. Predicates without an operator between them are combined with
+`and`.
+
+- `name:`, `partof:`, `parts:`, `subname:` and `file:` match a glob, where
+  `*` matches anything except `/` and `**` matches anything.
+- `text:` matches text containing the value.
+- `impl:` is one of `done`, `code` or `none`.
+- `type:` is one of `REQ`, `SPC` or `TST`.
+- `spc` and `tst` are compared with `<`, `<=`, `>`, `>=` or `=`, i.e. `spc<0.5`
+  or `tst>=50%`.
+- `descendant-of:` and `ancestor-of:` select the artifacts which are
+  (recursively) part of or a parent of the matching artifacts.
+
+Use `~` instead of `:` to search with a regular expression, i.e.
+`partof~REQ-net`.\n\n    ")]
+    pub query: Option<String>,
+
     #[structopt(short="N", long="name")]
     /// \"name\" field: show the name of the artifact.
     pub name: bool,
@@ -121,9 +152,35 @@ Filter by spc (specification) completeness
     /// Do not display color in the output.
     pub plain: bool,
 
-    #[structopt(long="type", default_value="list")]
-    /// Type of output from [list, json]
-    pub output_ty: String,
+    #[structopt(long="sort", value_name="KEY", default_value="name")]
+    /// Sort the artifacts by one of [name, spc, tst, file, parts-count]
+    pub sort: String,
+
+    #[structopt(short="r", long="reverse")]
+    /// Reverse the sort order.
+    pub reverse: bool,
+
+    #[structopt(long="group-by", value_name="GROUP")]
+    /// Group the artifacts by one of [file, type, parent]
+    pub group_by: Option<String>,
+
+    #[structopt(long="columns", value_name="COLUMNS", help = "\
+Choose and order the columns of the table, csv and tsv output. This overrides
+the field flags (i.e. `-NP`) for those outputs.
+
+COLUMNS is a comma-separated list of [spc, tst, name, subnames, parts, partof,
+file, impl, text]. `text` can be given a width to truncate to, i.e. `text:60`.
+
+    art ls --columns name,spc,text:60\n\n    ")]
+    pub columns: Option<String>,
+
+    #[structopt(long="type", value_name="FORMAT")]
+    /// Deprecated, use `--format` (or `--art-type` to filter by artifact type).
+    pub ty: Option<String>,
+
+    #[structopt(long="format", default_value="list")]
+    /// Format of the output from [list, json, csv, tsv]
+    pub format: String,
 }
 
 /// Run the `art ls` command
@@ -136,50 +193,131 @@ pub fn run(cmd: Ls) -> Result<i32> {
 
     let (_, project) = read_project(repo)?;
     let display_flags = Flags::from_cmd(&cmd);
-    let mut filtered = filter_artifacts(&cmd, &project.artifacts)?;
-    filtered.sort();
-
-    let ty_ = OutputType::from_str(&cmd.output_ty)?;
-    if ty_ == OutputType::Json {
-        let artifacts: Vec<_> = filtered.iter().map(|n| project.artifacts.get(n)).collect();
-        write!(w, "{}", expect!(json::to_string_pretty(&artifacts)))?;
-        return Ok(0);
+    let columns = match cmd.columns {
+        Some(ref c) => parse_columns(c)?,
+        None => Column::from_flags(&display_flags),
+    };
+    let sort = SortKey::from_str(&cmd.sort)?;
+    let group_by = match cmd.group_by {
+        Some(ref g) => Some(GroupBy::from_str(g)?),
+        None => None,
+    };
+    let filtered = filter_artifacts(&cmd, &project.artifacts, &project.settings.base)?;
+    let sorted = sort_artifacts(filtered, &project.artifacts, sort, cmd.reverse);
+    let groups = group_artifacts(sorted, &project.artifacts, &project.settings.base, group_by);
+
+    let ty_ = output_type(&cmd)?;
+    match ty_ {
+        OutputType::Json => {
+            let value = if group_by.is_some() {
+                let grouped: IndexMap<&str, Vec<&Artifact>> = groups
+                    .iter()
+                    .map(|g| {
+                        (
+                            g.0.as_str(),
+                            g.1.iter().map(|n| &project.artifacts[n]).collect(),
+                        )
+                    })
+                    .collect();
+                json::to_string_pretty(&grouped)
+            } else {
+                let artifacts: Vec<_> = groups
+                    .iter()
+                    .flat_map(|g| g.1.iter())
+                    .map(|n| &project.artifacts[n])
+                    .collect();
+                json::to_string_pretty(&artifacts)
+            };
+            write!(w, "{}", expect!(value))?;
+            return Ok(0);
+        }
+        OutputType::Csv | OutputType::Tsv => {
+            display_separated(&mut w, ty_, &columns, &groups, &project, group_by.is_some())?;
+            return Ok(0);
+        }
+        OutputType::List => {}
     }
 
-    if cmd.long {
-        for name in filtered.iter() {
-            let art = &project.artifacts[name];
-            for el in &mut art.full_style(&project.artifacts, &display_flags) {
-                if cmd.plain {
-                    el.set_plain();
+    for (group, names) in groups.iter() {
+        if group_by.is_some() {
+            let mut header = El::Text(t!(format!("# {}\n", group)).bold());
+            if cmd.plain {
+                header.set_plain();
+            }
+            header.paint(&mut w)?;
+        }
+        if cmd.long {
+            for name in names.iter() {
+                let art = &project.artifacts[name];
+                for el in &mut art.full_style(&project.artifacts, &display_flags) {
+                    if cmd.plain {
+                        el.set_plain();
+                    }
+                    el.paint(&mut w)?;
                 }
-                el.paint(&mut w)?;
             }
+        } else {
+            display_table(
+                &mut w,
+                &cmd,
+                &columns,
+                names,
+                &project.artifacts,
+                &project.settings.base,
+            )?;
+        }
+        if group_by.is_some() && !cmd.long {
+            writeln!(w)?;
         }
-    } else {
-        display_table(&mut w, &cmd, &display_flags, &filtered, &project.artifacts)?;
     }
 
     Ok(0)
 }
 
-fn filter_artifacts(cmd: &Ls, artifacts: &IndexMap<Name, Artifact>) -> Result<IndexSet<Name>> {
+fn filter_artifacts(
+    cmd: &Ls,
+    artifacts: &IndexMap<Name, Artifact>,
+    base: &Path,
+) -> Result<IndexSet<Name>> {
     let fields = Flags::from_str(&cmd.fields)?;
     ensure!(!fields.impl_, "I/impl field not supported in search");
+    let spc = CompletedFilter::from_str(&cmd.spc)?;
+    let tst = CompletedFilter::from_str(&cmd.tst)?;
+    let types = match cmd.art_type {
+        Some(ref t) => parse_types(t)?,
+        None => IndexSet::new(),
+    };
     let re = match cmd.pattern {
-        Some(ref p) => {
-            if p.starts_with("(?") {
-                Regex::new(p)
-            } else {
-                // ignore case by default
-                Regex::new(&format!("(?i){}", p))
-            }?
-        }
-        None => return Ok(artifacts.keys().cloned().collect()),
+        Some(ref p) => Some(if p.starts_with("(?") {
+            Regex::new(p)
+        } else {
+            // ignore case by default
+            Regex::new(&format!("(?i){}", p))
+        }?),
+        None => None,
+    };
+    let queried = match cmd.query {
+        Some(ref q) => Some(Query::from_str(q)?.select(artifacts, base)),
+        None => None,
     };
     // return true if we should keep
     let filter_map = |(name, art): (&Name, &Artifact)| -> Option<Name> {
         debug_assert_eq!(name, &art.name);
+        if !types.is_empty() && !types.contains(&name.ty) {
+            return None;
+        }
+        if !spc.matches(art.completed.spc) || !tst.matches(art.completed.tst) {
+            return None;
+        }
+        if let Some(ref q) = queried {
+            if !q.contains(name) {
+                return None;
+            }
+        }
+        let re = match re {
+            Some(ref re) => re,
+            None => return Some(name.clone()),
+        };
         macro_rules! check { [$field:expr] => {{
             if !re.is_match($field) {
                 return None;
@@ -212,43 +350,124 @@ fn filter_artifacts(cmd: &Ls, artifacts: &IndexMap<Name, Artifact>) -> Result<In
     Ok(artifacts.iter().filter_map(filter_map).collect())
 }
 
+/// Get the output type, also accepting the old `--type list|json` form.
+fn output_type(cmd: &Ls) -> Result<OutputType> {
+    match cmd.ty {
+        Some(ref t) if t == "list" || t == "json" => {
+            warn!("`--type {}` is deprecated, use `--format {}`", t, t);
+            OutputType::from_str(t)
+        }
+        Some(ref t) => bail!(
+            "`--type` is the deprecated output format, use `--art-type {}` to filter \
+             by artifact type",
+            t
+        ),
+        None => OutputType::from_str(&cmd.format),
+    }
+}
+
+/// Parse a comma separated list of artifact types.
+fn parse_types(s: &str) -> Result<IndexSet<Type>> {
+    let mut out = IndexSet::new();
+    for t in s.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        out.insert(match t.to_ascii_uppercase().as_str() {
+            "REQ" => Type::REQ,
+            "SPC" => Type::SPC,
+            "TST" => Type::TST,
+            _ => bail!(
+                "Invalid artifact type {:?}, must be one of REQ, SPC or TST",
+                t
+            ),
+        });
+    }
+    Ok(out)
+}
+
+/// #SPC-cli-ls.filter
+/// A filter on the completion percentage, see `-s/--spc`.
+///
+/// Both ends are inclusive percentages.
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct CompletedFilter {
+    min: f32,
+    max: f32,
+}
+
+impl CompletedFilter {
+    fn from_str(s: &str) -> Result<CompletedFilter> {
+        let parse = |v: &str| -> Result<f32> {
+            let v = v.trim().trim_end_matches('%');
+            let out = match v.parse::<f32>() {
+                Ok(v) => v,
+                Err(_) => bail!("Invalid completion percentage {:?} in {:?}", v, s),
+            };
+            ensure!(
+                (0.0..=100.0).contains(&out),
+                "Completion percentage must be between 0 and 100: {:?}",
+                s
+            );
+            Ok(out)
+        };
+
+        // an empty bound uses the default
+        let bound = |v: &str, default: f32| {
+            if v.trim().is_empty() {
+                Ok(default)
+            } else {
+                parse(v)
+            }
+        };
+
+        let s = s.trim();
+        let (min, max) = if s.starts_with('<') {
+            (0.0, bound(&s[1..], 0.0)?)
+        } else if s.starts_with('>') {
+            (bound(&s[1..], 100.0)?, 100.0)
+        } else if let Some(i) = s.find("..") {
+            let min = bound(&s[..i], 0.0)?;
+            let max = bound(&s[i + 2..], 100.0)?;
+            ensure!(min <= max, "Invalid completion range {:?}", s);
+            (min, max)
+        } else {
+            let v = parse(s)?;
+            (v, v)
+        };
+        Ok(CompletedFilter { min: min, max: max })
+    }
+
+    /// Whether the completion ratio (0.0 - 1.0) is within the filter.
+    ///
+    /// Percentages are displayed with one decimal so they are compared with
+    /// the same precision.
+    fn matches(&self, ratio: f32) -> bool {
+        let percent = ratio * 100.0;
+        percent >= self.min - 0.05 && percent < self.max + 0.05
+    }
+}
+
 /// SPC-cli-ls.table
 fn display_table<W: IoWrite>(
     w: &mut W,
     cmd: &Ls,
-    display_flags: &Flags,
-    filtered: &IndexSet<Name>,
+    columns: &[Column],
+    filtered: &[Name],
     artifacts: &IndexMap<Name, Artifact>,
+    base: &Path,
 ) -> io::Result<()> {
-    let mut header = vec![vec![t!("spc%").bold()], vec![t!("tst%").bold()]];
-    if display_flags.name {
-        header.push(vec![t!(" | name").bold()]);
-    }
-    if display_flags.subnames {
-        header.push(vec![t!(" | subnames").bold()]);
-    }
-    if display_flags.parts {
-        header.push(vec![t!(" | parts").bold()]);
-    }
-    if display_flags.partof {
-        header.push(vec![t!(" | partof").bold()]);
-    }
-    if display_flags.file {
-        header.push(vec![t!(" | file").bold()]);
-    }
-    if display_flags.impl_ {
-        header.push(vec![t!(" | impl").bold()]);
-    }
-    if display_flags.text {
-        header.push(vec![t!(" | text").bold()]);
-    }
+    let header: Vec<Vec<Text>> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| {
+            vec![t!(format!("{}{}", Column::separator(columns, i), col.title())).bold()]
+        })
+        .collect();
     let mut rows = Vec::with_capacity(filtered.len() + 1);
     rows.push(header);
 
     rows.extend(
         filtered
             .iter()
-            .map(|name| artifacts[name].line_style(artifacts, &display_flags)),
+            .map(|name| artifacts[name].line_style(artifacts, columns, base)),
     );
     let mut table = El::Table(Table::new(rows));
     if cmd.plain {
@@ -257,10 +476,75 @@ fn display_table<W: IoWrite>(
     table.paint(w)
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// #SPC-cli-ls.separated
+///
+/// Display the artifacts as comma or tab separated values. If `grouped` then
+/// the first column is the group.
+fn display_separated<W: IoWrite>(
+    w: &mut W,
+    ty_: OutputType,
+    columns: &[Column],
+    groups: &[(String, Vec<Name>)],
+    project: &Project,
+    grouped: bool,
+) -> io::Result<()> {
+    let escape = |s: &str| -> String {
+        if ty_ == OutputType::Tsv {
+            escape_tsv(s)
+        } else {
+            escape_csv(s)
+        }
+    };
+    let sep = if ty_ == OutputType::Tsv { "\t" } else { "," };
+
+    let mut header: Vec<String> = Vec::with_capacity(columns.len() + 1);
+    if grouped {
+        header.push("group".into());
+    }
+    header.extend(columns.iter().map(|c| c.title().to_string()));
+    writeln!(w, "{}", header.join(sep))?;
+
+    for (group, names) in groups.iter() {
+        for name in names.iter() {
+            let art = &project.artifacts[name];
+            let mut row: Vec<String> = Vec::with_capacity(columns.len() + 1);
+            if grouped {
+                row.push(escape(group));
+            }
+            row.extend(
+                columns
+                    .iter()
+                    .map(|c| escape(&art.column_plain(c, &project.settings.base))),
+            );
+            writeln!(w, "{}", row.join(sep))?;
+        }
+    }
+    Ok(())
+}
+
+/// Quote a csv field if it contains any special characters (RFC 4180).
+pub(crate) fn escape_csv(s: &str) -> String {
+    if s.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Tsv fields cannot contain tabs or newlines, so escape them.
+fn escape_tsv(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum OutputType {
     List,
     Json,
+    Csv,
+    Tsv,
 }
 
 impl OutputType {
@@ -268,11 +552,230 @@ impl OutputType {
         Ok(match s {
             "list" => OutputType::List,
             "json" => OutputType::Json,
+            "csv" => OutputType::Csv,
+            "tsv" => OutputType::Tsv,
             _ => bail!("Invalid output type: {}", s),
         })
     }
 }
 
+/// #SPC-cli-ls.sort
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum SortKey {
+    Name,
+    Spc,
+    Tst,
+    File,
+    PartsCount,
+}
+
+impl SortKey {
+    fn from_str(s: &str) -> Result<SortKey> {
+        Ok(match s {
+            "name" => SortKey::Name,
+            "spc" => SortKey::Spc,
+            "tst" => SortKey::Tst,
+            "file" => SortKey::File,
+            "parts-count" => SortKey::PartsCount,
+            _ => bail!(
+                "Invalid sort key {:?}, must be one of name, spc, tst, file or parts-count",
+                s
+            ),
+        })
+    }
+}
+
+/// Sort the artifacts by the key, using the name to break ties.
+fn sort_artifacts(
+    names: IndexSet<Name>,
+    artifacts: &IndexMap<Name, Artifact>,
+    key: SortKey,
+    reverse: bool,
+) -> Vec<Name> {
+    let mut out: Vec<Name> = names.into_iter().collect();
+    out.sort_by(|a, b| {
+        let (art_a, art_b) = (&artifacts[a], &artifacts[b]);
+        let ord = match key {
+            SortKey::Name => cmp::Ordering::Equal,
+            SortKey::Spc => expect!(art_a.completed.spc.partial_cmp(&art_b.completed.spc)),
+            SortKey::Tst => expect!(art_a.completed.tst.partial_cmp(&art_b.completed.tst)),
+            SortKey::File => {
+                let (file_a, file_b): (&Path, &Path) = (&art_a.file, &art_b.file);
+                file_a.cmp(file_b)
+            }
+            SortKey::PartsCount => art_a.parts.len().cmp(&art_b.parts.len()),
+        };
+        ord.then_with(|| a.cmp(b))
+    });
+    if reverse {
+        out.reverse();
+    }
+    out
+}
+
+/// #SPC-cli-ls.group
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum GroupBy {
+    File,
+    Type,
+    Parent,
+}
+
+impl GroupBy {
+    fn from_str(s: &str) -> Result<GroupBy> {
+        Ok(match s {
+            "file" => GroupBy::File,
+            "type" => GroupBy::Type,
+            "parent" => GroupBy::Parent,
+            _ => bail!("Invalid group {:?}, must be one of file, type or parent", s),
+        })
+    }
+}
+
+/// Group the (sorted) artifacts, keeping their order within each group.
+///
+/// Groups are sorted by their key. With `GroupBy::Parent` an artifact is
+/// listed under every one of its parents, or under `(none)` if it has none.
+fn group_artifacts(
+    sorted: Vec<Name>,
+    artifacts: &IndexMap<Name, Artifact>,
+    base: &Path,
+    group_by: Option<GroupBy>,
+) -> Vec<(String, Vec<Name>)> {
+    let group_by = match group_by {
+        Some(g) => g,
+        None => return vec![(String::new(), sorted)],
+    };
+    let mut groups: IndexMap<String, Vec<Name>> = IndexMap::new();
+    for name in sorted {
+        let art = &artifacts[&name];
+        let keys = match group_by {
+            GroupBy::File => vec![relative_path(&art.file, base)],
+            GroupBy::Type => vec![name.ty.as_str().to_string()],
+            GroupBy::Parent if art.partof.is_empty() => vec!["(none)".to_string()],
+            GroupBy::Parent => art.partof.iter().map(|p| p.as_str().to_string()).collect(),
+        };
+        for key in keys {
+            groups
+                .entry(key)
+                .or_insert_with(Vec::new)
+                .push(name.clone());
+        }
+    }
+    groups.sort_keys();
+    groups.into_iter().collect()
+}
+
+/// #SPC-cli-ls.columns
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Column {
+    Spc,
+    Tst,
+    Name,
+    Subnames,
+    Parts,
+    Partof,
+    File,
+    Impl,
+    /// The text, optionally truncated to the width.
+    Text(Option<usize>),
+}
+
+/// The width `text` is truncated to in the table when no width is given.
+const TABLE_TEXT_WIDTH: usize = 30;
+
+impl Column {
+    fn from_str(s: &str) -> Result<Column> {
+        let s = s.trim();
+        if s.starts_with("text:") {
+            let width = &s["text:".len()..];
+            let width: usize = match width.parse() {
+                Ok(w) if w > 0 => w,
+                _ => bail!("Invalid text width {:?}, must be a positive integer", width),
+            };
+            return Ok(Column::Text(Some(width)));
+        }
+        Ok(match s {
+            "spc" => Column::Spc,
+            "tst" => Column::Tst,
+            "name" => Column::Name,
+            "subnames" => Column::Subnames,
+            "parts" => Column::Parts,
+            "partof" => Column::Partof,
+            "file" => Column::File,
+            "impl" => Column::Impl,
+            "text" => Column::Text(None),
+            _ => bail!(
+                "Invalid column {:?}, must be one of spc, tst, name, subnames, parts, partof, \
+                 file, impl or text[:WIDTH]",
+                s
+            ),
+        })
+    }
+
+    /// The default columns: the completion followed by the flags.
+    fn from_flags(flags: &Flags) -> Vec<Column> {
+        let mut out = vec![Column::Spc, Column::Tst];
+        if flags.name {
+            out.push(Column::Name);
+        }
+        if flags.subnames {
+            out.push(Column::Subnames);
+        }
+        if flags.parts {
+            out.push(Column::Parts);
+        }
+        if flags.partof {
+            out.push(Column::Partof);
+        }
+        if flags.file {
+            out.push(Column::File);
+        }
+        if flags.impl_ {
+            out.push(Column::Impl);
+        }
+        if flags.text {
+            out.push(Column::Text(None));
+        }
+        out
+    }
+
+    fn title(&self) -> &'static str {
+        match *self {
+            Column::Spc => "spc%",
+            Column::Tst => "tst%",
+            Column::Name => "name",
+            Column::Subnames => "subnames",
+            Column::Parts => "parts",
+            Column::Partof => "partof",
+            Column::File => "file",
+            Column::Impl => "impl",
+            Column::Text(_) => "text",
+        }
+    }
+
+    /// The separator to put before the column in the table. Neighboring
+    /// completion columns are not separated.
+    fn separator(columns: &[Column], index: usize) -> &'static str {
+        let is_completion = |c: &Column| *c == Column::Spc || *c == Column::Tst;
+        if index == 0 || (is_completion(&columns[index - 1]) && is_completion(&columns[index])) {
+            ""
+        } else {
+            " | "
+        }
+    }
+}
+
+fn parse_columns(s: &str) -> Result<Vec<Column>> {
+    let out = s
+        .split(',')
+        .filter(|c| !c.trim().is_empty())
+        .map(Column::from_str)
+        .collect::<Result<Vec<_>>>()?;
+    ensure!(!out.is_empty(), "Must specify at least one column");
+    Ok(out)
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 struct Flags {
     name: bool,
@@ -420,7 +923,14 @@ impl Flags {
 
 /// Faster `Text`
 trait ArtifactExt {
-    fn line_style(&self, artifacts: &IndexMap<Name, Artifact>, flags: &Flags) -> Vec<Vec<Text>>;
+    fn line_style(
+        &self,
+        artifacts: &IndexMap<Name, Artifact>,
+        columns: &[Column],
+        base: &Path,
+    ) -> Vec<Vec<Text>>;
+
+    fn column_plain(&self, column: &Column, base: &Path) -> String;
 
     fn full_style(&self, artifacts: &IndexMap<Name, Artifact>, flags: &Flags) -> Vec<El>;
 
@@ -430,49 +940,75 @@ trait ArtifactExt {
 }
 
 impl ArtifactExt for Artifact {
-    fn line_style(&self, artifacts: &IndexMap<Name, Artifact>, flags: &Flags) -> Vec<Vec<Text>> {
-        let mut out = Vec::with_capacity(flags.len() + 2);
-        macro_rules! cell { [$item:expr] => {{
-            let mut cell = $item;
-            cell.insert(0, t!(" | "));
-            out.push(cell);
-        }}};
-
-        out.push(vec![self.completed.spc_style()]);
-        out.push(vec![self.completed.tst_style()]);
-
-        if flags.name {
-            cell!(vec![self.name_style()])
-        }
-        if flags.subnames {
-            let mut styles = Vec::new();
-            for s in self.subnames.iter() {
-                styles.push(self.subname_style(s));
-                styles.push(t!(", "));
-            }
-            if !styles.is_empty() {
-                styles.pop(); // remove trailing comma
+    fn line_style(
+        &self,
+        artifacts: &IndexMap<Name, Artifact>,
+        columns: &[Column],
+        base: &Path,
+    ) -> Vec<Vec<Text>> {
+        let mut out = Vec::with_capacity(columns.len());
+        for (i, col) in columns.iter().enumerate() {
+            let mut cell = match *col {
+                Column::Spc => vec![self.completed.spc_style()],
+                Column::Tst => vec![self.completed.tst_style()],
+                Column::Name => vec![self.name_style()],
+                Column::Subnames => {
+                    let mut styles = Vec::new();
+                    for s in self.subnames.iter() {
+                        styles.push(self.subname_style(s));
+                        styles.push(t!(", "));
+                    }
+                    if !styles.is_empty() {
+                        styles.pop(); // remove trailing comma
+                    }
+                    styles
+                }
+                Column::Parts => lookup_name_styles(artifacts, &self.parts),
+                Column::Partof => lookup_name_styles(artifacts, &self.partof),
+                Column::File => vec![t!(relative_path(&self.file, base))],
+                Column::Impl => vec![t!(self.impl_.to_string())],
+                Column::Text(width) => {
+                    let width = width.unwrap_or(TABLE_TEXT_WIDTH);
+                    vec![t!(truncate(&self.text, width).replace("\n", "\\n"))]
+                }
+            };
+            let sep = Column::separator(columns, i);
+            if !sep.is_empty() {
+                cell.insert(0, t!(sep));
             }
-            cell!(styles);
-        }
-        if flags.parts {
-            cell!(lookup_name_styles(artifacts, &self.parts));
-        }
-        if flags.partof {
-            cell!(lookup_name_styles(artifacts, &self.partof));
-        }
-        if flags.file {
-            cell!(vec![t!(self.file.display().to_string())]);
-        }
-        if flags.impl_ {
-            cell!(vec![t!(self.impl_.to_string())]);
-        }
-        if flags.text {
-            cell!(vec![t!(truncate(&self.text, 30).replace("\n", "\\n"))]);
+            out.push(cell);
         }
         out
     }
 
+    /// The column without any styling, used for csv/tsv.
+    fn column_plain(&self, column: &Column, base: &Path) -> String {
+        let join = |names: &IndexSet<Name>| {
+            names
+                .iter()
+                .map(|n| n.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match *column {
+            Column::Spc => format!("{:.1}", self.completed.spc * 100.0),
+            Column::Tst => format!("{:.1}", self.completed.tst * 100.0),
+            Column::Name => self.name.as_str().to_string(),
+            Column::Subnames => self
+                .subnames
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            Column::Parts => join(&self.parts),
+            Column::Partof => join(&self.partof),
+            Column::File => relative_path(&self.file, base),
+            Column::Impl => self.impl_.to_string(),
+            Column::Text(Some(width)) => truncate(&self.text, width),
+            Column::Text(None) => self.text.clone(),
+        }
+    }
+
     fn full_style(&self, artifacts: &IndexMap<Name, Artifact>, flags: &Flags) -> Vec<El> {
         let mut out = Vec::new();
 
@@ -566,7 +1102,7 @@ fn lookup_name_styles(artifacts: &IndexMap<Name, Artifact>, names: &IndexSet<Nam
     out
 }
 
-trait CompletedExt {
+pub(crate) trait CompletedExt {
     fn spc_style(&self) -> Text;
     fn tst_style(&self) -> Text;
     fn name_color(&self) -> Color;
@@ -632,6 +1168,221 @@ fn test_flags_str() {
     assert!(Flags::from_str("").is_err());
 }
 
+#[test]
+fn test_completed_filter_str() {
+    let filter = |min, max| CompletedFilter { min: min, max: max };
+    macro_rules! from_str {
+        ($f:expr) => {{
+            expect!(CompletedFilter::from_str($f))
+        }};
+    }
+    assert_eq!(filter(0.0, 45.0), from_str!("<45"));
+    assert_eq!(filter(45.0, 100.0), from_str!(">45"));
+    assert_eq!(filter(0.0, 0.0), from_str!("<"));
+    assert_eq!(filter(100.0, 100.0), from_str!(">"));
+    assert_eq!(filter(0.0, 100.0), from_str!(">0"));
+    assert_eq!(filter(30.0, 70.0), from_str!("30..70"));
+    assert_eq!(filter(30.0, 100.0), from_str!("30.."));
+    assert_eq!(filter(0.0, 70.5), from_str!("..70.5"));
+    assert_eq!(filter(45.0, 45.0), from_str!("45"));
+    assert_eq!(filter(45.0, 45.0), from_str!("45%"));
+
+    assert!(CompletedFilter::from_str("").is_err());
+    assert!(CompletedFilter::from_str("foo").is_err());
+    assert!(CompletedFilter::from_str("<101").is_err());
+    assert!(CompletedFilter::from_str("70..30").is_err());
+
+    let f = from_str!("<50");
+    assert!(f.matches(0.0));
+    assert!(f.matches(0.5));
+    assert!(!f.matches(0.501));
+    let f = from_str!("33.4");
+    assert!(f.matches(0.33435234));
+    assert!(!f.matches(0.335));
+    let f = from_str!(">");
+    assert!(f.matches(1.0));
+    assert!(!f.matches(0.99));
+}
+
+#[test]
+fn test_types_str() {
+    assert_eq!(indexset! {Type::SPC}, expect!(parse_types("SPC")));
+    assert_eq!(
+        indexset! {Type::REQ, Type::TST},
+        expect!(parse_types("req, TST"))
+    );
+    assert!(parse_types("json").is_err());
+    assert!(parse_types("FOO").is_err());
+}
+
+#[test]
+fn test_filter_artifacts() {
+    let art = |name: &str, spc: f32, tst: f32| Artifact {
+        text: "some text".into(),
+        ..art(name, "/fake", &[], spc, tst)
+    };
+    let artifacts: IndexMap<Name, Artifact> = vec![
+        art("REQ-a", 1.0, 1.0),
+        art("SPC-a", 0.6, 0.2),
+        art("SPC-b", 0.3, 0.7),
+        art("TST-a", 0.0, 0.0),
+    ]
+    .into_iter()
+    .map(|a| (a.name.clone(), a))
+    .collect();
+
+    let filter = |args: &[&str]| -> Vec<String> {
+        let mut full = vec!["ls"];
+        full.extend(args);
+        let cmd = Ls::from_iter(full);
+        let mut out: Vec<String> = expect!(filter_artifacts(&cmd, &artifacts, Path::new("/")))
+            .iter()
+            .map(|n| n.as_str().to_string())
+            .collect();
+        out.sort();
+        out
+    };
+
+    assert_eq!(filter(&[]), vec!["REQ-a", "SPC-a", "SPC-b", "TST-a"]);
+    assert_eq!(filter(&["-s", "<50"]), vec!["SPC-b", "TST-a"]);
+    assert_eq!(filter(&["-t", "<50", "--art-type", "SPC"]), vec!["SPC-a"]);
+    assert_eq!(filter(&["-s", "30..70"]), vec!["SPC-a", "SPC-b"]);
+    assert_eq!(filter(&["-s", "100"]), vec!["REQ-a"]);
+    assert_eq!(filter(&["--art-type", "REQ,TST"]), vec!["REQ-a", "TST-a"]);
+    assert_eq!(filter(&["-s", ">50", "a"]), vec!["REQ-a", "SPC-a"]);
+    assert_eq!(filter(&["--type", "json"]).len(), 4);
+    assert_eq!(filter(&["-q", "type:SPC or tst>50%"]), vec!["REQ-a", "SPC-a", "SPC-b"]);
+    assert_eq!(filter(&["-q", "not name:*-a", "-s", "<50"]), vec!["SPC-b"]);
+}
+
+#[test]
+fn test_output_type() {
+    let output = |args: &[&str]| -> Result<OutputType> {
+        let mut full = vec!["ls"];
+        full.extend(args);
+        output_type(&Ls::from_iter(full))
+    };
+    assert_eq!(OutputType::List, expect!(output(&[])));
+    assert_eq!(OutputType::Csv, expect!(output(&["--format", "csv"])));
+    assert_eq!(OutputType::Json, expect!(output(&["--type", "json"])));
+    // the artifact types are filtered with `--art-type`
+    assert!(output(&["--type", "SPC"]).is_err());
+}
+
+#[test]
+fn test_columns_str() {
+    assert_eq!(
+        expect!(parse_columns("name,spc,text:60")),
+        vec![Column::Name, Column::Spc, Column::Text(Some(60))]
+    );
+    assert_eq!(
+        expect!(parse_columns("text, parts")),
+        vec![Column::Text(None), Column::Parts]
+    );
+    assert_eq!(
+        Column::from_flags(&Flags::default()),
+        vec![Column::Spc, Column::Tst, Column::Name, Column::Parts]
+    );
+    assert!(parse_columns("").is_err());
+    assert!(parse_columns("name,bad").is_err());
+    assert!(parse_columns("text:0").is_err());
+    assert!(parse_columns("text:abc").is_err());
+
+    let columns = [Column::Spc, Column::Tst, Column::Name, Column::Tst];
+    let seps: Vec<_> = (0..columns.len())
+        .map(|i| Column::separator(&columns, i))
+        .collect();
+    assert_eq!(seps, vec!["", "", " | ", " | "]);
+}
+
+#[test]
+fn test_sort_group() {
+    let art = |name: &str, file: &str, partof: &[&str], spc: f32| Artifact {
+        text: "some text".into(),
+        ..art(name, file, partof, spc, 0.0)
+    };
+    let mut artifacts: IndexMap<Name, Artifact> = vec![
+        art("REQ-a", "/repo/b.md", &[], 0.5),
+        art("SPC-a", "/repo/a.md", &["REQ-a"], 1.0),
+        art("SPC-b", "/repo/a.md", &["REQ-a", "SPC-a"], 0.0),
+        art("TST-a", "/repo/b.md", &["SPC-a"], 0.5),
+    ]
+    .into_iter()
+    .map(|a| (a.name.clone(), a))
+    .collect();
+    artifacts[&name!("REQ-a")].parts = indexset! {name!("SPC-a"), name!("SPC-b")};
+    artifacts[&name!("SPC-a")].parts = indexset! {name!("SPC-b"), name!("TST-a")};
+
+    let sort = |key: &str, reverse: bool| -> Vec<String> {
+        let names: IndexSet<Name> = artifacts.keys().cloned().collect();
+        sort_artifacts(names, &artifacts, expect!(SortKey::from_str(key)), reverse)
+            .iter()
+            .map(|n| n.as_str().to_string())
+            .collect()
+    };
+    assert_eq!(
+        sort("name", false),
+        vec!["REQ-a", "SPC-a", "SPC-b", "TST-a"]
+    );
+    assert_eq!(sort("name", true), vec!["TST-a", "SPC-b", "SPC-a", "REQ-a"]);
+    // ties are broken by the name
+    assert_eq!(sort("spc", false), vec!["SPC-b", "REQ-a", "TST-a", "SPC-a"]);
+    assert_eq!(sort("spc", true), vec!["SPC-a", "TST-a", "REQ-a", "SPC-b"]);
+    assert_eq!(
+        sort("file", false),
+        vec!["SPC-a", "SPC-b", "REQ-a", "TST-a"]
+    );
+    assert_eq!(
+        sort("parts-count", true),
+        vec!["SPC-a", "REQ-a", "TST-a", "SPC-b"]
+    );
+    assert!(SortKey::from_str("bad").is_err());
+
+    let group = |group_by: &str| -> Vec<(String, Vec<String>)> {
+        let sorted: Vec<Name> = artifacts.keys().cloned().collect();
+        let group_by = Some(expect!(GroupBy::from_str(group_by)));
+        group_artifacts(sorted, &artifacts, Path::new("/repo"), group_by)
+            .into_iter()
+            .map(|(g, names)| (g, names.iter().map(|n| n.as_str().to_string()).collect()))
+            .collect()
+    };
+    let strs = |g: &str, names: &[&str]| -> (String, Vec<String>) {
+        (g.to_string(), names.iter().map(|n| n.to_string()).collect())
+    };
+    assert_eq!(
+        group("file"),
+        vec![
+            strs("a.md", &["SPC-a", "SPC-b"]),
+            strs("b.md", &["REQ-a", "TST-a"]),
+        ]
+    );
+    assert_eq!(
+        group("type"),
+        vec![
+            strs("REQ", &["REQ-a"]),
+            strs("SPC", &["SPC-a", "SPC-b"]),
+            strs("TST", &["TST-a"]),
+        ]
+    );
+    assert_eq!(
+        group("parent"),
+        vec![
+            strs("(none)", &["REQ-a"]),
+            strs("REQ-a", &["SPC-a", "SPC-b"]),
+            strs("SPC-a", &["SPC-b", "TST-a"]),
+        ]
+    );
+    assert!(GroupBy::from_str("bad").is_err());
+}
+
+#[test]
+fn test_escape_separated() {
+    assert_eq!(escape_csv("plain"), "plain");
+    assert_eq!(escape_csv("a, b"), "\"a, b\"");
+    assert_eq!(escape_csv("say \"hi\"\nnow"), "\"say \"\"hi\"\"\nnow\"");
+    assert_eq!(escape_tsv("a\tb\nc\\d"), "a\\tb\\nc\\\\d");
+}
+
 #[test]
 fn test_style() {
     {
@@ -680,5 +1431,6 @@ fn test_style() {
         vec![t!(" | ")],
     ];
     let flags = Flags::default();
-    assert_eq!(expected, art.line_style(&artifacts, &flags));
+    let columns = Column::from_flags(&flags);
+    assert_eq!(expected, art.line_style(&artifacts, &columns, Path::new("/")));
 }
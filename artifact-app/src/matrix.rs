@@ -0,0 +1,427 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.matrix
+//! Export a traceability matrix of the requirements to their specifications,
+//! tests and code.
+
+use artifact_ser::markdown::strfmt_code_url;
+
+use crate::dev_prelude::*;
+use crate::ls::escape_csv;
+
+/// The format of the exported matrix.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum MatrixFormat {
+    Csv,
+    /// csv which spreadsheet applications open correctly: a UTF-8 byte order
+    /// mark, `\r\n` line endings and cells which cannot be read as formulas.
+    Xlsx,
+    Html,
+    Markdown,
+}
+
+impl MatrixFormat {
+    pub(crate) fn from_str(s: &str) -> Result<MatrixFormat> {
+        Ok(match s {
+            "csv" => MatrixFormat::Csv,
+            "xlsx-csv" => MatrixFormat::Xlsx,
+            "html" => MatrixFormat::Html,
+            "md" | "markdown" => MatrixFormat::Markdown,
+            _ => bail!(
+                "Invalid matrix format {:?}, must be one of csv, xlsx-csv, html or md",
+                s
+            ),
+        })
+    }
+}
+
+/// The direction to trace the artifacts in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Trace {
+    /// A row for every requirement, with the specifications, tests and code
+    /// which are (recursively) part of it.
+    Forward,
+    /// A row for every specification and test, with the requirements and
+    /// specifications it is (recursively) part of.
+    Backward,
+}
+
+/// A row of the matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MatrixRow {
+    pub name: Name,
+    pub reqs: Vec<Name>,
+    pub specs: Vec<Name>,
+    pub tests: Vec<Name>,
+    pub code: Vec<CodeRef>,
+    pub completed: Completed,
+}
+
+/// A reference to an artifact (or one of its subnames) in code.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CodeRef {
+    /// The file relative to the project base.
+    pub file: String,
+    pub line: u64,
+    /// The link to the code, if the `code_url` setting is set.
+    pub url: Option<String>,
+}
+
+impl CodeRef {
+    fn label(&self) -> String {
+        format!("{}[{}]", self.file, self.line)
+    }
+}
+
+/// A single cell of the matrix, which can contain several values.
+type Cell = Vec<(String, Option<String>)>;
+
+/// Build the rows of the matrix. Only artifacts in `selected` (if given)
+/// become rows, but the traced artifacts are not filtered.
+pub(crate) fn matrix_rows(
+    artifacts: &IndexMap<Name, Artifact>,
+    base: &Path,
+    code_url: Option<&str>,
+    trace: Trace,
+    selected: Option<&IndexSet<Name>>,
+) -> Result<Vec<MatrixRow>> {
+    let mut rows = Vec::new();
+    for art in artifacts.values() {
+        let is_row = match trace {
+            Trace::Forward => art.name.ty == Type::REQ,
+            Trace::Backward => art.name.ty != Type::REQ,
+        };
+        if !is_row || selected.map(|s| !s.contains(&art.name)).unwrap_or(false) {
+            continue;
+        }
+        let mut traced = match trace {
+            Trace::Forward => family(artifacts, &art.name, |a| &a.parts),
+            Trace::Backward => family(artifacts, &art.name, |a| &a.partof),
+        };
+        traced.sort();
+        let of_type =
+            |ty: Type| -> Vec<Name> { traced.iter().filter(|n| n.ty == ty).cloned().collect() };
+
+        // forward tracing collects the code of everything the requirement
+        // is made of, backward tracing only the artifact's own code.
+        let mut code = Vec::new();
+        let with_code = match trace {
+            Trace::Forward => traced.clone(),
+            Trace::Backward => vec![art.name.clone()],
+        };
+        for name in with_code.iter() {
+            if let Impl::Code(ref impl_code) = artifacts[name].impl_ {
+                let locs = impl_code.primary.iter().chain(impl_code.secondary.values());
+                for loc in locs {
+                    code.push(code_ref(loc, base, code_url)?);
+                }
+            }
+        }
+
+        rows.push(MatrixRow {
+            name: art.name.clone(),
+            reqs: of_type(Type::REQ),
+            specs: of_type(Type::SPC),
+            tests: of_type(Type::TST),
+            code: code,
+            completed: art.completed,
+        });
+    }
+    rows.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(rows)
+}
+
+/// All artifacts recursively reachable from `name` through `next`.
+fn family<F>(artifacts: &IndexMap<Name, Artifact>, name: &Name, next: F) -> Vec<Name>
+where
+    F: Fn(&Artifact) -> &IndexSet<Name>,
+{
+    let mut seen: IndexSet<Name> = IndexSet::new();
+    let mut todo = vec![name.clone()];
+    while let Some(n) = todo.pop() {
+        if let Some(art) = artifacts.get(&n) {
+            for other in next(art).iter() {
+                if other != name && seen.insert(other.clone()) {
+                    todo.push(other.clone());
+                }
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+fn code_ref(loc: &CodeLoc, base: &Path, code_url: Option<&str>) -> Result<CodeRef> {
+    let file = relative_path(&loc.file, base);
+    let url = match code_url {
+        Some(fmt) => match strfmt_code_url(fmt, &file, loc.line) {
+            Ok(url) => Some(url),
+            Err(e) => bail!("{}", e),
+        },
+        None => None,
+    };
+    Ok(CodeRef {
+        file: file,
+        line: loc.line,
+        url: url,
+    })
+}
+
+/// The titles and cells of the matrix.
+fn table(rows: &[MatrixRow], trace: Trace) -> (Vec<&'static str>, Vec<Vec<Cell>>) {
+    let names = |names: &[Name]| -> Cell {
+        names
+            .iter()
+            .map(|n| (n.as_str().to_string(), None))
+            .collect()
+    };
+    let percent = |ratio: f32| -> Cell { vec![(format!("{:.1}", ratio * 100.0), None)] };
+
+    let titles = match trace {
+        Trace::Forward => vec![
+            "requirement",
+            "specifications",
+            "tests",
+            "code",
+            "spc%",
+            "tst%",
+        ],
+        Trace::Backward => vec![
+            "artifact",
+            "requirements",
+            "specifications",
+            "code",
+            "spc%",
+            "tst%",
+        ],
+    };
+    let cells = rows
+        .iter()
+        .map(|row| {
+            let code = row
+                .code
+                .iter()
+                .map(|c| (c.label(), c.url.clone()))
+                .collect();
+            let traced = match trace {
+                Trace::Forward => vec![names(&row.specs), names(&row.tests)],
+                Trace::Backward => vec![names(&row.reqs), names(&row.specs)],
+            };
+            let mut out = vec![names(&[row.name.clone()])];
+            out.extend(traced);
+            out.push(code);
+            out.push(percent(row.completed.spc));
+            out.push(percent(row.completed.tst));
+            out
+        })
+        .collect();
+    (titles, cells)
+}
+
+/// Render the matrix in the format.
+pub(crate) fn render_matrix(rows: &[MatrixRow], trace: Trace, format: MatrixFormat) -> String {
+    let (titles, cells) = table(rows, trace);
+    match format {
+        MatrixFormat::Csv => render_csv(&titles, &cells, "; ", "\n", escape_csv),
+        MatrixFormat::Xlsx => {
+            let mut out = String::from("\u{feff}");
+            out.push_str(&render_csv(&titles, &cells, "\n", "\r\n", escape_xlsx));
+            out
+        }
+        MatrixFormat::Html => render_html(&titles, &cells),
+        MatrixFormat::Markdown => render_markdown(&titles, &cells),
+    }
+}
+
+/// In csv the url of a value is put in parentheses after it.
+fn cell_plain(cell: &Cell, join: &str) -> String {
+    let values: Vec<String> = cell
+        .iter()
+        .map(|(text, url)| match *url {
+            Some(ref url) => format!("{} ({})", text, url),
+            None => text.clone(),
+        })
+        .collect();
+    values.join(join)
+}
+
+fn render_csv(
+    titles: &[&str],
+    cells: &[Vec<Cell>],
+    join: &str,
+    newline: &str,
+    escape: fn(&str) -> String,
+) -> String {
+    let mut out = titles.join(",");
+    out.push_str(newline);
+    for row in cells.iter() {
+        let row: Vec<String> = row.iter().map(|c| escape(&cell_plain(c, join))).collect();
+        out.push_str(&row.join(","));
+        out.push_str(newline);
+    }
+    out
+}
+
+/// Escape a csv cell so that spreadsheets do not evaluate it as a formula.
+fn escape_xlsx(s: &str) -> String {
+    if s.starts_with(|c: char| c == '=' || c == '+' || c == '-' || c == '@') {
+        escape_csv(&format!("'{}", s))
+    } else {
+        escape_csv(s)
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(titles: &[&str], cells: &[Vec<Cell>]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Traceability Matrix</title>\n\
+         <style>table { border-collapse: collapse; } \
+         td, th { border: 1px solid #DCDEE2; padding: 4px; vertical-align: top; }</style>\n\
+         </head>\n<body>\n<table>\n<tr>",
+    );
+    for title in titles.iter() {
+        out.push_str(&format!("<th>{}</th>", escape_html(title)));
+    }
+    out.push_str("</tr>\n");
+    for row in cells.iter() {
+        out.push_str("<tr>");
+        for cell in row.iter() {
+            let values: Vec<String> = cell
+                .iter()
+                .map(|(text, url)| match *url {
+                    Some(ref url) => {
+                        format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(text))
+                    }
+                    None => escape_html(text),
+                })
+                .collect();
+            out.push_str(&format!("<td>{}</td>", values.join("<br>")));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+fn render_markdown(titles: &[&str], cells: &[Vec<Cell>]) -> String {
+    let mut out = format!("| {} |\n", titles.join(" | "));
+    let delims: Vec<&str> = titles.iter().map(|_| "---").collect();
+    out.push_str(&format!("| {} |\n", delims.join(" | ")));
+    for row in cells.iter() {
+        let row: Vec<String> = row
+            .iter()
+            .map(|cell| {
+                let values: Vec<String> = cell
+                    .iter()
+                    .map(|(text, url)| {
+                        let text = text.replace('|', "\\|");
+                        match *url {
+                            Some(ref url) => format!("[{}]({})", text, url),
+                            None => text,
+                        }
+                    })
+                    .collect();
+                values.join("<br>")
+            })
+            .collect();
+        out.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    out
+}
+
+#[cfg(test)]
+fn test_artifacts() -> IndexMap<Name, Artifact> {
+    let base = PathDir::new(env!("CARGO_MANIFEST_DIR")).unwrap();
+    let file = PathFile::new(base.join("src/matrix.rs")).unwrap();
+    let design = concat!(env!("CARGO_MANIFEST_DIR"), "/design/a.md");
+    let artifact = |name: &str, partof: &[&str], parts: &[&str]| Artifact {
+        parts: names(parts),
+        ..art(name, design, partof, 1.0, 0.5)
+    };
+    let mut out: IndexMap<Name, Artifact> = vec![
+        artifact("REQ-a", &[], &["SPC-a"]),
+        artifact("REQ-b", &[], &[]),
+        artifact("SPC-a", &["REQ-a"], &["TST-a"]),
+        artifact("TST-a", &["SPC-a"], &[]),
+    ]
+    .into_iter()
+    .map(|a| (a.name.clone(), a))
+    .collect();
+    out[&name!("SPC-a")].impl_ = Impl::Code(ImplCode {
+        primary: Some(CodeLoc::new(&file, 10)),
+        secondary: indexmap! {},
+    });
+    out
+}
+
+#[test]
+fn test_matrix() {
+    let artifacts = test_artifacts();
+    let base = PathDir::new(env!("CARGO_MANIFEST_DIR")).unwrap();
+    let url = Some("https://example.com/{file}#L{line}");
+
+    let rows = matrix_rows(&artifacts, &base, url, Trace::Forward, None).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].name, name!("REQ-a"));
+    assert_eq!(rows[0].specs, vec![name!("SPC-a")]);
+    assert_eq!(rows[0].tests, vec![name!("TST-a")]);
+    assert_eq!(
+        rows[0].code,
+        vec![CodeRef {
+            file: "src/matrix.rs".into(),
+            line: 10,
+            url: Some("https://example.com/src/matrix.rs#L11".into()),
+        }]
+    );
+    assert!(rows[1].specs.is_empty());
+
+    let selected = indexset! {name!("REQ-b")};
+    let rows = matrix_rows(&artifacts, &base, None, Trace::Forward, Some(&selected)).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(
+        render_matrix(&rows, Trace::Forward, MatrixFormat::Csv),
+        "requirement,specifications,tests,code,spc%,tst%\nREQ-b,,,,100.0,50.0\n"
+    );
+
+    let rows = matrix_rows(&artifacts, &base, None, Trace::Backward, None).unwrap();
+    assert_eq!(
+        render_matrix(&rows, Trace::Backward, MatrixFormat::Markdown),
+        "| artifact | requirements | specifications | code | spc% | tst% |\n\
+         | --- | --- | --- | --- | --- | --- |\n\
+         | SPC-a | REQ-a |  | src/matrix.rs[10] | 100.0 | 50.0 |\n\
+         | TST-a | REQ-a | SPC-a |  | 100.0 | 50.0 |\n"
+    );
+
+    let rows = matrix_rows(&artifacts, &base, url, Trace::Forward, None).unwrap();
+    let xlsx = render_matrix(&rows, Trace::Forward, MatrixFormat::Xlsx);
+    assert!(xlsx.starts_with("\u{feff}requirement,"));
+    assert!(xlsx.contains("src/matrix.rs[10] (https://example.com/src/matrix.rs#L11)"));
+    assert!(xlsx.ends_with("\r\n"));
+    let html = render_matrix(&rows, Trace::Forward, MatrixFormat::Html);
+    assert!(
+        html.contains("<a href=\"https://example.com/src/matrix.rs#L11\">src/matrix.rs[10]</a>")
+    );
+
+    assert_eq!(escape_xlsx("=SUM(A1)"), "'=SUM(A1)");
+    assert_eq!(escape_xlsx("a,b"), "\"a,b\"");
+}
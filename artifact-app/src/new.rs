@@ -0,0 +1,204 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.new
+//! Create a new artifact from the command line.
+
+use std::env;
+use std::io::{self, Read};
+use std::process::Command;
+
+use crate::dev_prelude::*;
+use crate::tempdir::TempDir;
+use artifact_data::*;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "new")]
+/// Create a new artifact.
+///
+/// The artifact is created through the same validation (and backups) as every
+/// other modification of the project.
+pub struct New {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "NAME")]
+    /// The name of the new artifact, i.e. `SPC-foo-bar`.
+    pub name: String,
+
+    #[structopt(short = "f", long = "file")]
+    /// The file to create the artifact in [default: the file of its first partof or parent]
+    pub file: Option<String>,
+
+    #[structopt(short = "p", long = "partof")]
+    /// Artifacts this is a part of, i.e. `REQ-x` or `REQ-[x, y]`. Can be given multiple times.
+    pub partof: Vec<String>,
+
+    #[structopt(short = "t", long = "text")]
+    /// The text of the artifact, `-` reads it from stdin [default: the template for its type]
+    pub text: Option<String>,
+
+    #[structopt(long = "done")]
+    /// Define the artifact as done, with this explanation.
+    pub done: Option<String>,
+
+    #[structopt(short = "e", long = "edit")]
+    /// Edit the text with `$EDITOR` before creating the artifact.
+    pub edit: bool,
+}
+
+/// Run the `art new` command
+pub fn run(cmd: New) -> Result<i32> {
+    set_log_verbosity!(cmd);
+    let work_dir = work_dir!(cmd);
+    let repo = find_repo(&work_dir)?;
+    info!("Running art-new in repo {}", repo.display());
+
+    let name = Name::from_str(&cmd.name)?;
+    let mut partof = IndexSet::new();
+    for p in cmd.partof.iter() {
+        partof.extend(Names::from_str(p)?.iter().cloned());
+    }
+
+    let (_, project) = read_project(repo.clone())?;
+    ensure!(
+        !project.artifacts.contains_key(&name),
+        "{} already exists in {}",
+        name,
+        project.artifacts[&name].file.display()
+    );
+
+    let file = match cmd.file {
+        Some(ref f) => {
+            let abs = PathAbs::new(work_dir.join(f))?;
+            let path: &Path = abs.as_ref();
+            PathSer::from(path)
+        }
+        None => match default_file(&project.artifacts, &name, &partof) {
+            Some(f) => f,
+            None => bail!(
+                "{} has no partof or parent artifact to take the file from, pass --file",
+                name
+            ),
+        },
+    };
+
+    let mut text = match cmd.text {
+        Some(ref t) if t == "-" => {
+            let mut t = String::new();
+            io::stdin().read_to_string(&mut t)?;
+            t
+        }
+        Some(ref t) => t.clone(),
+        None => template(&project.settings, &name),
+    };
+    if cmd.edit {
+        text = edit_text(&name, &text)?;
+    }
+
+    let artifact = ArtifactIm {
+        name: name.clone(),
+        file: file,
+        partof: partof,
+        done: cmd.done.clone(),
+        text: text,
+    };
+    let (lints, project) = modify_project(&repo, vec![ArtifactOp::Create { artifact }])?;
+    if !lints.is_empty() {
+        eprintln!("{}", lints);
+    }
+    let created = &project.artifacts[&name];
+    println!(
+        "Created {} in {}",
+        name,
+        relative_path(&created.file, &project.settings.base)
+    );
+    Ok(0)
+}
+
+/// The file of the first artifact in `partof`, or of the parent of `name`.
+fn default_file(
+    artifacts: &IndexMap<Name, Artifact>,
+    name: &Name,
+    partof: &IndexSet<Name>,
+) -> Option<PathSer> {
+    partof
+        .iter()
+        .cloned()
+        .chain(name.parent())
+        .filter_map(|n| artifacts.get(&n))
+        .map(|art| art.file.clone())
+        .next()
+}
+
+/// The template for the type of the artifact from the `[new.templates]` settings.
+fn template(settings: &Settings, name: &Name) -> String {
+    settings
+        .new
+        .templates
+        .get(name.ty.as_str())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Let the user edit the text with `$EDITOR` (or `$VISUAL`), returning the result.
+fn edit_text(name: &Name, text: &str) -> Result<String> {
+    let editor = match env::var("VISUAL").or_else(|_| env::var("EDITOR")) {
+        Ok(e) => e,
+        Err(_) => bail!("--edit requires the EDITOR environment variable to be set"),
+    };
+    let tmp = TempDir::new("art-new")?;
+    let path = tmp.path().join(format!("{}.md", name.as_str()));
+    let file = PathFile::create(&path)?;
+    file.write_str(text)?;
+
+    // the editor can contain arguments, i.e. `code --wait`
+    let mut args = editor.split_whitespace();
+    let program = expect!(args.next(), "EDITOR is empty");
+    let status = Command::new(program).args(args).arg(&path).status()?;
+    ensure!(status.success(), "{} exited with {}", editor, status);
+    Ok(file.read_string()?)
+}
+
+#[test]
+fn test_default_file() {
+    let artifacts: IndexMap<Name, Artifact> = vec![
+        art("REQ-a", "/design/a.md", &[], 0.0, 0.0),
+        art("SPC-foo", "/design/foo.md", &[], 0.0, 0.0),
+    ]
+    .into_iter()
+    .map(|a| (a.name.clone(), a))
+    .collect();
+
+    let partof = indexset! {name!("REQ-a")};
+    assert_eq!(
+        default_file(&artifacts, &name!("SPC-foo-bar"), &partof),
+        Some(PathSer::from("/design/a.md"))
+    );
+    assert_eq!(
+        default_file(&artifacts, &name!("SPC-foo-bar"), &indexset! {}),
+        Some(PathSer::from("/design/foo.md"))
+    );
+    assert_eq!(
+        default_file(&artifacts, &name!("SPC-other"), &indexset! {}),
+        None
+    );
+}
@@ -0,0 +1,56 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.recover
+//! Finish or undo a save which was interrupted.
+
+use crate::dev_prelude::*;
+use artifact_data::journal;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "recover")]
+/// Recover from a save which was interrupted (i.e. by a crash or power loss).
+///
+/// Every save is recorded in `.art/journal.json` while it is in flight. By
+/// default the save is finished, writing the new artifact files. Pass
+/// `--rollback` to restore the files from before the save instead.
+pub struct Recover {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(long = "rollback")]
+    /// Restore the original files instead of finishing the save.
+    pub rollback: bool,
+}
+
+/// Run the `art recover` command
+pub fn run(cmd: Recover) -> Result<i32> {
+    set_log_verbosity!(cmd);
+    let repo = find_repo(&work_dir!(cmd))?;
+    info!("Running art-recover in repo {}", repo.display());
+
+    match journal::recover(&repo, cmd.rollback)? {
+        None => println!("Nothing to recover"),
+        Some(journal::Recovered::Forward) => println!("Finished the interrupted save"),
+        Some(journal::Recovered::Back) => println!("Restored the files from before the save"),
+    }
+    Ok(0)
+}
@@ -31,7 +31,11 @@ use crate::frontend;
 use crate::serve;
 
 use crate::dev_prelude::*;
+use artifact_data::git;
+use artifact_data::merge;
+use artifact_data::undo::{self, UndoEntry, UndoHistory};
 use artifact_data::*;
+use artifact_lib::query::Query;
 
 // ----- SERVER -----
 
@@ -85,6 +89,7 @@ fn rpc_read_project(id: jrpc::Id, params: Option<json::Value>) -> jrpc::Response
         }
     }
 
+    super::remember_bases(&locked.project);
     jrpc::Response::success(id, json::to_value(locked).expect("serde"))
 }
 
@@ -120,6 +125,7 @@ fn handle_read_project_params(
         *project = ProjectResult {
             project: new_project,
             lints: lints,
+            changed: Vec::new(),
         };
     }
 
@@ -131,42 +137,279 @@ fn rpc_modify_project(id: jrpc::Id, params: Option<json::Value>) -> jrpc::Respon
     let mut locked = super::LOCKED.lock().unwrap();
     let locked = locked.as_mut().unwrap();
 
+    let ParamsModifyProject { ops, author } = match handle_modify_params(&id, params) {
+        Ok(p) => p,
+        Err(res) => return res,
+    };
+    if let Some(ref author) = author {
+        if let Err(err) = git::check_author(author) {
+            return jrpc::Response::error(
+                id,
+                jrpc::ErrorCode::InvalidParams,
+                err.to_string(),
+                None,
+            );
+        }
+    }
+
+    let ops = match rebase_locked(&id, locked, ops) {
+        Ok(ops) => ops,
+        Err(res) => return res,
+    };
+
+    // the in-memory project can be out of date with the files
+    let base = locked.project.settings.base.clone();
+    let timestamp = history::timestamp_now();
+    let entry = match UndoEntry::new(&locked.project, &ops, timestamp.clone()) {
+        Some(e) => Some(e),
+        None => read_project(&base)
+            .ok()
+            .and_then(|(_, project)| UndoEntry::new(&project, &ops, timestamp)),
+    };
+
+    if let Err(res) = modify_locked(&id, locked, ops, author.as_ref().map(String::as_str)) {
+        return res;
+    }
+    if let Some(entry) = entry {
+        let mut history = super::UNDO.lock().unwrap();
+        history.push(entry);
+        save_undo(&base, &history);
+    }
+
+    jrpc::Response::success(id, json::to_value(locked).expect("serde"))
+}
+
+/// Merge the operations on artifacts which changed since they were handed
+/// out with those changes.
+fn rebase_locked(
+    id: &jrpc::Id,
+    locked: &ProjectResult,
+    ops: Vec<ArtifactOp>,
+) -> ::std::result::Result<Vec<ArtifactOp>, jrpc::Response<json::Value>> {
+    if merge::is_current(&locked.project, &ops) {
+        return Ok(ops);
+    }
+    // a load error is reported by the modification itself
+    let project = match read_project(&locked.project.settings.base) {
+        Ok((_, p)) => p,
+        Err(_) => return Ok(ops),
+    };
+    let bases = super::BASES.lock().unwrap();
+    merge::rebase_ops(&project, &bases, ops).map_err(|conflicts| {
+        jrpc::Response::error(
+            id.clone(),
+            jrpc::ErrorCode::ServerError(-32000),
+            ModifyErrorKind::MergeConflict.to_string(),
+            Some(json::to_value(&conflicts).expect("serde")),
+        )
+    })
+}
+
+/// Check the operations against the in-memory project without saving.
+fn rpc_validate_modify(id: jrpc::Id, params: Option<json::Value>) -> jrpc::Response<json::Value> {
+    info!("ValidateModify");
+    let locked = super::LOCKED.lock().unwrap();
+    let locked = locked.as_ref().unwrap();
+
+    let params = match handle_modify_params(&id, params) {
+        Ok(p) => p,
+        Err(res) => return res,
+    };
+
+    let validation = validate_modify(&locked.project, params.ops);
+    jrpc::Response::success(id, json::to_value(&validation).expect("serde"))
+}
+
+/// The parameters of `ModifyProject` and `ValidateModify`.
+fn handle_modify_params(
+    id: &jrpc::Id,
+    params: Option<json::Value>,
+) -> ::std::result::Result<ParamsModifyProject, jrpc::Response<json::Value>> {
     let params = match params {
         Some(p) => p,
         None => {
-            return jrpc::Response::error(
-                id,
+            return Err(jrpc::Response::error(
+                id.clone(),
                 jrpc::ErrorCode::InvalidParams,
                 "No 'params'".to_string(),
                 None,
+            ));
+        }
+    };
+
+    // a plain list of operations has no author
+    let params = if params.is_array() {
+        json::from_value(params).map(|ops| ParamsModifyProject {
+            ops: ops,
+            author: None,
+        })
+    } else {
+        json::from_value(params)
+    };
+    params.map_err(|err| {
+        jrpc::Response::error(
+            id.clone(),
+            jrpc::ErrorCode::InvalidParams,
+            err.to_string(),
+            None,
+        )
+    })
+}
+
+/// Undo (or redo) the most recent modification.
+fn rpc_undo_redo(id: jrpc::Id, is_undo: bool) -> jrpc::Response<json::Value> {
+    info!("{}", if is_undo { "Undo" } else { "Redo" });
+    let mut locked = super::LOCKED.lock().unwrap();
+    let locked = locked.as_mut().unwrap();
+    let mut history = super::UNDO.lock().unwrap();
+
+    let entry = if is_undo {
+        history.undo.pop()
+    } else {
+        history.redo.pop()
+    };
+    let entry = match entry {
+        Some(e) => e,
+        None => {
+            return jrpc::Response::error(
+                id,
+                jrpc::ErrorCode::InvalidRequest,
+                format!("Nothing to {}", if is_undo { "undo" } else { "redo" }),
+                None,
             );
         }
     };
 
-    let ops: Vec<ArtifactOp> = match json::from_value(params) {
-        Ok(o) => o,
-        Err(err) => {
-            return jrpc::Response::error(id, jrpc::ErrorCode::InvalidParams, err.to_string(), None);
+    let ops = if is_undo {
+        entry.inverse.clone()
+    } else {
+        entry.ops.clone()
+    };
+    let result = modify_locked(&id, locked, ops, None);
+
+    // keep the entry where it was if it failed, i.e. if the files changed since
+    match (is_undo, result.is_ok()) {
+        (true, true) | (false, false) => history.redo.push(entry),
+        (false, true) | (true, false) => history.undo.push(entry),
+    }
+    if let Err(res) = result {
+        return res;
+    }
+    save_undo(&locked.project.settings.base, &history);
+
+    jrpc::Response::success(id, json::to_value(locked).expect("serde"))
+}
+
+fn rpc_list_history(id: jrpc::Id) -> jrpc::Response<json::Value> {
+    info!("ListHistory");
+    let history = super::UNDO.lock().unwrap();
+    jrpc::Response::success(id, json::to_value(&history.items()).expect("serde"))
+}
+
+/// Modify the project, replacing the locked project with the result.
+///
+/// With the `serve.git_commit` setting the changed files are also committed.
+fn modify_locked(
+    id: &jrpc::Id,
+    locked: &mut ProjectResult,
+    ops: Vec<ArtifactOp>,
+    author: Option<&str>,
+) -> ::std::result::Result<(), jrpc::Response<json::Value>> {
+    let base = locked.project.settings.base.clone();
+    let message = if locked.project.settings.serve.git_commit {
+        let files = git::modified_files(&locked.project, &ops);
+        if let Err(err) = git::ensure_clean(&base, &files) {
+            return Err(jrpc::Response::error(
+                id.clone(),
+                jrpc::ErrorCode::InvalidRequest,
+                err.to_string(),
+                None,
+            ));
         }
+        Some(git::commit_message(&ops))
+    } else {
+        None
     };
 
-    let (lints, project) = match modify_project(&locked.project.settings.base, ops) {
-        Ok(r) => r,
-        Err(err) => {
+    let (lints, project, changed) =
+        modify_project_changed(&base, ops).map_err(|err| err.into_response(id.clone()))?;
+    if let Some(message) = message {
+        // the files are already saved, the commit is best effort
+        if let Err(err) = git::commit(&base, &changed, &message, author) {
+            warn!("Could not commit the modification: {}", err);
+        }
+    }
+    *locked = ProjectResult {
+        project: project,
+        lints: lints,
+        changed: changed.iter().map(|p| PathSer::from(p.as_path())).collect(),
+    };
+    super::remember_bases(&locked.project);
+    Ok(())
+}
+
+/// Persisting the history is best effort, the modification already happened.
+fn save_undo(base: &PathDir, history: &UndoHistory) {
+    if let Err(err) = undo::save_undo(base, history) {
+        warn!("Could not save the undo history: {}", err);
+    }
+}
+
+trait ModifyErrorExt {
+    fn into_response(self, id: jrpc::Id) -> jrpc::Response<json::Value>;
+}
+
+impl ModifyErrorExt for ModifyError {
+    fn into_response(self, id: jrpc::Id) -> jrpc::Response<json::Value> {
+        jrpc::Response::error(
+            id,
+            jrpc::ErrorCode::ServerError(-32000),
+            self.kind.to_string(),
+            Some(json::to_value(&self.lints).unwrap()),
+        )
+    }
+}
+
+/// #SPC-query.rpc
+fn rpc_query_artifacts(id: jrpc::Id, params: Option<json::Value>) -> jrpc::Response<json::Value> {
+    info!("QueryArtifacts");
+    let locked = super::LOCKED.lock().unwrap();
+    let locked = locked.as_ref().unwrap();
+
+    let params: ParamsQueryArtifacts = match params.map(json::from_value) {
+        Some(Ok(p)) => p,
+        Some(Err(err)) => {
             return jrpc::Response::error(
                 id,
-                jrpc::ErrorCode::ServerError(-32000),
-                err.kind.to_string(),
-                Some(json::to_value(&err.lints).unwrap()),
+                jrpc::ErrorCode::InvalidParams,
+                err.to_string(),
+                None,
+            );
+        }
+        None => {
+            return jrpc::Response::error(
+                id,
+                jrpc::ErrorCode::InvalidParams,
+                "No 'params'".to_string(),
+                None,
             );
         }
     };
 
-    let result = ProjectResult { project, lints };
-    *locked = result;
-    let value = json::to_value(locked).expect("serde");
+    let query = match Query::from_str(&params.query) {
+        Ok(q) => q,
+        Err(err) => {
+            return jrpc::Response::error(
+                id,
+                jrpc::ErrorCode::InvalidParams,
+                err.to_string(),
+                None,
+            );
+        }
+    };
 
-    jrpc::Response::success(id, value)
+    let names = query.filter(&locked.project);
+    jrpc::Response::success(id, json::to_value(&names).expect("serde"))
 }
 
 // ----- HANDLE ENDPOINTS -----
@@ -200,6 +443,11 @@ fn handle_rpc<'a>(req: &mut Request<'_, '_>, mut res: Response<'a>) -> Middlewar
     let response = match request.method {
         Method::ReadProject => rpc_read_project(id, request.params),
         Method::ModifyProject => rpc_modify_project(id, request.params),
+        Method::QueryArtifacts => rpc_query_artifacts(id, request.params),
+        Method::Undo => rpc_undo_redo(id, true),
+        Method::Redo => rpc_undo_redo(id, false),
+        Method::ListHistory => rpc_list_history(id),
+        Method::ValidateModify => rpc_validate_modify(id, request.params),
     };
     let out = res.send(json::to_string(&response).unwrap());
     debug!("Exiting handle_rpc");
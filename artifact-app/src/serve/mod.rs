@@ -18,6 +18,7 @@
 use std::io;
 
 use crate::dev_prelude::*;
+use artifact_data::undo::{self, UndoHistory};
 use artifact_data::*;
 
 mod handler;
@@ -41,6 +42,30 @@ pub struct Serve {
 
 lazy_static! {
     static ref LOCKED: Mutex<Option<ProjectResult>> = Mutex::new(None);
+    /// Always locked _after_ `LOCKED`.
+    static ref UNDO: Mutex<UndoHistory> = Mutex::new(UndoHistory::default());
+    /// The artifacts handed out to clients by their id, to merge operations
+    /// made on out of date artifacts. Always locked _after_ `LOCKED`.
+    static ref BASES: Mutex<IndexMap<HashIm, ArtifactIm>> = Mutex::new(IndexMap::new());
+}
+
+/// The most artifacts kept in `BASES`, the oldest are dropped first.
+const MAX_BASES: usize = 10_000;
+
+/// Remember the artifacts of the project handed out to a client.
+fn remember_bases(project: &Project) {
+    let mut bases = BASES.lock().unwrap();
+    // artifacts handed out again move to the back, so that the ones which
+    // were not seen for the longest time are dropped first
+    let ids: IndexSet<HashIm> = project.artifacts.values().map(|a| a.id).collect();
+    bases.retain(|id, _| !ids.contains(id));
+    for art in project.artifacts.values() {
+        bases.insert(art.id, ArtifactIm::from(art.clone()));
+    }
+    if bases.len() > MAX_BASES {
+        let extra = bases.len() - MAX_BASES;
+        *bases = bases.drain(..).skip(extra).collect();
+    }
 }
 
 /// Run the `art serve` command
@@ -51,10 +76,20 @@ pub fn run(cmd: Serve) -> Result<i32> {
 
     let (lints, project) = read_project(repo)?;
     {
+        let history = match undo::load_undo(&project.settings.base) {
+            Ok(h) => h,
+            Err(err) => {
+                warn!("Starting with an empty undo history: {}", err);
+                UndoHistory::default()
+            }
+        };
+        *UNDO.lock().unwrap() = history;
+        remember_bases(&project);
         let mut locked = LOCKED.lock().unwrap();
         *locked = Some(ProjectResult {
             project: project,
             lints: lints,
+            changed: Vec::new(),
         });
     }
 
@@ -0,0 +1,69 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.snapshot
+//! Record the completion of the project in its history.
+
+use crate::dev_prelude::*;
+use artifact_data::git;
+use artifact_data::history;
+use artifact_data::*;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "snapshot")]
+/// Record the current completion of the project in `.art/history.jsonl`.
+///
+/// Use `art trend` to see how the completion evolved over the snapshots.
+pub struct Snapshot {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(long = "commit")]
+    /// The git commit to record with the snapshot.
+    pub commit: Option<String>,
+
+    #[structopt(long = "git")]
+    /// Record the current git commit (`git rev-parse HEAD`) with the snapshot.
+    pub git: bool,
+}
+
+/// Run the `art snapshot` command
+pub fn run(cmd: Snapshot) -> Result<i32> {
+    set_log_verbosity!(cmd);
+    let repo = find_repo(&work_dir!(cmd))?;
+    info!("Running art-snapshot in repo {}", repo.display());
+
+    let commit = match cmd.commit {
+        Some(c) => Some(c),
+        None if cmd.git => Some(git::head(&repo)?),
+        None => None,
+    };
+    let (lints, project) = read_project(repo)?;
+    let snapshot = history::Snapshot::new(&project, &lints, history::timestamp_now(), commit);
+    history::append_snapshot(&project.settings.base, &snapshot)?;
+    println!(
+        "Recorded snapshot of {} artifacts: spc={:.1}% tst={:.1}%",
+        snapshot.totals.artifacts,
+        snapshot.totals.spc * 100.0,
+        snapshot.totals.tst * 100.0,
+    );
+    Ok(0)
+}
@@ -0,0 +1,528 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.stats
+//! Summarize the completion, implementation and lints of the whole project.
+
+use std::io;
+
+use crate::dev_prelude::*;
+use crate::ls::CompletedExt;
+use artifact_data::history;
+use artifact_data::*;
+use termstyle::{El, Text};
+
+macro_rules! t { [$t:expr] => {{
+    Text::new($t.into())
+}}}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "stats")]
+/// Summarize the whole project: completion, implementation, files and lints.
+pub struct Stats {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(short = "n", long = "top", default_value = "5")]
+    /// The number of least complete subtrees and largest files to display.
+    pub top: usize,
+
+    #[structopt(long = "format", default_value = "human")]
+    /// Format of the output from [human, json]
+    pub format: String,
+
+    #[structopt(long = "plain")]
+    /// Do not display color in the output.
+    pub plain: bool,
+}
+
+/// Run the `art stats` command
+pub fn run(cmd: Stats) -> Result<i32> {
+    let mut w = io::stdout();
+
+    set_log_verbosity!(cmd);
+    let repo = find_repo(&work_dir!(cmd))?;
+    info!("Running art-stats in repo {}", repo.display());
+    let format = StatsFormat::from_str(&cmd.format)?;
+
+    let (lints, project) = read_project(repo)?;
+    let stats = project_stats(
+        &project.artifacts,
+        &project.code_impls,
+        &project.settings.base,
+        &lints,
+        cmd.top,
+    );
+    match format {
+        StatsFormat::Human => {
+            for mut el in stats.human_style(&project.artifacts) {
+                if cmd.plain {
+                    el.set_plain();
+                }
+                el.paint(&mut w)?;
+            }
+        }
+        StatsFormat::Json => write!(w, "{}", expect!(json::to_string_pretty(&stats.to_json())))?,
+    }
+    Ok(0)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum StatsFormat {
+    Human,
+    Json,
+}
+
+impl StatsFormat {
+    fn from_str(s: &str) -> Result<StatsFormat> {
+        Ok(match s {
+            "human" => StatsFormat::Human,
+            "json" => StatsFormat::Json,
+            _ => bail!("Invalid format {:?}, must be one of human or json", s),
+        })
+    }
+}
+
+/// The statistics of a project.
+#[derive(Debug, PartialEq)]
+struct ProjectStats {
+    artifacts: usize,
+    types: Vec<(Type, usize)>,
+    /// The mean completion of all artifacts.
+    completed: Completed,
+    /// The completion of the artifacts without a `partof`.
+    roots: Vec<(Name, Completed)>,
+    impl_done: usize,
+    impl_code: usize,
+    impl_none: usize,
+    subnames: usize,
+    subnames_implemented: usize,
+    /// The number of code references in each source file.
+    code_refs: Vec<(String, usize)>,
+    /// `(name, completed, descendants)` of the least complete artifacts with parts.
+    least_complete: Vec<(Name, Completed, usize)>,
+    /// The files with the most artifacts.
+    files: Vec<(String, usize)>,
+    /// The number of lints in each category.
+    lints: Vec<(String, usize)>,
+}
+
+/// Compute the statistics of the project. `top` limits the least complete
+/// subtrees and the largest files.
+fn project_stats(
+    artifacts: &IndexMap<Name, Artifact>,
+    code_impls: &IndexMap<Name, ImplCode>,
+    base: &Path,
+    lints: &lint::Categorized,
+    top: usize,
+) -> ProjectStats {
+    let types = [Type::REQ, Type::SPC, Type::TST]
+        .iter()
+        .map(|ty| (*ty, artifacts.keys().filter(|n| n.ty == *ty).count()))
+        .collect();
+
+    let (spc, tst) = history::mean_completed(artifacts.values().map(|a| &a.completed));
+
+    let mut roots: Vec<(Name, Completed)> = artifacts
+        .values()
+        .filter(|a| a.partof.is_empty())
+        .map(|a| (a.name.clone(), a.completed))
+        .collect();
+    roots.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let (mut impl_done, mut impl_code, mut impl_none) = (0, 0, 0);
+    let (mut subnames, mut subnames_implemented) = (0, 0);
+    for art in artifacts.values() {
+        subnames += art.subnames.len();
+        match art.impl_ {
+            Impl::Done(_) => impl_done += 1,
+            Impl::Code(ref code) => {
+                impl_code += 1;
+                subnames_implemented += art
+                    .subnames
+                    .iter()
+                    .filter(|s| code.secondary.contains_key(*s))
+                    .count();
+            }
+            Impl::NotImpl => impl_none += 1,
+        }
+    }
+
+    let mut code_refs = IndexMap::new();
+    for code in code_impls.values() {
+        let locs = code.primary.iter().chain(code.secondary.values());
+        for loc in locs {
+            *code_refs.entry(relative_path(&loc.file, base)).or_insert(0) += 1;
+        }
+    }
+
+    let mut least_complete: Vec<(Name, Completed, usize)> = artifacts
+        .values()
+        .filter(|a| !a.parts.is_empty())
+        .map(|a| {
+            (
+                a.name.clone(),
+                a.completed,
+                count_descendants(artifacts, &a.name),
+            )
+        })
+        .collect();
+    least_complete.sort_by(|a, b| {
+        let (ca, cb) = (a.1.spc + a.1.tst, b.1.spc + b.1.tst);
+        expect!(ca.partial_cmp(&cb)).then_with(|| a.0.cmp(&b.0))
+    });
+    least_complete.truncate(top);
+
+    let mut files = IndexMap::new();
+    for art in artifacts.values() {
+        *files.entry(relative_path(&art.file, base)).or_insert(0) += 1;
+    }
+    let mut files = sorted_counts(files);
+    files.truncate(top);
+
+    let mut lint_counts = IndexMap::new();
+    for lint in lints.error.iter().chain(lints.other.iter()) {
+        *lint_counts
+            .entry(format!("{:?}", lint.category))
+            .or_insert(0) += 1;
+    }
+
+    ProjectStats {
+        artifacts: artifacts.len(),
+        types: types,
+        completed: Completed { spc: spc, tst: tst },
+        roots: roots,
+        impl_done: impl_done,
+        impl_code: impl_code,
+        impl_none: impl_none,
+        subnames: subnames,
+        subnames_implemented: subnames_implemented,
+        code_refs: sorted_counts(code_refs),
+        least_complete: least_complete,
+        files: files,
+        lints: sorted_counts(lint_counts),
+    }
+}
+
+/// The number of artifacts below `name` in the `parts` hierarchy.
+fn count_descendants(artifacts: &IndexMap<Name, Artifact>, name: &Name) -> usize {
+    let mut seen: IndexSet<&Name> = IndexSet::new();
+    let mut todo = vec![name];
+    while let Some(n) = todo.pop() {
+        if let Some(art) = artifacts.get(n) {
+            for part in art.parts.iter() {
+                if part != name && seen.insert(part) {
+                    todo.push(part);
+                }
+            }
+        }
+    }
+    seen.len()
+}
+
+/// Sort the counts from largest to smallest, ties by key.
+fn sorted_counts(counts: IndexMap<String, usize>) -> Vec<(String, usize)> {
+    let mut out: Vec<(String, usize)> = counts.into_iter().collect();
+    out.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    out
+}
+
+impl ProjectStats {
+    /// #SPC-cli.stats_human
+    fn human_style(&self, artifacts: &IndexMap<Name, Artifact>) -> Vec<El> {
+        let mut out = Vec::new();
+        macro_rules! line { [ $( $x:expr ),* ] => {{
+            $( out.push(El::Text($x)); )*
+            out.push(El::Text(t!("\n")));
+        }}}
+        macro_rules! header { [ $x:expr ] => {{
+            line![];
+            line![t!($x).bold()];
+        }}}
+        let name_style = |name: &Name| match artifacts.get(name) {
+            Some(art) => t!(name.as_str()).color(art.completed.name_color()),
+            None => t!(name.as_str()),
+        };
+
+        // right align the completion columns
+        let pad = |ratio: f32| {
+            let width = format!("{:.1}", ratio * 100.0).len();
+            t!(" ".repeat(5usize.saturating_sub(width)))
+        };
+
+        let types: Vec<String> = self
+            .types
+            .iter()
+            .map(|(ty, count)| format!("{} {}", ty.as_str(), count))
+            .collect();
+        line![
+            t!("Artifacts: ").bold(),
+            t!(format!("{} ({})", self.artifacts, types.join(", ")))
+        ];
+        line![
+            t!("Completion: ").bold(),
+            t!("spc "),
+            self.completed.spc_style(),
+            t!("%  tst "),
+            self.completed.tst_style(),
+            t!("%")
+        ];
+        line![
+            t!("Implemented: ").bold(),
+            t!(format!(
+                "{} done, {} in code, {} not implemented",
+                self.impl_done, self.impl_code, self.impl_none
+            ))
+        ];
+        let ratio = if self.subnames == 0 {
+            0.0
+        } else {
+            self.subnames_implemented as f32 / self.subnames as f32
+        };
+        line![
+            t!("Subnames: ").bold(),
+            t!(format!(
+                "{} of {} implemented ({})",
+                self.subnames_implemented,
+                self.subnames,
+                percent(ratio)
+            ))
+        ];
+
+        header!("Roots:");
+        line![t!(format!("  {:>5} {:>5}  name", "spc%", "tst%")).italic()];
+        for (name, completed) in self.roots.iter() {
+            line![
+                t!("  "),
+                pad(completed.spc),
+                completed.spc_style(),
+                t!(" "),
+                pad(completed.tst),
+                completed.tst_style(),
+                t!("  "),
+                name_style(name)
+            ];
+        }
+
+        header!("Least complete subtrees:");
+        line![t!(format!(
+            "  {:>5} {:>5} {:>5}  name",
+            "spc%", "tst%", "parts"
+        ))
+        .italic()];
+        for (name, completed, descendants) in self.least_complete.iter() {
+            line![
+                t!("  "),
+                pad(completed.spc),
+                completed.spc_style(),
+                t!(" "),
+                pad(completed.tst),
+                completed.tst_style(),
+                t!(format!(" {:>5}  ", descendants)),
+                name_style(name)
+            ];
+        }
+
+        for (title, counts) in [
+            ("Files with the most artifacts:", &self.files),
+            ("Code references per file:", &self.code_refs),
+            ("Lints per category:", &self.lints),
+        ]
+        .iter()
+        {
+            header!(*title);
+            if counts.is_empty() {
+                line![t!("  (none)")];
+            }
+            for (key, count) in counts.iter() {
+                line![t!(format!("  {:>5}  {}", count, key))];
+            }
+        }
+        out
+    }
+
+    fn to_json(&self) -> json::Value {
+        let completed = |c: &Completed| {
+            let mut obj = json::Map::new();
+            obj.insert("spc".into(), json::Value::from(c.spc));
+            obj.insert("tst".into(), json::Value::from(c.tst));
+            obj
+        };
+        let counts = |counts: &[(String, usize)]| {
+            let obj: json::Map<String, json::Value> = counts
+                .iter()
+                .map(|(k, v)| (k.clone(), json::Value::from(*v)))
+                .collect();
+            json::Value::Object(obj)
+        };
+
+        let mut out = json::Map::new();
+        out.insert("artifacts".into(), json::Value::from(self.artifacts));
+        let types: json::Map<String, json::Value> = self
+            .types
+            .iter()
+            .map(|(ty, count)| (ty.as_str().to_string(), json::Value::from(*count)))
+            .collect();
+        out.insert("types".into(), json::Value::Object(types));
+        out.insert(
+            "completed".into(),
+            json::Value::Object(completed(&self.completed)),
+        );
+        let roots: json::Map<String, json::Value> = self
+            .roots
+            .iter()
+            .map(|(n, c)| (n.as_str().to_string(), json::Value::Object(completed(c))))
+            .collect();
+        out.insert("roots".into(), json::Value::Object(roots));
+
+        let mut impls = json::Map::new();
+        impls.insert("done".into(), json::Value::from(self.impl_done));
+        impls.insert("code".into(), json::Value::from(self.impl_code));
+        impls.insert("none".into(), json::Value::from(self.impl_none));
+        out.insert("impl".into(), json::Value::Object(impls));
+
+        let mut subnames = json::Map::new();
+        subnames.insert("total".into(), json::Value::from(self.subnames));
+        subnames.insert(
+            "implemented".into(),
+            json::Value::from(self.subnames_implemented),
+        );
+        out.insert("subnames".into(), json::Value::Object(subnames));
+
+        out.insert("code_refs".into(), counts(&self.code_refs));
+        let least: Vec<json::Value> = self
+            .least_complete
+            .iter()
+            .map(|(n, c, descendants)| {
+                let mut obj = completed(c);
+                obj.insert("name".into(), json::Value::from(n.as_str()));
+                obj.insert("descendants".into(), json::Value::from(*descendants));
+                json::Value::Object(obj)
+            })
+            .collect();
+        out.insert("least_complete".into(), json::Value::from(least));
+        out.insert("files".into(), counts(&self.files));
+        out.insert("lints".into(), counts(&self.lints));
+        json::Value::Object(out)
+    }
+}
+
+#[cfg(test)]
+fn test_artifact(name: &str, partof: &[&str], parts: &[&str], spc: f32, tst: f32) -> Artifact {
+    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/design/a.md");
+    Artifact {
+        parts: names(parts),
+        ..art(name, file, partof, spc, tst)
+    }
+}
+
+#[test]
+fn test_project_stats() {
+    let base = PathDir::new(env!("CARGO_MANIFEST_DIR")).unwrap();
+    let stats_rs = PathFile::new(base.join("src/stats.rs")).unwrap();
+    let ls_rs = PathFile::new(base.join("src/ls.rs")).unwrap();
+    let code = ImplCode {
+        primary: Some(CodeLoc::new(&stats_rs, 1)),
+        secondary: indexmap! {
+            subname!(".x") => CodeLoc::new(&ls_rs, 2),
+        },
+    };
+
+    let mut artifacts: IndexMap<Name, Artifact> = vec![
+        test_artifact("REQ-a", &[], &["SPC-a"], 0.5, 0.25),
+        test_artifact("REQ-b", &[], &[], 0.0, 0.0),
+        test_artifact("SPC-a", &["REQ-a"], &["TST-a"], 1.0, 0.5),
+        test_artifact("TST-a", &["SPC-a"], &[], 1.0, 1.0),
+    ]
+    .into_iter()
+    .map(|a| (a.name.clone(), a))
+    .collect();
+    {
+        let spc = &mut artifacts[&name!("SPC-a")];
+        spc.impl_ = Impl::Code(code.clone());
+        spc.subnames = indexset! {subname!(".x"), subname!(".y")};
+    }
+    artifacts[&name!("TST-a")].impl_ = Impl::Done("done".into());
+    artifacts[&name!("REQ-b")].file = PathSer::from(concat!(env!("CARGO_MANIFEST_DIR"), "/b.md"));
+    let code_impls = indexmap! {name!("SPC-a") => code};
+
+    let lints = lint::Categorized {
+        error: vec![],
+        other: vec![lint::Lint {
+            level: lint::Level::Warn,
+            path: None,
+            line: None,
+            category: lint::Category::Subname,
+            msg: "unused".into(),
+        }],
+    };
+
+    let stats = project_stats(&artifacts, &code_impls, &base, &lints, 1);
+    assert_eq!(stats.artifacts, 4);
+    assert_eq!(
+        stats.types,
+        vec![(Type::REQ, 2), (Type::SPC, 1), (Type::TST, 1)]
+    );
+    assert_eq!(
+        stats.completed,
+        Completed {
+            spc: 0.625,
+            tst: 0.438
+        }
+    );
+    assert_eq!(
+        stats
+            .roots
+            .iter()
+            .map(|(n, _)| n.clone())
+            .collect::<Vec<_>>(),
+        vec![name!("REQ-a"), name!("REQ-b")]
+    );
+    assert_eq!(
+        (stats.impl_done, stats.impl_code, stats.impl_none),
+        (1, 1, 2)
+    );
+    assert_eq!((stats.subnames, stats.subnames_implemented), (2, 1));
+    assert_eq!(
+        stats.code_refs,
+        vec![
+            ("src/ls.rs".to_string(), 1),
+            ("src/stats.rs".to_string(), 1)
+        ]
+    );
+    assert_eq!(
+        stats.least_complete,
+        vec![(
+            name!("REQ-a"),
+            Completed {
+                spc: 0.5,
+                tst: 0.25
+            },
+            2
+        )]
+    );
+    assert_eq!(stats.files, vec![("design/a.md".to_string(), 3)]);
+    assert_eq!(stats.lints, vec![("Subname".to_string(), 1)]);
+
+    let value = stats.to_json();
+    assert_eq!(value["impl"]["code"], json::Value::from(1));
+    assert_eq!(value["roots"]["REQ-b"]["spc"], json::Value::from(0.0));
+}
@@ -0,0 +1,321 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.tree
+//! Display the `partof` hierarchy of artifacts as a tree.
+
+use std::io;
+
+use crate::dev_prelude::*;
+use crate::ls::CompletedExt;
+use artifact_data::graph::{self, GraphId, Graphs};
+use artifact_data::*;
+use termstyle::{El, Text};
+
+macro_rules! t { [$t:expr] => {{
+    Text::new($t.into())
+}}}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "tree")]
+/// Display the artifacts as a tree of their parts.
+pub struct Tree {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "ROOT")]
+    /// The artifact to use as the root of the tree [default: all artifacts without a partof]
+    pub root: Option<String>,
+
+    #[structopt(short = "d", long = "depth")]
+    /// Only display parts up to this depth below the root(s).
+    pub depth: Option<usize>,
+
+    #[structopt(short = "c", long = "collapse")]
+    /// Do not display the parts of artifacts which are 100% specified and tested.
+    pub collapse: bool,
+
+    #[structopt(long = "plain")]
+    /// Do not display color in the output.
+    pub plain: bool,
+}
+
+/// Run the `art tree` command
+pub fn run(cmd: Tree) -> Result<i32> {
+    let mut w = io::stdout();
+
+    set_log_verbosity!(cmd);
+    let repo = find_repo(&work_dir!(cmd))?;
+    info!("Running art-tree in repo {}", repo.display());
+
+    let (_, project) = read_project(repo)?;
+    let root = match cmd.root {
+        Some(ref r) => {
+            let name = Name::from_str(r)?;
+            ensure!(
+                project.artifacts.contains_key(&name),
+                "{} does not exist",
+                name.as_str()
+            );
+            Some(name)
+        }
+        None => None,
+    };
+
+    for mut el in tree_style(&project.artifacts, root.as_ref(), &cmd) {
+        if cmd.plain {
+            el.set_plain();
+        }
+        el.paint(&mut w)?;
+    }
+    Ok(0)
+}
+
+/// Render the tree starting at `root`, or at every artifact without a
+/// `partof` if there is no root.
+fn tree_style(artifacts: &IndexMap<Name, Artifact>, root: Option<&Name>, cmd: &Tree) -> Vec<El> {
+    let partofs: IndexMap<Name, IndexSet<Name>> = artifacts
+        .iter()
+        .map(|(name, art)| (name.clone(), art.partof.clone()))
+        .collect();
+    let graphs = graph::determine_graphs(&partofs);
+
+    let mut roots: Vec<&Name> = match root {
+        Some(r) => vec![r],
+        None => artifacts
+            .iter()
+            .filter(|(_, art)| art.partof.is_empty())
+            .map(|(name, _)| name)
+            .collect(),
+    };
+    roots.sort();
+
+    let mut tree = TreeBuilder {
+        artifacts: artifacts,
+        graphs: &graphs,
+        cmd: cmd,
+        shown: IndexSet::new(),
+        out: Vec::new(),
+    };
+    for r in &roots {
+        tree.node(r, "", "", 0);
+    }
+
+    if root.is_none() {
+        // Artifacts in a cycle have no root, display them so they are not lost.
+        let mut reached = reachable(&graphs, &roots);
+        let mut missing: Vec<&Name> = artifacts.keys().filter(|n| !reached.contains(*n)).collect();
+        missing.sort();
+        for name in missing {
+            if !reached.contains(name) {
+                tree.node(name, "", "", 0);
+                reached.extend(reachable(&graphs, &[name]));
+            }
+        }
+    }
+    tree.out
+}
+
+/// The artifacts which are the roots or (recursively) their parts.
+fn reachable(graphs: &Graphs, roots: &[&Name]) -> IndexSet<Name> {
+    let mut out = IndexSet::new();
+    let mut stack: Vec<Name> = roots.iter().map(|n| (*n).clone()).collect();
+    while let Some(name) = stack.pop() {
+        if out.contains(&name) {
+            continue;
+        }
+        if let Some(id) = graphs.lookup_id.get(&name) {
+            stack.extend(
+                graphs
+                    .full
+                    .neighbors(*id)
+                    .map(|id: GraphId| graphs.lookup_name[&id].clone()),
+            );
+        }
+        out.insert(name);
+    }
+    out
+}
+
+struct TreeBuilder<'a> {
+    artifacts: &'a IndexMap<Name, Artifact>,
+    graphs: &'a Graphs,
+    cmd: &'a Tree,
+    /// Artifacts whose parts have already been displayed.
+    shown: IndexSet<Name>,
+    out: Vec<El>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    /// Render a node and (recursively) its parts.
+    ///
+    /// `prefix` is put before the node itself and `indent` before all of its
+    /// parts.
+    fn node(&mut self, name: &Name, prefix: &str, indent: &str, depth: usize) {
+        self.out.push(El::Text(t!(prefix.to_string())));
+        let art = match self.artifacts.get(name) {
+            Some(a) => a,
+            None => {
+                // partof an artifact which doesn't exist
+                self.out
+                    .push(El::Text(t!(format!("{}\n", name.as_str())).italic()));
+                return;
+            }
+        };
+        self.out.push(El::Text(
+            t!(name.as_str()).color(art.completed.name_color()),
+        ));
+        self.out.push(El::Text(t!("  ")));
+        self.out.push(El::Text(art.completed.spc_style()));
+        self.out.push(El::Text(t!("% ")));
+        self.out.push(El::Text(art.completed.tst_style()));
+        self.out.push(El::Text(t!("%")));
+
+        let mut parts: Vec<&Name> = self
+            .graphs
+            .full
+            .neighbors(self.graphs.lookup_id[name])
+            .map(|id: GraphId| &self.graphs.lookup_name[&id])
+            .collect();
+        parts.sort();
+
+        if self.shown.contains(name) {
+            // the artifact has multiple parents and was already displayed
+            self.note("(see above)");
+            self.out.push(El::Text(t!("\n")));
+            return;
+        }
+
+        if parts.is_empty() {
+            self.shown.insert(name.clone());
+            self.out.push(El::Text(t!("\n")));
+            return;
+        }
+        let complete = art.completed.spc >= 1.0 && art.completed.tst >= 1.0;
+        if self.cmd.collapse && complete {
+            self.note(&format!("({} parts complete)", parts.len()));
+            self.out.push(El::Text(t!("\n")));
+            return;
+        }
+        if let Some(max) = self.cmd.depth {
+            if depth >= max {
+                self.note(&format!("({} parts hidden)", parts.len()));
+                self.out.push(El::Text(t!("\n")));
+                return;
+            }
+        }
+        // only now are the parts displayed, a collapsed or hidden artifact is
+        // displayed again with its note wherever it is reached.
+        self.shown.insert(name.clone());
+        self.out.push(El::Text(t!("\n")));
+
+        let last = parts.len() - 1;
+        for (i, part) in parts.into_iter().enumerate() {
+            let (branch, next) = if i == last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            self.node(
+                part,
+                &format!("{}{}", indent, branch),
+                &format!("{}{}", indent, next),
+                depth + 1,
+            );
+        }
+    }
+
+    fn note(&mut self, note: &str) {
+        self.out.push(El::Text(t!(format!(" {}", note)).italic()));
+    }
+}
+
+#[test]
+fn test_tree() {
+    let artifacts: IndexMap<Name, Artifact> = vec![
+        art("REQ-a", "/fake", &[], 0.5, 0.25),
+        art("REQ-b", "/fake", &[], 1.0, 1.0),
+        art("SPC-a", "/fake", &["REQ-a"], 1.0, 0.5),
+        art("SPC-b", "/fake", &["REQ-a", "SPC-a"], 0.0, 0.0),
+        art("SPC-c", "/fake", &["REQ-b"], 1.0, 1.0),
+        art("TST-a", "/fake", &["SPC-a", "SPC-b"], 1.0, 1.0),
+    ]
+    .into_iter()
+    .map(|a| (a.name.clone(), a))
+    .collect();
+
+    let tree = |args: &[&str]| -> String {
+        let mut full = vec!["tree"];
+        full.extend(args);
+        let cmd = Tree::from_iter(full);
+        let root = cmd.root.as_ref().map(|r| name!(r.as_str()));
+        let mut w: Vec<u8> = Vec::new();
+        for mut el in tree_style(&artifacts, root.as_ref(), &cmd) {
+            el.set_plain();
+            expect!(el.paint(&mut w));
+        }
+        expect!(String::from_utf8(w))
+    };
+
+    assert_eq!(
+        tree(&[]),
+        "\
+REQ-a  50.0% 25.0%
+├── SPC-a  100.0% 50.0%
+│   ├── SPC-b  0.0% 0.0%
+│   │   └── TST-a  100.0% 100.0%
+│   └── TST-a  100.0% 100.0% (see above)
+└── SPC-b  0.0% 0.0% (see above)
+REQ-b  100.0% 100.0%
+└── SPC-c  100.0% 100.0%
+"
+    );
+    assert_eq!(
+        tree(&["SPC-a", "--depth", "1"]),
+        "\
+SPC-a  100.0% 50.0%
+├── SPC-b  0.0% 0.0% (1 parts hidden)
+└── TST-a  100.0% 100.0%
+"
+    );
+    // SPC-b is first reached too deep to display its parts, so they are
+    // displayed where it is reached again.
+    assert_eq!(
+        tree(&["--depth", "2"]),
+        "\
+REQ-a  50.0% 25.0%
+├── SPC-a  100.0% 50.0%
+│   ├── SPC-b  0.0% 0.0% (1 parts hidden)
+│   └── TST-a  100.0% 100.0%
+└── SPC-b  0.0% 0.0%
+    └── TST-a  100.0% 100.0% (see above)
+REQ-b  100.0% 100.0%
+└── SPC-c  100.0% 100.0%
+"
+    );
+    assert_eq!(
+        tree(&["--collapse", "-d", "0"]),
+        "\
+REQ-a  50.0% 25.0% (2 parts hidden)
+REQ-b  100.0% 100.0% (1 parts complete)
+"
+    );
+}
@@ -0,0 +1,408 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.trend
+//! Display how the completion of the project evolved over its snapshots.
+
+use std::io;
+
+use crate::dev_prelude::*;
+use crate::ls::escape_csv;
+use artifact_data::history::{self, Snapshot};
+use artifact_data::*;
+use artifact_lib::query::Query;
+use artifact_ser::markdown::{BLUE, GRAY, OLIVE};
+use termstyle::Color::*;
+use termstyle::{El, Text};
+
+macro_rules! t { [$t:expr] => {{
+    Text::new($t.into())
+}}}
+
+/// The characters of a sparkline, from 0% to 100%.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const SVG_WIDTH: f32 = 600.0;
+const SVG_HEIGHT: f32 = 200.0;
+const SVG_PAD: f32 = 30.0;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "trend")]
+/// Show how the completion of the project evolved over the snapshots taken
+/// with `art snapshot`.
+pub struct Trend {
+    #[structopt(long = "verbose", short = "v", default_value = "0")]
+    /// Pass many times for more log output.
+    pub verbosity: u64,
+
+    #[structopt(long = "work-dir")]
+    /// Use a different working directory [default: $CWD]
+    pub work_dir: Option<String>,
+
+    #[structopt(name = "QUERY")]
+    /// Only include the artifacts selected by the query (see `art ls --help`).
+    pub query: Option<String>,
+
+    #[structopt(long = "format", default_value = "human")]
+    /// Format of the output from [human, csv, json, svg]
+    pub format: String,
+
+    #[structopt(long = "plain")]
+    /// Do not display color in the output.
+    pub plain: bool,
+}
+
+/// Run the `art trend` command
+pub fn run(cmd: Trend) -> Result<i32> {
+    let mut w = io::stdout();
+
+    set_log_verbosity!(cmd);
+    let repo = find_repo(&work_dir!(cmd))?;
+    info!("Running art-trend in repo {}", repo.display());
+    let format = TrendFormat::from_str(&cmd.format)?;
+
+    let snapshots = history::load_history(&repo)?;
+    // The query is evaluated against the current project, since old
+    // snapshots only contain the completion of the artifacts.
+    let names = match cmd.query {
+        Some(ref q) => {
+            let query = Query::from_str(q)?;
+            let (_, project) = read_project(repo.clone())?;
+            Some(query.filter(&project))
+        }
+        None => None,
+    };
+    let points = trend_points(&snapshots, names.as_ref());
+
+    match format {
+        TrendFormat::Human => {
+            if points.is_empty() {
+                eprintln!("No snapshots found. Record one with `art snapshot`.");
+                return Ok(1);
+            }
+            for mut el in human_style(&points) {
+                if cmd.plain {
+                    el.set_plain();
+                }
+                el.paint(&mut w)?;
+            }
+        }
+        TrendFormat::Csv => write!(w, "{}", to_csv(&points))?,
+        TrendFormat::Json => write!(w, "{}", expect!(json::to_string_pretty(&to_json(&points))))?,
+        TrendFormat::Svg => write!(w, "{}", to_svg(&points))?,
+    }
+    Ok(0)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum TrendFormat {
+    Human,
+    Csv,
+    Json,
+    Svg,
+}
+
+impl TrendFormat {
+    fn from_str(s: &str) -> Result<TrendFormat> {
+        Ok(match s {
+            "human" => TrendFormat::Human,
+            "csv" => TrendFormat::Csv,
+            "json" => TrendFormat::Json,
+            "svg" => TrendFormat::Svg,
+            _ => bail!(
+                "Invalid format {:?}, must be one of human, csv, json or svg",
+                s
+            ),
+        })
+    }
+}
+
+/// The completion of the selected artifacts in a single snapshot.
+#[derive(Debug, Clone, PartialEq)]
+struct TrendPoint {
+    timestamp: String,
+    commit: Option<String>,
+    artifacts: usize,
+    spc: f32,
+    tst: f32,
+    lint_errors: usize,
+    lint_other: usize,
+}
+
+/// Compute the completion of every snapshot, limited to `names` if given.
+fn trend_points(snapshots: &[Snapshot], names: Option<&IndexSet<Name>>) -> Vec<TrendPoint> {
+    snapshots
+        .iter()
+        .map(|snap| {
+            let (artifacts, spc, tst) = match names {
+                Some(names) => {
+                    let selected: Vec<&Completed> = snap
+                        .artifacts
+                        .iter()
+                        .filter(|(n, _)| names.contains(*n))
+                        .map(|(_, c)| c)
+                        .collect();
+                    let (spc, tst) = history::mean_completed(selected.iter().cloned());
+                    (selected.len(), spc, tst)
+                }
+                None => (snap.totals.artifacts, snap.totals.spc, snap.totals.tst),
+            };
+            TrendPoint {
+                timestamp: snap.timestamp.clone(),
+                commit: snap.commit.clone(),
+                artifacts: artifacts,
+                spc: spc,
+                tst: tst,
+                lint_errors: snap.lints.error,
+                lint_other: snap.lints.other,
+            }
+        })
+        .collect()
+}
+
+/// Render the ratios as a sparkline, one character per ratio.
+fn sparkline<I: IntoIterator<Item = f32>>(ratios: I) -> String {
+    ratios
+        .into_iter()
+        .map(|r| {
+            let i = (r.max(0.0).min(1.0) * (SPARKS.len() - 1) as f32).round() as usize;
+            SPARKS[i]
+        })
+        .collect()
+}
+
+fn short_commit(commit: &Option<String>) -> String {
+    match *commit {
+        Some(ref c) => c.chars().take(8).collect(),
+        None => "-".to_string(),
+    }
+}
+
+/// #SPC-cli.trend_human
+fn human_style(points: &[TrendPoint]) -> Vec<El> {
+    let mut out = Vec::new();
+    macro_rules! line { [ $( $x:expr ),* ] => {{
+        $( out.push(El::Text($x)); )*
+        out.push(El::Text(t!("\n")));
+    }}}
+    let delta = |prev: Option<f32>, now: f32| -> Text {
+        match prev {
+            Some(p) if (now - p).abs() >= 0.0005 => {
+                let color = if now > p { Green } else { Red };
+                t!(format!(" {:>+6.1}", (now - p) * 100.0)).color(color)
+            }
+            _ => t!(format!(" {:>6}", "")),
+        }
+    };
+
+    line![t!(format!(
+        "{:<25} {:<8} {:>9} {:>7} {:>6} {:>7} {:>6}",
+        "timestamp", "commit", "artifacts", "spc%", "", "tst%", ""
+    ))
+    .bold()];
+    let mut prev: Option<&TrendPoint> = None;
+    for p in points.iter() {
+        line![
+            t!(format!(
+                "{:<25} {:<8} {:>9} {:>7}",
+                p.timestamp,
+                short_commit(&p.commit),
+                p.artifacts,
+                percent(p.spc)
+            )),
+            delta(prev.map(|p| p.spc), p.spc),
+            t!(format!(" {:>7}", percent(p.tst))),
+            delta(prev.map(|p| p.tst), p.tst)
+        ];
+        prev = Some(p);
+    }
+    line![];
+    line![
+        t!("spc ").bold(),
+        t!(sparkline(points.iter().map(|p| p.spc))).color(Blue)
+    ];
+    line![
+        t!("tst ").bold(),
+        t!(sparkline(points.iter().map(|p| p.tst))).color(Green)
+    ];
+    out
+}
+
+/// #SPC-cli.trend_csv
+fn to_csv(points: &[TrendPoint]) -> String {
+    let mut out = String::from("timestamp,commit,artifacts,spc,tst,lint_errors,lint_other\n");
+    for p in points.iter() {
+        out.push_str(&format!(
+            "{},{},{},{:.3},{:.3},{},{}\n",
+            escape_csv(&p.timestamp),
+            escape_csv(p.commit.as_ref().map(String::as_str).unwrap_or("")),
+            p.artifacts,
+            p.spc,
+            p.tst,
+            p.lint_errors,
+            p.lint_other,
+        ));
+    }
+    out
+}
+
+fn to_json(points: &[TrendPoint]) -> json::Value {
+    let points: Vec<json::Value> = points
+        .iter()
+        .map(|p| {
+            let mut obj = json::Map::new();
+            obj.insert("timestamp".into(), json::Value::from(p.timestamp.as_str()));
+            let commit = match p.commit {
+                Some(ref c) => json::Value::from(c.as_str()),
+                None => json::Value::Null,
+            };
+            obj.insert("commit".into(), commit);
+            obj.insert("artifacts".into(), json::Value::from(p.artifacts));
+            obj.insert("spc".into(), json::Value::from(p.spc));
+            obj.insert("tst".into(), json::Value::from(p.tst));
+            obj.insert("lint_errors".into(), json::Value::from(p.lint_errors));
+            obj.insert("lint_other".into(), json::Value::from(p.lint_other));
+            json::Value::Object(obj)
+        })
+        .collect();
+    json::Value::from(points)
+}
+
+/// #SPC-cli.trend_svg
+/// A line chart of the spc and tst completion over the snapshots.
+fn to_svg(points: &[TrendPoint]) -> String {
+    let inner_w = SVG_WIDTH - 2.0 * SVG_PAD;
+    let inner_h = SVG_HEIGHT - 2.0 * SVG_PAD;
+    let x = |i: usize| -> f32 {
+        if points.len() <= 1 {
+            SVG_PAD + inner_w / 2.0
+        } else {
+            SVG_PAD + inner_w * i as f32 / (points.len() - 1) as f32
+        }
+    };
+    let y = |ratio: f32| -> f32 { SVG_PAD + inner_h * (1.0 - ratio.max(0.0).min(1.0)) };
+    let polyline = |ratios: Vec<f32>, color: &str| -> String {
+        let coords: Vec<String> = ratios
+            .iter()
+            .enumerate()
+            .map(|(i, r)| format!("{:.1},{:.1}", x(i), y(*r)))
+            .collect();
+        format!(
+            "  <polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>\n",
+            color,
+            coords.join(" ")
+        )
+    };
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\">\n",
+        w = SVG_WIDTH,
+        h = SVG_HEIGHT,
+    );
+    for ratio in [0.0, 0.5, 1.0].iter() {
+        out.push_str(&format!(
+            "  <line x1=\"{x1}\" y1=\"{y:.1}\" x2=\"{x2}\" y2=\"{y:.1}\" stroke=\"{c}\"/>\n\
+             \x20 <text x=\"2\" y=\"{y:.1}\" font-size=\"10\">{p}%</text>\n",
+            x1 = SVG_PAD,
+            x2 = SVG_WIDTH - SVG_PAD,
+            y = y(*ratio),
+            c = GRAY,
+            p = ratio * 100.0,
+        ));
+    }
+    out.push_str(&polyline(points.iter().map(|p| p.spc).collect(), BLUE));
+    out.push_str(&polyline(points.iter().map(|p| p.tst).collect(), OLIVE));
+    out.push_str(&format!(
+        "  <text x=\"{x}\" y=\"15\" font-size=\"12\" fill=\"{spc}\">spc</text>\n\
+         \x20 <text x=\"{x2}\" y=\"15\" font-size=\"12\" fill=\"{tst}\">tst</text>\n",
+        x = SVG_PAD,
+        x2 = SVG_PAD + 30.0,
+        spc = BLUE,
+        tst = OLIVE,
+    ));
+    out.push_str("</svg>\n");
+    out
+}
+
+#[cfg(test)]
+fn test_snapshot(timestamp: &str, completed: &[(&str, f32, f32)]) -> Snapshot {
+    let artifacts: IndexMap<Name, Completed> = completed
+        .iter()
+        .map(|(n, spc, tst)| {
+            (
+                name!(*n),
+                Completed {
+                    spc: *spc,
+                    tst: *tst,
+                },
+            )
+        })
+        .collect();
+    let (spc, tst) = history::mean_completed(artifacts.values());
+    Snapshot {
+        timestamp: timestamp.to_string(),
+        commit: None,
+        totals: history::SnapshotTotals {
+            artifacts: artifacts.len(),
+            spc: spc,
+            tst: tst,
+        },
+        lints: history::SnapshotLints { error: 0, other: 1 },
+        artifacts: artifacts,
+    }
+}
+
+#[test]
+fn test_trend() {
+    let snapshots = vec![
+        test_snapshot(
+            "2018-01-01T00:00:00Z",
+            &[("REQ-a", 0.0, 0.0), ("SPC-b", 0.5, 0.0)],
+        ),
+        test_snapshot(
+            "2018-01-02T00:00:00Z",
+            &[
+                ("REQ-a", 0.5, 0.25),
+                ("SPC-b", 1.0, 0.5),
+                ("TST-c", 1.0, 1.0),
+            ],
+        ),
+    ];
+
+    let all = trend_points(&snapshots, None);
+    assert_eq!(all[0].artifacts, 2);
+    assert_eq!((all[0].spc, all[0].tst), (0.25, 0.0));
+    assert_eq!(all[1].artifacts, 3);
+
+    let names = indexset! {name!("SPC-b"), name!("TST-c")};
+    let selected = trend_points(&snapshots, Some(&names));
+    assert_eq!(selected[0].artifacts, 1);
+    assert_eq!((selected[0].spc, selected[0].tst), (0.5, 0.0));
+    assert_eq!((selected[1].spc, selected[1].tst), (1.0, 0.75));
+
+    assert_eq!(sparkline(vec![0.0, 0.5, 1.0]), "▁▅█");
+    assert_eq!(
+        to_csv(&selected),
+        "timestamp,commit,artifacts,spc,tst,lint_errors,lint_other\n\
+         2018-01-01T00:00:00Z,,1,0.500,0.000,0,1\n\
+         2018-01-02T00:00:00Z,,2,1.000,0.750,0,1\n"
+    );
+    let value = to_json(&selected);
+    assert_eq!(value[1]["tst"], json::Value::from(0.75));
+    assert_eq!(value[0]["commit"], json::Value::Null);
+    assert!(to_svg(&selected).contains("<polyline"));
+}
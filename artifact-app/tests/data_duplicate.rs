@@ -0,0 +1,98 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.duplicate
+
+use artifact_data::duplicate::{find_duplicates, normalize_words, Fingerprint};
+use artifact_test::artifact::art;
+use artifact_test::dev_prelude::*;
+
+fn with_text(name: &str, text: &str) -> (Name, Artifact) {
+    let art = Artifact {
+        text: text.into(),
+        ..art(name, "/fake", &[], 0.0, 0.0)
+    };
+    (art.name.clone(), art)
+}
+
+const LONG_TEXT: &str = "The server shall accept connections on the configured port \
+                         and reject any client which does not present a valid certificate. \
+                         Rejected clients are logged with their address and the reason.";
+
+#[test]
+fn sanity_normalize_words() {
+    assert_eq!(
+        normalize_words("Hello,  World!\n- the `foo_bar` IS here"),
+        vec!["hello", "world", "the", "foo", "bar", "is", "here"]
+    );
+    assert!(normalize_words("  ** -- \n").is_empty());
+}
+
+#[test]
+fn sanity_fingerprint_similarity() {
+    let fp = |t: &str| Fingerprint::from_words(&normalize_words(t));
+    let a = fp(LONG_TEXT);
+
+    // whitespace, case and punctuation are ignored
+    let b = fp(&LONG_TEXT.to_uppercase().replace(" ", "\n  "));
+    assert_eq!(a.exact, b.exact);
+    assert_eq!(a.similarity(&b), 1.0);
+
+    let c = fp(&LONG_TEXT.replace("logged", "recorded"));
+    let sim = a.similarity(&c);
+    assert!(sim > 0.7 && sim < 1.0, "sim={}", sim);
+
+    let d = fp("A completely different requirement about the user interface colors.");
+    assert!(a.similarity(&d) < 0.1);
+}
+
+#[test]
+fn sanity_find_duplicates() {
+    let mut artifacts: IndexMap<Name, Artifact> = IndexMap::new();
+    for (n, a) in vec![
+        with_text("REQ-a", LONG_TEXT),
+        with_text("REQ-b", &format!("{}\n", LONG_TEXT)),
+        with_text("SPC-c", &LONG_TEXT.replace("logged", "recorded")),
+        with_text("SPC-d", "Totally unrelated text which only talks about the database."),
+        // short texts are ignored
+        with_text("TST-e", "see above"),
+        with_text("TST-f", "see above"),
+    ] {
+        artifacts.insert(n, a);
+    }
+
+    let dups = find_duplicates(&artifacts, 1.0, 5);
+    let pairs: Vec<_> = dups.iter().map(|d| (d.first.clone(), d.second.clone())).collect();
+    assert_eq!(pairs, vec![(name!("REQ-a"), name!("REQ-b"))]);
+
+    let dups = find_duplicates(&artifacts, 0.7, 5);
+    let pairs: Vec<_> = dups.iter().map(|d| (d.first.clone(), d.second.clone())).collect();
+    assert_eq!(
+        pairs,
+        vec![
+            (name!("REQ-a"), name!("REQ-b")),
+            (name!("REQ-a"), name!("SPC-c")),
+            (name!("REQ-b"), name!("SPC-c")),
+        ]
+    );
+
+    // min_words=0 includes the short texts
+    let dups = find_duplicates(&artifacts, 1.0, 0);
+    assert!(dups
+        .iter()
+        .any(|d| d.first == name!("TST-e") && d.second == name!("TST-f")));
+}
@@ -0,0 +1,219 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.edit
+
+use artifact_test::artifact_data::edit::{name_prefix, resolve_edits, EditError, EditOp};
+use artifact_test::*;
+
+fn basic() -> Project {
+    let (_, project) = read_project(INTEROP_TESTS_PATH.join("basic")).unwrap();
+    project
+}
+
+fn deleted(ops: &[ArtifactOp]) -> IndexSet<Name> {
+    ops.iter()
+        .filter_map(|op| match *op {
+            ArtifactOp::Delete { ref name, .. } => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn sanity_edit_link() {
+    let project = basic();
+    let edits = vec![EditOp::Link {
+        name: name!("SPC-foo"),
+        partof: indexset! {name!("REQ-lib")},
+        expect_id: None,
+    }];
+    let ops = resolve_edits(&project, edits).unwrap();
+    assert_eq!(ops.len(), 1);
+    match ops[0] {
+        ArtifactOp::Update {
+            ref artifact,
+            orig_id,
+        } => {
+            assert_eq!(artifact.name, name!("SPC-foo"));
+            assert_eq!(artifact.partof, indexset! {name!("REQ-lib")});
+            assert_eq!(orig_id, project.artifacts[&name!("SPC-foo")].id);
+        }
+        ref op => panic!("unexpected op: {:?}", op),
+    }
+
+    // linking to an existing partof changes nothing
+    let edits = vec![EditOp::Link {
+        name: name!("SPC-foo_done"),
+        partof: indexset! {name!("REQ-foo")},
+        expect_id: None,
+    }];
+    assert!(resolve_edits(&project, edits).unwrap().is_empty());
+}
+
+#[test]
+fn sanity_edit_errors() {
+    let project = basic();
+    let mismatch = vec![EditOp::Mv {
+        name: name!("SPC-foo"),
+        file: "design/other.md".into(),
+        expect_id: Some(HashIm::default()),
+    }];
+    match resolve_edits(&project, mismatch) {
+        Err(EditError::IdMismatch { .. }) => {}
+        r => panic!("unexpected: {:?}", r),
+    }
+
+    let not_linked = vec![EditOp::Unlink {
+        name: name!("SPC-foo"),
+        partof: indexset! {name!("REQ-lib")},
+        expect_id: None,
+    }];
+    match resolve_edits(&project, not_linked) {
+        Err(EditError::NotLinked { .. }) => {}
+        r => panic!("unexpected: {:?}", r),
+    }
+
+    let dne = vec![EditOp::Rm {
+        name: name!("REQ-dne"),
+        recursive: false,
+        expect_id: None,
+    }];
+    match resolve_edits(&project, dne) {
+        Err(EditError::NotFound { .. }) => {}
+        r => panic!("unexpected: {:?}", r),
+    }
+}
+
+#[test]
+fn sanity_edit_rm() {
+    let project = basic();
+    let edits = vec![EditOp::Rm {
+        name: name!("REQ-foo"),
+        recursive: false,
+        expect_id: Some(project.artifacts[&name!("REQ-foo")].id),
+    }];
+    let ops = resolve_edits(&project, edits).unwrap();
+    assert_eq!(deleted(&ops), indexset! {name!("REQ-foo")});
+    // REQ-foo is removed from the partof of SPC-foo_done
+    assert_eq!(ops.len(), 2);
+    match ops[1] {
+        ArtifactOp::Update { ref artifact, .. } => {
+            assert_eq!(artifact.name, name!("SPC-foo_done"));
+            assert!(!artifact.partof.contains(&name!("REQ-foo")));
+        }
+        ref op => panic!("unexpected op: {:?}", op),
+    }
+
+    let edits = vec![EditOp::Rm {
+        name: name!("REQ-foo"),
+        recursive: true,
+        expect_id: None,
+    }];
+    let ops = resolve_edits(&project, edits).unwrap();
+    assert_eq!(
+        deleted(&ops),
+        indexset! {
+            name!("REQ-foo"),
+            name!("SPC-foo"),
+            name!("SPC-foo_done"),
+            name!("TST-foo"),
+        }
+    );
+}
+
+#[test]
+fn sanity_edit_new_then_set() {
+    let project = basic();
+    let edits = vec![
+        EditOp::New {
+            name: name!("SPC-new"),
+            file: "design/foo.md".into(),
+            partof: indexset! {name!("REQ-lib")},
+            done: None,
+            text: "first".into(),
+        },
+        EditOp::Set {
+            name: name!("SPC-new"),
+            text: Some("second".into()),
+            partof: None,
+            done: None,
+            not_done: false,
+            expect_id: None,
+        },
+    ];
+    let ops = resolve_edits(&project, edits).unwrap();
+    assert_eq!(ops.len(), 1);
+    match ops[0] {
+        ArtifactOp::Create { ref artifact } => {
+            assert_eq!(artifact.text, "second");
+            let file: &Path = artifact.file.as_ref();
+            let base: &Path = &project.settings.base;
+            assert_eq!(file, base.join("design/foo.md").as_path());
+        }
+        ref op => panic!("unexpected op: {:?}", op),
+    }
+}
+
+/// The new file of every updated artifact, relative to the project base.
+fn moved(project: &Project, ops: &[ArtifactOp]) -> IndexMap<Name, String> {
+    let base: &Path = &project.settings.base;
+    ops.iter()
+        .map(|op| match *op {
+            ArtifactOp::Update { ref artifact, .. } => {
+                let file: &Path = artifact.file.as_ref();
+                let file = file.strip_prefix(base).unwrap().to_string_lossy();
+                (artifact.name.clone(), file.to_string())
+            }
+            ref op => panic!("unexpected op: {:?}", op),
+        })
+        .collect()
+}
+
+#[test]
+fn sanity_edit_split_merge() {
+    assert_eq!(name_prefix(&name!("SPC-net-tcp")), "net");
+    assert_eq!(name_prefix(&name!("REQ-Foo")), "foo");
+
+    let project = basic();
+    let edits = vec![EditOp::Split {
+        file: "design/purpose.md".into(),
+        dir: None,
+    }];
+    let ops = resolve_edits(&project, edits).unwrap();
+    // REQ-purpose stays in purpose.md
+    let expected = indexmap! {
+        name!("REQ-lib") => "design/lib.md".to_string(),
+        name!("REQ-foo") => "design/foo.md".to_string(),
+        name!("REQ-baz") => "design/baz.md".to_string(),
+        name!("SPC-build") => "design/build.md".to_string(),
+        name!("TST-build") => "design/build.md".to_string(),
+    };
+    assert_eq!(moved(&project, &ops), expected);
+
+    let edits = vec![EditOp::Merge {
+        from: vec!["design/foo.md".into()],
+        file: "design/purpose.md".into(),
+    }];
+    let ops = resolve_edits(&project, edits).unwrap();
+    let expected = indexmap! {
+        name!("SPC-foo") => "design/purpose.md".to_string(),
+        name!("SPC-foo_done") => "design/purpose.md".to_string(),
+        name!("TST-foo") => "design/purpose.md".to_string(),
+    };
+    assert_eq!(moved(&project, &ops), expected);
+}
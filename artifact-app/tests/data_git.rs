@@ -0,0 +1,175 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.git
+
+use std::fs;
+
+use artifact_test::artifact_data::git;
+use artifact_test::artifact_data::ART_DIR;
+use artifact_test::*;
+
+/// Run git in the directory, returning its stdout.
+fn run<P: AsRef<Path>>(dir: P, args: &[&str]) -> String {
+    let out = expect!(git::git(dir, args, &[]));
+    expect!(String::from_utf8(out))
+}
+
+/// A git repository with the basic interop project in the `project`
+/// sub directory, with everything committed.
+fn basic_repo(tmp: &tempdir::TempDir) -> PathDir {
+    let base_path = tmp.path().join("project");
+    let (send_err, recv_err) = ch::bounded(128);
+    deep_copy(
+        send_err,
+        PathDir::new(INTEROP_TESTS_PATH.join("basic")).unwrap(),
+        base_path.clone(),
+    );
+    let errs: Vec<_> = recv_err.iter().collect();
+    assert!(errs.is_empty(), "Got IO Errors:\n{:#?}", errs);
+
+    run(tmp.path(), &["init", "-q"]);
+    run(tmp.path(), &["config", "user.name", "Initial Author"]);
+    run(tmp.path(), &["config", "user.email", "initial@example.com"]);
+    run(tmp.path(), &["add", "-A"]);
+    run(tmp.path(), &["commit", "-q", "-m", "initial"]);
+    PathDir::new(base_path).unwrap()
+}
+
+#[test]
+fn sanity_git_export_revision() {
+    let tmp = tempdir::TempDir::new("art-git").unwrap();
+    let base = basic_repo(&tmp);
+    fs::remove_file(base.join("design").join("foo.md")).unwrap();
+    run(&base, &["commit", "-q", "-a", "-m", "remove foo"]);
+
+    // the project is exported from its sub directory, not the repo's top
+    let (_old_tmp, old) = git::export_revision(&base, "HEAD~1").unwrap();
+    assert!(old.join(ART_DIR).exists());
+    let (_, project) = read_project(&old).unwrap();
+    assert!(project.artifacts.contains_key(&name!("SPC-foo")));
+
+    let (_new_tmp, new) = git::export_revision(&base, "HEAD").unwrap();
+    let (_, project) = read_project(&new).unwrap();
+    assert!(!project.artifacts.contains_key(&name!("SPC-foo")));
+    assert!(project.artifacts.contains_key(&name!("REQ-purpose")));
+}
+
+#[test]
+fn sanity_git_ensure_clean() {
+    let tmp = tempdir::TempDir::new("art-git").unwrap();
+    let base = basic_repo(&tmp);
+    let (_, project) = read_project(&base).unwrap();
+    let update = |name: &str| {
+        let art = &project.artifacts[&name!(name)];
+        let mut artifact = ArtifactIm::from(art.clone());
+        artifact.text.push_str("\n\nMore text.");
+        vec![ArtifactOp::Update {
+            artifact: artifact,
+            orig_id: art.id,
+        }]
+    };
+
+    let foo = base.join("design").join("foo.md");
+    let mut text = fs::read_to_string(&foo).unwrap();
+    text.push_str("\nUncommitted text.\n");
+    fs::write(&foo, &text).unwrap();
+
+    // only the files of the modified artifacts have to be clean
+    let ops = update("SPC-foo");
+    let files = git::modified_files(&project, &ops);
+    assert_eq!(files, vec![foo.clone()]);
+    assert!(git::ensure_clean(&base, &files).is_err());
+
+    let ops = update("REQ-purpose");
+    let files = git::modified_files(&project, &ops);
+    assert_eq!(files, vec![base.join("design").join("purpose.md")]);
+    git::ensure_clean(&base, &files).unwrap();
+}
+
+#[test]
+fn sanity_git_commit() {
+    let tmp = tempdir::TempDir::new("art-git").unwrap();
+    let base = basic_repo(&tmp);
+    let (_, project) = read_project(&base).unwrap();
+
+    // changes which are not part of the modification
+    fs::write(base.join("notes.md"), "not an artifact\n").unwrap();
+    let lib = base.join("src").join("lib.rs");
+    let mut text = fs::read_to_string(&lib).unwrap();
+    text.push_str("\n// uncommitted\n");
+    fs::write(&lib, &text).unwrap();
+
+    // move everything in foo.md to a new file and change REQ-lib
+    let moved = base.join("design").join("moved.md");
+    let mut ops = Vec::new();
+    for name in &["SPC-foo", "SPC-foo_done", "TST-foo"] {
+        let art = &project.artifacts[&name!(*name)];
+        let mut artifact = ArtifactIm::from(art.clone());
+        artifact.file = PathSer::from(moved.as_path());
+        ops.push(ArtifactOp::Update {
+            artifact: artifact,
+            orig_id: art.id,
+        });
+    }
+    let art = &project.artifacts[&name!("REQ-lib")];
+    let mut artifact = ArtifactIm::from(art.clone());
+    artifact.text.push_str("\n\nMore text.");
+    ops.push(ArtifactOp::Update {
+        artifact: artifact,
+        orig_id: art.id,
+    });
+
+    let files = git::modified_files(&project, &ops);
+    git::ensure_clean(&base, &files).unwrap();
+    let (_, _, changed) = modify_project_changed(&base, ops.clone()).unwrap();
+    let message = git::commit_message(&ops);
+    git::commit(
+        &base,
+        &changed,
+        &message,
+        Some("Some One <one@example.com>"),
+    )
+    .unwrap();
+
+    let show = run(
+        &base,
+        &[
+            "show",
+            "--name-status",
+            "--no-renames",
+            "--format=%an <%ae>%n%s",
+            "HEAD",
+        ],
+    );
+    let show: Vec<_> = show.lines().filter(|l| !l.is_empty()).collect();
+    assert_eq!(
+        show,
+        vec![
+            "Some One <one@example.com>",
+            "art: modify 4 artifacts",
+            "D\tproject/design/foo.md",
+            "A\tproject/design/moved.md",
+            "M\tproject/design/purpose.md",
+        ]
+    );
+
+    // the other changes are left alone
+    let status = run(&base, &["status", "--porcelain"]);
+    let status: Vec<_> = status.lines().collect();
+    assert_eq!(status, vec![" M project/src/lib.rs", "?? project/notes.md"]);
+}
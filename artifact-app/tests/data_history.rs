@@ -0,0 +1,76 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.history
+
+use artifact_test::artifact_data::history::{self, Snapshot, SnapshotLints, SnapshotTotals};
+use artifact_test::artifact_data::ART_DIR;
+use artifact_test::*;
+
+fn snapshot(timestamp: &str, commit: Option<&str>, spc: f32) -> Snapshot {
+    Snapshot {
+        timestamp: timestamp.to_string(),
+        commit: commit.map(|c| c.to_string()),
+        totals: SnapshotTotals {
+            artifacts: 1,
+            spc: spc,
+            tst: 0.0,
+        },
+        lints: SnapshotLints { error: 0, other: 2 },
+        artifacts: indexmap! {
+            name!("REQ-a") => Completed { spc: spc, tst: 0.0 },
+        },
+    }
+}
+
+#[test]
+fn sanity_history_roundtrip() {
+    let tmp = tempdir::TempDir::new("art-history").unwrap();
+    let base = PathDir::create(tmp.path().join("project")).unwrap();
+    PathDir::create(base.join(ART_DIR)).unwrap();
+
+    assert_eq!(history::load_history(&base).unwrap(), vec![]);
+
+    let first = snapshot("2018-01-01T00:00:00Z", None, 0.25);
+    let second = snapshot("2018-01-02T00:00:00Z", Some("abc123"), 0.5);
+    history::append_snapshot(&base, &first).unwrap();
+    history::append_snapshot(&base, &second).unwrap();
+    assert_eq!(
+        history::load_history(&base).unwrap(),
+        vec![first.clone(), second.clone()]
+    );
+
+    // snapshots are appended one per line
+    let path = PathFile::new(history::history_path(&base)).unwrap();
+    let mut text = path.read_string().unwrap();
+    assert_eq!(text.lines().count(), 2);
+
+    text.push_str("not json\n");
+    path.write_str(&text).unwrap();
+    let err = history::load_history(&base).unwrap_err().to_string();
+    assert!(err.contains(":3: invalid snapshot"), "{}", err);
+}
+
+#[test]
+fn sanity_mean_completed() {
+    let completed = vec![
+        Completed { spc: 1.0, tst: 0.5 },
+        Completed { spc: 0.0, tst: 0.0 },
+    ];
+    assert_eq!(history::mean_completed(completed.iter()), (0.5, 0.25));
+    assert_eq!(history::mean_completed(vec![].iter()), (0.0, 0.0));
+}
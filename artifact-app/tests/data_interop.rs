@@ -115,3 +115,27 @@ fn data_interop_lints_error1() {
 fn data_interop_lints_error2() {
     run_interop_tests(INTEROP_TESTS_PATH.join("lints2"));
 }
+
+#[test]
+/// #TST-read-artifact.lints_subnames
+fn data_interop_lints_subnames() {
+    run_interop_tests(INTEROP_TESTS_PATH.join("lints_subnames"));
+}
+
+#[test]
+/// #TST-read-artifact.lints_markdown
+fn data_interop_lints_markdown() {
+    run_interop_tests(INTEROP_TESTS_PATH.join("lints_markdown"));
+}
+
+#[test]
+/// #TST-read-artifact.lints_links
+fn data_interop_lints_links() {
+    run_interop_tests(INTEROP_TESTS_PATH.join("lints_links"));
+}
+
+#[test]
+/// #TST-read-artifact.lints_rules
+fn data_interop_lints_rules() {
+    run_interop_tests(INTEROP_TESTS_PATH.join("lints_rules"));
+}
@@ -0,0 +1,149 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.journal
+
+use artifact_test::artifact_data::journal::{self, Journal, JournalFile, Recovered};
+use artifact_test::artifact_data::ART_DIR;
+use artifact_test::*;
+
+/// A save which was interrupted after `a.md` was renamed, while `b.md`
+/// was to be removed and `c.md` created.
+fn interrupted(base: &PathDir, committed: bool) {
+    PathDir::create(base.join(ART_DIR)).unwrap();
+    let path = |name: &str| base.join(name).to_path_buf();
+
+    PathFile::create(path("b.md"))
+        .unwrap()
+        .write_str("old b")
+        .unwrap();
+    PathFile::create(path("b.md.artbk"))
+        .unwrap()
+        .write_str("old b")
+        .unwrap();
+    PathFile::create(path("c.md.arttmp"))
+        .unwrap()
+        .write_str("new c")
+        .unwrap();
+    PathFile::create(path("a.md.artbk"))
+        .unwrap()
+        .write_str("old a")
+        .unwrap();
+    if committed {
+        PathFile::create(path("a.md"))
+            .unwrap()
+            .write_str("new a")
+            .unwrap();
+    } else {
+        PathFile::create(path("a.md"))
+            .unwrap()
+            .write_str("old a")
+            .unwrap();
+        PathFile::create(path("a.md.arttmp"))
+            .unwrap()
+            .write_str("new a")
+            .unwrap();
+    }
+
+    let journal = Journal {
+        committed: committed,
+        files: vec![
+            JournalFile {
+                path: path("a.md"),
+                tmp: Some(path("a.md.arttmp")),
+                backup: Some(path("a.md.artbk")),
+            },
+            JournalFile {
+                path: path("b.md"),
+                tmp: None,
+                backup: Some(path("b.md.artbk")),
+            },
+            JournalFile {
+                path: path("c.md"),
+                tmp: Some(path("c.md.arttmp")),
+                backup: None,
+            },
+        ],
+    };
+    PathFile::create(journal::journal_path(base))
+        .unwrap()
+        .write_str(&json::to_string(&journal).unwrap())
+        .unwrap();
+    assert_eq!(journal::load_journal(base).unwrap(), Some(journal));
+}
+
+/// The files left in the directory, with their contents.
+fn files(base: &PathDir) -> IndexMap<String, String> {
+    let mut out: IndexMap<String, String> = base
+        .list()
+        .unwrap()
+        .filter_map(|p| match p.unwrap() {
+            PathType::File(f) => Some((
+                f.file_name().unwrap().to_string_lossy().to_string(),
+                f.read_string().unwrap(),
+            )),
+            PathType::Dir(_) => None,
+        })
+        .collect();
+    out.sort_keys();
+    out
+}
+
+#[test]
+fn sanity_journal_roll_forward() {
+    let tmp = tempdir::TempDir::new("art-journal").unwrap();
+    let base = PathDir::create(tmp.path().join("project")).unwrap();
+    assert_eq!(journal::recover(&base, false).unwrap(), None);
+
+    interrupted(&base, true);
+    assert_eq!(
+        journal::recover(&base, false).unwrap(),
+        Some(Recovered::Forward)
+    );
+    let expected = indexmap! {
+        "a.md".to_string() => "new a".to_string(),
+        "c.md".to_string() => "new c".to_string(),
+    };
+    assert_eq!(files(&base), expected);
+    assert_eq!(journal::load_journal(&base).unwrap(), None);
+}
+
+#[test]
+fn sanity_journal_roll_back() {
+    let tmp = tempdir::TempDir::new("art-journal").unwrap();
+    let base = PathDir::create(tmp.path().join("project")).unwrap();
+    let expected = indexmap! {
+        "a.md".to_string() => "old a".to_string(),
+        "b.md".to_string() => "old b".to_string(),
+    };
+
+    interrupted(&base, true);
+    assert_eq!(
+        journal::recover(&base, true).unwrap(),
+        Some(Recovered::Back)
+    );
+    assert_eq!(files(&base), expected);
+    assert_eq!(journal::load_journal(&base).unwrap(), None);
+
+    // a save which was never committed can only be rolled back
+    interrupted(&base, false);
+    assert_eq!(
+        journal::recover(&base, false).unwrap(),
+        Some(Recovered::Back)
+    );
+    assert_eq!(files(&base), expected);
+}
@@ -0,0 +1,96 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.links
+
+use artifact_data::links::{is_http, local_target_exists, KnownUrls, UrlStatus};
+use artifact_data::md_lint::{find_links, MarkdownLink};
+use artifact_test::dev_prelude::*;
+
+#[test]
+fn sanity_find_links() {
+    let text = "A [link](foo.md)\n\n![image](img/a.png) and <https://example.com>\n";
+    let link = |line, dest: &str, image| MarkdownLink {
+        line: line,
+        dest: dest.into(),
+        image: image,
+    };
+    assert_eq!(
+        find_links(text),
+        vec![
+            link(1, "foo.md", false),
+            link(3, "img/a.png", true),
+            link(3, "https://example.com", false),
+        ]
+    );
+}
+
+#[test]
+fn sanity_known_urls() {
+    let known = expect!(KnownUrls::from_text(
+        "# comment\n\
+         \n\
+         https://example.com/page\n\
+         https://example.com/gone 404\n\
+         https://example.com/moved 301\n\
+         https://docs.rs/*\n",
+    ));
+    assert_eq!(known.status("https://example.com/page"), UrlStatus::Ok);
+    assert_eq!(
+        known.status("https://example.com/page#anchor"),
+        UrlStatus::Ok
+    );
+    assert_eq!(known.status("https://example.com/moved"), UrlStatus::Ok);
+    assert_eq!(
+        known.status("https://example.com/gone"),
+        UrlStatus::Broken(404)
+    );
+    assert_eq!(known.status("https://docs.rs/artifact"), UrlStatus::Ok);
+    assert_eq!(
+        known.status("https://example.com/other"),
+        UrlStatus::Unknown
+    );
+
+    assert!(KnownUrls::from_text("https://example.com notastatus").is_err());
+    assert!(KnownUrls::from_text("https://example.com 200 extra").is_err());
+    assert!(KnownUrls::from_text("https://example.com/* 200").is_err());
+
+    assert!(is_http("https://example.com"));
+    assert!(is_http("HTTP://example.com"));
+    assert!(!is_http("mailto:someone@example.com"));
+    assert!(!is_http("foo.md"));
+}
+
+#[test]
+fn sanity_local_target_exists() {
+    let tmp = expect!(PathTmp::create("test-"));
+    let base: &Path = &tmp;
+    let dir = base.join("design");
+    expect!(::std::fs::create_dir(&dir));
+    expect!(touch(base.join("README.md")));
+    expect!(touch(dir.join("image.png")));
+
+    let exists = |dest| local_target_exists(dest, &dir, base);
+    assert_eq!(exists("image.png"), Some(true));
+    assert_eq!(exists("README.md"), Some(true));
+    assert_eq!(exists("../README.md"), Some(true));
+    assert_eq!(exists("/README.md"), Some(true));
+    assert_eq!(exists("/image.png"), Some(false));
+    assert_eq!(exists("missing.md"), Some(false));
+    assert_eq!(exists("https://example.com"), None);
+    assert_eq!(exists("#anchor"), None);
+}
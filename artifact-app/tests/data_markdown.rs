@@ -0,0 +1,152 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.markdown
+
+use artifact_data::dot::validate_dot;
+use artifact_data::md_lint::{local_path, validate_markdown, MarkdownIssue};
+use artifact_test::dev_prelude::*;
+
+fn issue(line: usize, msg: &str) -> MarkdownIssue {
+    MarkdownIssue {
+        line: line,
+        msg: msg.into(),
+    }
+}
+
+#[test]
+fn sanity_validate_dot() {
+    let valid = &[
+        "digraph { a -> b; b -> c [label=\"x\"]; }",
+        "graph G { a -- b -- c; subgraph cluster_x { d; e } a -- {d e} }",
+        "strict digraph { node [shape=box]; rankdir=LR\n [[SPC-foo]] -> [[.sub]] }",
+        "digraph { a [label=<<b>hi</b>>] } /* comment */",
+        "digraph {\n  // comment\n  a:n -> b:s:e; -1.5 -> .5\n}",
+    ];
+    for dot in valid {
+        assert_eq!(validate_dot(dot), Ok(()), "{}", dot);
+    }
+
+    let invalid = &[
+        (
+            "digraph { a -- b }",
+            1,
+            "'--' cannot be used in a directed graph, use '->'",
+        ),
+        (
+            "graph { a -> b }",
+            1,
+            "'->' cannot be used in an undirected graph, use '--'",
+        ),
+        (
+            "digraph {\n a -> b\n c ->\n}",
+            4,
+            "expected a node id, subgraph or statement but got '}'",
+        ),
+        ("digraph { a -> b ", 1, "expected '}' but got end of graph"),
+        (
+            "digraph { a [label=\"x\" }",
+            1,
+            "expected an id but got '}'",
+        ),
+        ("digraph {\n a [label=\"x]\n}", 2, "unclosed string"),
+        (
+            "digraph { a; } extra",
+            1,
+            "expected end of graph but got \"extra\"",
+        ),
+        (
+            "flowchart { a }",
+            1,
+            "expected 'graph' or 'digraph' but got \"flowchart\"",
+        ),
+    ];
+    for (dot, line, msg) in invalid {
+        let err = validate_dot(dot).expect_err(dot);
+        assert_eq!((err.line, err.msg.as_str()), (*line, *msg), "{}", dot);
+    }
+}
+
+#[test]
+fn sanity_local_path() {
+    assert_eq!(local_path("foo/bar.md"), Some("foo/bar.md".into()));
+    assert_eq!(local_path("../a%20b.png#anchor"), Some("../a b.png".into()));
+    assert_eq!(local_path("file.md?raw=true"), Some("file.md".into()));
+    assert_eq!(local_path("http://example.com/foo.md"), None);
+    assert_eq!(local_path("mailto:someone@example.com"), None);
+    assert_eq!(local_path("#anchor"), None);
+    assert_eq!(local_path("/absolute/path"), None);
+    assert_eq!(local_path(""), None);
+}
+
+#[test]
+fn sanity_validate_markdown() {
+    let tmp = expect!(PathTmp::create("test-"));
+    expect!(touch(tmp.join("exists.md")));
+
+    let text = r#"A valid table:
+
+| a | b |
+|:--|--:|
+| 1 | 2 |
+
+A broken table:
+
+| a | b |
+|---|
+
+[exists](exists.md) [missing](missing.md) [web](https://example.com)
+![missing image](img/missing.png)
+
+```dot
+digraph {
+    a -> b
+    b -- c
+}
+```
+
+~~~
+```
+this is still in the fence
+~~~~
+
+````python
+print("never closed")
+```
+"#;
+    let expected = vec![
+        issue(10, "table header has 2 columns but the delimiter row has 1"),
+        issue(12, "link target does not exist: missing.md"),
+        issue(13, "image target does not exist: img/missing.png"),
+        issue(
+            18,
+            "invalid dot graph: '--' cannot be used in a directed graph, use '->'",
+        ),
+        issue(
+            27,
+            "code fence \"````\" is never closed, it will swallow all following text",
+        ),
+    ];
+    assert_eq!(validate_markdown(text, Some(tmp.as_ref())), expected);
+
+    // links are not checked without a directory
+    let no_links: Vec<_> = expected
+        .into_iter()
+        .filter(|i| !i.msg.contains("does not exist"))
+        .collect();
+    assert_eq!(validate_markdown(text, None), no_links);
+}
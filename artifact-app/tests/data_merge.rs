@@ -0,0 +1,130 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.merge
+
+use artifact_test::artifact_data::merge::{is_current, merge_lines, merge_set, rebase_ops};
+use artifact_test::*;
+
+#[test]
+fn sanity_merge_lines() {
+    let base = "a\nb\nc\nd\ne";
+    // changes separated by an unchanged line are merged
+    assert_eq!(
+        merge_lines(base, "A\nb\nc\nd\ne", "a\nb\nc\nd\nE\nf"),
+        Some("A\nb\nc\nd\nE\nf".to_string())
+    );
+    // the same change on both sides
+    assert_eq!(
+        merge_lines(base, "a\nB\nc\nd\ne", "a\nB\nc\nd\ne"),
+        Some("a\nB\nc\nd\ne".to_string())
+    );
+    // removed lines
+    assert_eq!(
+        merge_lines(base, "b\nc\nd\ne", "a\nb\nc\nd"),
+        Some("b\nc\nd".to_string())
+    );
+    // the same line changed differently
+    assert_eq!(merge_lines(base, "a\nB\nc\nd\ne", "a\nX\nc\nd\ne"), None);
+
+    // the trailing newline is kept
+    let base = "a\nb\nc\nd\ne\n";
+    assert_eq!(
+        merge_lines(base, "A\nb\nc\nd\ne\n", "a\nb\nc\nd\nE\n"),
+        Some("A\nb\nc\nd\nE\n".to_string())
+    );
+    // and removed if either side removed it
+    assert_eq!(
+        merge_lines(base, "A\nb\nc\nd\ne\n", "a\nb\nc\nd\ne"),
+        Some("A\nb\nc\nd\ne".to_string())
+    );
+}
+
+#[test]
+fn sanity_merge_set() {
+    let set = |names: &[&str]| -> IndexSet<Name> { names.iter().map(|n| name!(*n)).collect() };
+    let base = set(&["REQ-a", "REQ-b", "REQ-c"]);
+    let ours = set(&["REQ-a", "REQ-b", "REQ-d"]);
+    let theirs = set(&["REQ-b", "REQ-c", "REQ-e"]);
+    assert_eq!(
+        merge_set(&base, &ours, &theirs),
+        set(&["REQ-b", "REQ-d", "REQ-e"])
+    );
+}
+
+#[test]
+fn sanity_rebase_ops() {
+    let tmp = tempdir::TempDir::new("art-merge").unwrap();
+    let base_path = tmp.path().join("basic");
+    let (send_err, recv_err) = ch::bounded(128);
+    deep_copy(
+        send_err,
+        PathDir::new(INTEROP_TESTS_PATH.join("basic")).unwrap(),
+        base_path.clone(),
+    );
+    let errs: Vec<_> = recv_err.iter().collect();
+    assert!(errs.is_empty(), "Got IO Errors:\n{:#?}", errs);
+
+    let (_, project) = read_project(&base_path).unwrap();
+    let art = &project.artifacts[&name!("SPC-foo")];
+    let base = ArtifactIm::from(art.clone());
+    let bases = indexmap! { art.id => base.clone() };
+
+    // someone else appends to the text
+    let mut theirs = base.clone();
+    theirs.text.push_str("\n\nTheir foo.");
+    modify_project(
+        &base_path,
+        vec![ArtifactOp::Update {
+            artifact: theirs,
+            orig_id: art.id,
+        }],
+    )
+    .unwrap();
+    let (_, current) = read_project(&base_path).unwrap();
+
+    // while we change the first line
+    let mut ours = base.clone();
+    ours.text = format!("Our foo.\n\n{}", base.text);
+    let ops = vec![ArtifactOp::Update {
+        artifact: ours.clone(),
+        orig_id: art.id,
+    }];
+    assert!(!is_current(&current, &ops));
+    let ops = rebase_ops(&current, &bases, ops).unwrap();
+    assert!(is_current(&current, &ops));
+    let (_, project) = modify_project(&base_path, ops).unwrap();
+    let text = &project.artifacts[&name!("SPC-foo")].text;
+    assert!(text.starts_with("Our foo."), "{}", text);
+    assert!(text.ends_with("Their foo."), "{}", text);
+
+    // changing the same line differently conflicts
+    let mut ours = base.clone();
+    ours.text.push_str("\n\nOur other foo.");
+    let ops = vec![ArtifactOp::Update {
+        artifact: ours,
+        orig_id: art.id,
+    }];
+    let conflicts = rebase_ops(&project, &bases, ops).unwrap_err();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].orig_id, art.id);
+    assert_eq!(
+        conflicts[0].theirs_id,
+        Some(project.artifacts[&name!("SPC-foo")].id)
+    );
+    assert_eq!(conflicts[0].fields, vec!["text".to_string()]);
+}
@@ -0,0 +1,121 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.modify
+
+use artifact_test::artifact_data::{modify_project_changed, validate_modify};
+use artifact_test::*;
+
+/// A copy of the basic project, formatted so that saving it is a noop.
+fn basic_copy(tmp: &tempdir::TempDir) -> PathDir {
+    let project_path = tmp.path().join("basic");
+    let (send_err, recv_err) = ch::bounded(128);
+    deep_copy(
+        send_err,
+        PathDir::new(INTEROP_TESTS_PATH.join("basic")).unwrap(),
+        project_path.clone(),
+    );
+    let errs: Vec<_> = recv_err.iter().collect();
+    assert!(errs.is_empty(), "Got IO Errors:\n{:#?}", errs);
+    let project_path = PathDir::new(project_path).unwrap();
+    modify_project_changed(&project_path, vec![]).unwrap();
+    project_path
+}
+
+#[test]
+fn sanity_modify_changed() {
+    let tmp = tempdir::TempDir::new("art-modify").unwrap();
+    let base = basic_copy(&tmp);
+
+    let (_, project, changed) = modify_project_changed(&base, vec![]).unwrap();
+    assert!(changed.is_empty(), "{:?}", changed);
+
+    // only the file of the updated artifact is written
+    let art = &project.artifacts[&name!("SPC-foo")];
+    let mut im = ArtifactIm::from(art.clone());
+    im.text.push_str("\n\nMore foo.");
+    let ops = vec![ArtifactOp::Update {
+        artifact: im,
+        orig_id: art.id,
+    }];
+    let (_, project, changed) = modify_project_changed(&base, ops).unwrap();
+    let foo = project.artifacts[&name!("SPC-foo")].file.to_path_buf();
+    assert_eq!(changed, vec![foo]);
+
+    // moving the only artifacts out of a file removes it
+    let purpose = base.join("design").join("purpose.md").to_path_buf();
+    let other = base.join("design").join("other.md").to_path_buf();
+    let ops: Vec<_> = project
+        .artifacts
+        .values()
+        .filter(|art| art.file.to_path_buf() == purpose)
+        .map(|art| {
+            let mut im = ArtifactIm::from(art.clone());
+            im.file = PathSer::from(other.as_path());
+            ArtifactOp::Update {
+                artifact: im,
+                orig_id: art.id,
+            }
+        })
+        .collect();
+    assert!(!ops.is_empty());
+    let (_, _, changed) = modify_project_changed(&base, ops).unwrap();
+    assert_eq!(changed, vec![other, purpose.clone()]);
+    assert!(!purpose.exists());
+}
+
+#[test]
+fn sanity_validate_modify() {
+    let tmp = tempdir::TempDir::new("art-modify").unwrap();
+    let base = basic_copy(&tmp);
+    let (_, project) = read_project(&base).unwrap();
+    let art = &project.artifacts[&name!("SPC-foo")];
+    let before = art.file.read_string().unwrap();
+
+    let mut im = ArtifactIm::from(art.clone());
+    im.text.push_str("\n\nMore foo.");
+    let ops = vec![ArtifactOp::Update {
+        artifact: im.clone(),
+        orig_id: art.id,
+    }];
+    let validation = validate_modify(&project, ops);
+    assert!(validation.error.is_none(), "{:?}", validation);
+    assert!(validation.lints.error.is_empty());
+    assert_eq!(
+        validation.completed.len(),
+        project.artifacts.len(),
+        "{:?}",
+        validation
+    );
+    assert_eq!(validation.completed[&name!("SPC-foo")], art.completed);
+
+    // an out of date id is a mismatch
+    let ops = vec![ArtifactOp::Update {
+        artifact: im,
+        orig_id: HashIm([0; 16]),
+    }];
+    let validation = validate_modify(&project, ops);
+    match validation.error {
+        Some(ModifyErrorKind::HashMismatch) => {}
+        ref e => panic!("{:?}", e),
+    }
+    assert!(!validation.lints.error.is_empty());
+    assert!(validation.completed.is_empty());
+
+    // nothing is ever saved
+    assert_eq!(art.file.read_string().unwrap(), before);
+}
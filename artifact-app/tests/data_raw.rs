@@ -17,7 +17,9 @@
 
 use artifact_test::*;
 
-use crate::artifact_data::raw::{ArtFileType, ArtifactRaw, Formatter, Parser, TextRaw};
+use crate::artifact_data::raw::{
+    to_toml_preserving, ArtFileType, ArtifactRaw, Formatter, Parser, TextRaw,
+};
 use crate::artifact_data::raw_names::NamesRaw;
 use artifact_test::raw::{arb_raw_artifacts, arts_from_json_str, arts_from_toml_str};
 
@@ -139,6 +141,117 @@ partof:
     assert_eq!(exp_raw_1, formatter.to_markdown(&exp_1));
 }
 
+#[test]
+fn sanity_toml_preserving() {
+    let orig = r#"# The requirements of the project.
+
+[REQ-b]
+# b is important
+text = "b text"
+
+[REQ-a]
+text = "a text"  # keep me
+
+[REQ-removed]
+text = "gone"
+"#;
+    let mut arts = arts_from_toml_str(orig).unwrap();
+    arts.remove(&name!("REQ-removed"));
+    arts.insert(
+        name!("REQ-b"),
+        ArtifactRaw {
+            done: Some("b is done".into()),
+            partof: None,
+            text: Some(TextRaw("b text".into())),
+        },
+    );
+    arts.insert(name!("REQ-new"), ArtifactRaw::empty());
+
+    let out = to_toml_preserving(orig, &arts);
+    println!("### Preserved:\n{}<END>", out);
+    assert_eq!(arts_from_toml_str(&out).unwrap(), arts);
+    assert!(out.starts_with("# The requirements of the project.\n"));
+    assert!(out.contains("# b is important"));
+    assert!(out.contains("text = \"a text\"  # keep me"));
+    assert!(!out.contains("REQ-removed"));
+    let (b, a) = (out.find("[REQ-b]").unwrap(), out.find("[REQ-a]").unwrap());
+    assert!(b < a, "order was not kept");
+
+    // unchanged artifacts are written exactly as they were
+    let arts = arts_from_toml_str(orig).unwrap();
+    assert_eq!(to_toml_preserving(orig, &arts), orig);
+}
+
+#[test]
+fn sanity_markdown_preamble() {
+    let orig = "# Design of foo\n\nSome introduction.\n\n# REQ-foo\nfoo text\n";
+    let parser = Parser::new();
+    let (arts, prose) = parser.from_markdown_prose(orig.as_bytes()).unwrap();
+    assert_eq!(prose.preamble, "# Design of foo\n\nSome introduction.");
+    assert!(prose.sections.is_empty());
+
+    let out = Formatter::default().to_markdown_prose(&prose, &arts);
+    assert_eq!(
+        out,
+        "# Design of foo\n\nSome introduction.\n\n\n# REQ-foo\nfoo text"
+    );
+    assert_eq!(
+        parser.from_markdown_prose(out.as_bytes()).unwrap(),
+        (arts, prose)
+    );
+
+    // a file without prose has none
+    let (_, prose) = parser.from_markdown_prose(&b"# REQ-foo\n"[..]).unwrap();
+    assert!(prose.is_empty());
+}
+
+#[test]
+fn sanity_markdown_sections() {
+    // the `settings` project puts its names at `##`
+    let (_, project) = read_project(INTEROP_TESTS_PATH.join("settings")).unwrap();
+    let parser = Parser::from_settings(&project.settings).unwrap();
+    let formatter = Formatter::from_settings(&project.settings);
+
+    let orig = "## REQ-a\na text\n\n# Chapter\nchapter text\n\n## REQ-b\nb text\n";
+    let (arts, prose) = parser.from_markdown_prose(orig.as_bytes()).unwrap();
+    assert_eq!(
+        arts.keys().cloned().collect::<Vec<_>>(),
+        vec![name!("REQ-a"), name!("REQ-b")]
+    );
+    assert_eq!(arts[&name!("REQ-a")].text, Some(TextRaw("a text".into())));
+    assert_eq!(prose.preamble, "");
+    assert_eq!(prose.sections[&name!("REQ-a")], "# Chapter\nchapter text");
+
+    let out = formatter.to_markdown_prose(&prose, &arts);
+    assert_eq!(
+        out,
+        "## REQ-a\na text\n\n\n# Chapter\nchapter text\n\n\n## REQ-b\nb text"
+    );
+    assert_eq!(
+        parser.from_markdown_prose(out.as_bytes()).unwrap(),
+        (arts.clone(), prose.clone())
+    );
+
+    // the section is kept when its artifact is removed
+    let mut arts = arts;
+    arts.remove(&name!("REQ-a"));
+    assert_eq!(
+        formatter.to_markdown_prose(&prose, &arts),
+        "## REQ-b\nb text\n\n\n# Chapter\nchapter text"
+    );
+
+    // a `#` line in a code block is part of the artifact
+    let orig = "## REQ-c\n```sh\n# comment\nls\n```\n~~~\n# other\n~~~\nc text\n";
+    let (arts, prose) = parser.from_markdown_prose(orig.as_bytes()).unwrap();
+    assert!(prose.is_empty(), "{:?}", prose);
+    assert_eq!(
+        arts[&name!("REQ-c")].text,
+        Some(TextRaw(
+            "```sh\n# comment\nls\n```\n~~~\n# other\n~~~\nc text\n".into()
+        ))
+    );
+}
+
 proptest! {
     #[test]
     fn fuzz_artifacts_serde(ref orig in arb_raw_artifacts(20)) {
@@ -0,0 +1,184 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.rules
+
+use artifact_data::rules::{MatcherRaw, Rule, RuleLevel, RuleRaw};
+use artifact_lib::query::glob_regex;
+use artifact_test::artifact::art;
+use artifact_test::dev_prelude::*;
+
+fn artifact(name: &str, file: &str, partof: &[&str], text: &str) -> Artifact {
+    Artifact {
+        text: text.into(),
+        ..art(name, file, partof, 0.5, 0.0)
+    }
+}
+
+fn rule(select: MatcherRaw, require: MatcherRaw, forbid: Option<MatcherRaw>) -> Rule {
+    let raw = RuleRaw {
+        name: "test".into(),
+        message: "test message".into(),
+        level: RuleLevel::Warn,
+        select: select,
+        require: require,
+        forbid: forbid,
+    };
+    expect!(Rule::from_raw(&raw))
+}
+
+#[test]
+fn sanity_glob_regex() {
+    let re = glob_regex("REQ-safety-*", true);
+    assert!(re.is_match("REQ-safety-brakes"));
+    assert!(re.is_match("req-SAFETY-brakes"));
+    assert!(!re.is_match("REQ-safety"));
+    assert!(!re.is_match("SPC-safety-brakes"));
+
+    let re = glob_regex("design/legacy/*.md", false);
+    assert!(re.is_match("design/legacy/old.md"));
+    assert!(!re.is_match("design/legacy/sub/old.md"));
+    assert!(!re.is_match("design/LEGACY/old.md"));
+    assert!(!re.is_match("design/legacy/old.toml"));
+    assert!(glob_regex("design/legacy/**", false).is_match("design/legacy/sub/old.md"));
+
+    let re = glob_regex("SPC-?", true);
+    assert!(re.is_match("SPC-a"));
+    assert!(!re.is_match("SPC-ab"));
+}
+
+#[test]
+fn sanity_rules() {
+    let base = Path::new("/project");
+    let legacy = artifact(
+        "SPC-legacy",
+        "/project/design/legacy/old.md",
+        &["REQ-new"],
+        "Old spec.\n\n## Rationale\nBecause.\n",
+    );
+    let other = artifact(
+        "SPC-other",
+        "/project/design/purpose.md",
+        &[],
+        "No reason.\n",
+    );
+
+    // require a rationale
+    let r = rule(
+        MatcherRaw {
+            ty: Some("spc".into()),
+            ..MatcherRaw::default()
+        },
+        MatcherRaw {
+            text: Some(r"(?m)^## Rationale\s*$".into()),
+            ..MatcherRaw::default()
+        },
+        None,
+    );
+    assert!(r.selects(base, &legacy) && r.selects(base, &other));
+    assert!(!r.is_violated(base, &legacy));
+    assert!(r.is_violated(base, &other));
+
+    // forbid legacy artifacts being partof new ones
+    let r = rule(
+        MatcherRaw {
+            file: Some("design/legacy/*".into()),
+            ..MatcherRaw::default()
+        },
+        MatcherRaw::default(),
+        Some(MatcherRaw {
+            partof: Some("REQ-new*".into()),
+            ..MatcherRaw::default()
+        }),
+    );
+    assert!(r.selects(base, &legacy));
+    assert!(!r.selects(base, &other));
+    assert!(r.is_violated(base, &legacy));
+
+    // `*` does not cross a `/`, `**` does
+    let select_file = |glob: &str| {
+        rule(
+            MatcherRaw {
+                file: Some(glob.into()),
+                ..MatcherRaw::default()
+            },
+            MatcherRaw::default(),
+            None,
+        )
+    };
+    let r = select_file("design/*.md");
+    assert!(r.selects(base, &other));
+    assert!(!r.selects(base, &legacy));
+    let r = select_file("design/**.md");
+    assert!(r.selects(base, &other));
+    assert!(r.selects(base, &legacy));
+
+    // completion ranges are in percent
+    let r = rule(
+        MatcherRaw::default(),
+        MatcherRaw {
+            min_spc: Some(50.0),
+            max_tst: Some(10.0),
+            ..MatcherRaw::default()
+        },
+        None,
+    );
+    assert!(!r.is_violated(base, &other));
+    let r = rule(
+        MatcherRaw::default(),
+        MatcherRaw {
+            min_spc: Some(60.0),
+            ..MatcherRaw::default()
+        },
+        None,
+    );
+    assert!(r.is_violated(base, &other));
+}
+
+#[test]
+fn sanity_rules_invalid() {
+    let invalid = |m: MatcherRaw| {
+        let raw = RuleRaw {
+            name: "bad".into(),
+            message: "bad".into(),
+            level: RuleLevel::Error,
+            select: m,
+            require: MatcherRaw::default(),
+            forbid: None,
+        };
+        Rule::from_raw(&raw).unwrap_err()
+    };
+    assert_eq!(
+        invalid(MatcherRaw {
+            ty: Some("FOO".into()),
+            ..MatcherRaw::default()
+        }),
+        "rule \"bad\" [select]: type must be REQ, SPC or TST, got \"FOO\""
+    );
+    assert_eq!(
+        invalid(MatcherRaw {
+            impl_: Some("maybe".into()),
+            ..MatcherRaw::default()
+        }),
+        "rule \"bad\" [select]: impl must be done, code or none, got \"maybe\""
+    );
+    assert!(invalid(MatcherRaw {
+        text: Some("(unclosed".into()),
+        ..MatcherRaw::default()
+    })
+    .starts_with("rule \"bad\" [select]: invalid text regex:"));
+}
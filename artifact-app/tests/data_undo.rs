@@ -0,0 +1,121 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.undo
+
+use artifact_test::artifact_data::undo::{self, UndoEntry, UndoHistory, MAX_UNDO};
+use artifact_test::artifact_data::{modify_project, ART_DIR};
+use artifact_test::*;
+
+/// A copy of the basic project.
+fn basic_copy(tmp: &tempdir::TempDir) -> PathDir {
+    let project_path = tmp.path().join("basic");
+    let (send_err, recv_err) = ch::bounded(128);
+    deep_copy(
+        send_err,
+        PathDir::new(INTEROP_TESTS_PATH.join("basic")).unwrap(),
+        project_path.clone(),
+    );
+    let errs: Vec<_> = recv_err.iter().collect();
+    assert!(errs.is_empty(), "Got IO Errors:\n{:#?}", errs);
+    PathDir::new(project_path).unwrap()
+}
+
+fn entry(timestamp: &str) -> UndoEntry {
+    UndoEntry {
+        timestamp: timestamp.to_string(),
+        ops: vec![],
+        inverse: vec![],
+    }
+}
+
+#[test]
+fn sanity_undo_inverse() {
+    let tmp = tempdir::TempDir::new("art-undo").unwrap();
+    let base = basic_copy(&tmp);
+    let (_, before) = read_project(&base).unwrap();
+
+    let foo = &before.artifacts[&name!("SPC-foo")];
+    let mut updated = ArtifactIm::from(foo.clone());
+    updated.text.push_str("\n\nMore foo.");
+    let mut created = ArtifactIm::from(foo.clone());
+    created.name = name!("SPC-created");
+    created.partof = indexset! {};
+    let deleted = &before.artifacts[&name!("TST-build")];
+    let ops = vec![
+        ArtifactOp::Update {
+            artifact: updated,
+            orig_id: foo.id,
+        },
+        ArtifactOp::Create { artifact: created },
+        ArtifactOp::Delete {
+            name: deleted.name.clone(),
+            orig_id: deleted.id,
+        },
+    ];
+
+    let entry = UndoEntry::new(&before, &ops, "now".to_string()).unwrap();
+    assert_eq!(
+        entry.summary(),
+        vec!["update SPC-foo", "create SPC-created", "delete TST-build"]
+    );
+    match entry.inverse[0] {
+        ArtifactOp::Create { ref artifact } => assert_eq!(artifact.name, deleted.name),
+        ref op => panic!("unexpected op: {:?}", op),
+    }
+
+    // applying the inverse restores the project
+    let (_, after) = modify_project(&base, entry.ops.clone()).unwrap();
+    assert_ne!(after.artifacts, before.artifacts);
+    let (_, restored) = modify_project(&base, entry.inverse.clone()).unwrap();
+    assert_eq!(restored.artifacts, before.artifacts);
+
+    // an unknown orig_id has no inverse
+    let ops = vec![ArtifactOp::Delete {
+        name: name!("SPC-foo"),
+        orig_id: HashIm([0; 16]),
+    }];
+    assert_eq!(UndoEntry::new(&restored, &ops, "now".to_string()), None);
+}
+
+#[test]
+fn sanity_undo_history() {
+    let mut history = UndoHistory::default();
+    for i in 0..MAX_UNDO + 2 {
+        history.push(entry(&i.to_string()));
+    }
+    assert_eq!(history.undo.len(), MAX_UNDO);
+    assert_eq!(history.undo[0].timestamp, "2");
+
+    // pushing a new entry clears what can be redone
+    let undone = history.undo.pop().unwrap();
+    history.redo.push(undone);
+    let items = history.items();
+    assert_eq!(items.len(), MAX_UNDO);
+    assert!(items[MAX_UNDO - 1].undone);
+    assert!(!items[MAX_UNDO - 2].undone);
+    history.push(entry("new"));
+    assert!(history.redo.is_empty());
+
+    // the history is persisted in the `.art` directory
+    let tmp = tempdir::TempDir::new("art-undo").unwrap();
+    let base = PathDir::create(tmp.path().join("project")).unwrap();
+    PathDir::create(base.join(ART_DIR)).unwrap();
+    assert_eq!(undo::load_undo(&base).unwrap(), UndoHistory::default());
+    undo::save_undo(&base, &history).unwrap();
+    assert_eq!(undo::load_undo(&base).unwrap(), history);
+}
@@ -0,0 +1,234 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Unit Tests:
+//! - #TST-unit.query
+
+use artifact_lib::query::{glob_regex, Query};
+use artifact_test::artifact::art;
+use artifact_test::dev_prelude::*;
+
+fn artifact(name: &str, file: &str, partof: &[&str], spc: f32, impl_: Impl) -> Artifact {
+    Artifact {
+        text: format!("the text of {}", name),
+        impl_: impl_,
+        ..art(name, file, partof, spc, 0.0)
+    }
+}
+
+fn artifacts() -> IndexMap<Name, Artifact> {
+    let done = || Impl::Done("done".into());
+    let mut out: IndexMap<Name, Artifact> = vec![
+        artifact("REQ-net", "/repo/design/net.md", &[], 0.5, Impl::NotImpl),
+        artifact(
+            "REQ-net-tcp",
+            "/repo/design/net/tcp.md",
+            &["REQ-net"],
+            0.0,
+            Impl::NotImpl,
+        ),
+        artifact("SPC-net", "/repo/design/net.md", &["REQ-net"], 1.0, done()),
+        artifact(
+            "SPC-net-tcp",
+            "/repo/design/net/tcp.md",
+            &["SPC-net", "REQ-net-tcp"],
+            0.0,
+            Impl::NotImpl,
+        ),
+        artifact(
+            "SPC-other",
+            "/repo/design/other.md",
+            &[],
+            0.25,
+            Impl::NotImpl,
+        ),
+    ]
+    .into_iter()
+    .map(|a| (a.name.clone(), a))
+    .collect();
+
+    // compute the parts from the partof
+    let partofs: Vec<(Name, Name)> = out
+        .values()
+        .flat_map(|a| a.partof.iter().map(move |p| (p.clone(), a.name.clone())))
+        .collect();
+    for (parent, child) in partofs {
+        out[&parent].parts.insert(child);
+    }
+    out
+}
+
+fn query(q: &str) -> Vec<String> {
+    let query = expect!(Query::from_str(q));
+    query
+        .select(&artifacts(), Path::new("/repo"))
+        .iter()
+        .map(|n| n.as_str().to_string())
+        .collect()
+}
+
+fn query_err(q: &str) -> (usize, String) {
+    match Query::from_str(q) {
+        Ok(q) => panic!("expected error: {:?}", q),
+        Err(err) => (err.column, err.msg),
+    }
+}
+
+#[test]
+fn sanity_query_attributes() {
+    assert_eq!(query("type:REQ"), vec!["REQ-net", "REQ-net-tcp"]);
+    assert_eq!(query("TYPE:req"), vec!["REQ-net", "REQ-net-tcp"]);
+    assert_eq!(query("name:spc-net*"), vec!["SPC-net", "SPC-net-tcp"]);
+    assert_eq!(query("name~tcp$"), vec!["REQ-net-tcp", "SPC-net-tcp"]);
+    assert_eq!(query("partof:REQ-net"), vec!["REQ-net-tcp", "SPC-net"]);
+    assert_eq!(
+        query("parts:SPC-*"),
+        vec!["REQ-net", "REQ-net-tcp", "SPC-net"]
+    );
+    assert_eq!(
+        query("file:design/*.md"),
+        vec!["REQ-net", "SPC-net", "SPC-other"]
+    );
+    assert_eq!(
+        query("file:design/net/**"),
+        vec!["REQ-net-tcp", "SPC-net-tcp"]
+    );
+    assert_eq!(query("text:\"OF SPC-other\""), vec!["SPC-other"]);
+    assert_eq!(query("impl:done"), vec!["SPC-net"]);
+    assert_eq!(
+        query("spc<0.5"),
+        vec!["REQ-net-tcp", "SPC-net-tcp", "SPC-other"]
+    );
+    assert_eq!(query("spc>=50%"), vec!["REQ-net", "SPC-net"]);
+    assert_eq!(query("spc=25%"), vec!["SPC-other"]);
+    assert_eq!(query("tst>0"), Vec::<String>::new());
+}
+
+#[test]
+fn sanity_query_graph() {
+    assert_eq!(
+        query("descendant-of:REQ-net"),
+        vec!["REQ-net-tcp", "SPC-net", "SPC-net-tcp"]
+    );
+    assert_eq!(query("descendant-of:SPC-net"), vec!["SPC-net-tcp"]);
+    assert_eq!(query("descendant-of:SPC-net-tcp"), Vec::<String>::new());
+    assert_eq!(
+        query("ancestor-of:SPC-net-tcp"),
+        vec!["REQ-net", "REQ-net-tcp", "SPC-net"]
+    );
+    assert_eq!(query("ancestor-of:REQ-*"), vec!["REQ-net"]);
+}
+
+#[test]
+fn sanity_query_boolean() {
+    assert_eq!(
+        query("type:SPC and spc<0.5 and partof~REQ-net and not impl:done"),
+        vec!["SPC-net-tcp"]
+    );
+    // juxtaposition is `and`
+    assert_eq!(query("type:SPC spc<0.5"), vec!["SPC-net-tcp", "SPC-other"]);
+    assert_eq!(
+        query("impl:done or name:SPC-other"),
+        vec!["SPC-net", "SPC-other"]
+    );
+    // `and` binds tighter than `or`
+    assert_eq!(
+        query("type:REQ and spc>0 or impl:done"),
+        vec!["REQ-net", "SPC-net"]
+    );
+    assert_eq!(query("type:REQ and (spc>0 or impl:done)"), vec!["REQ-net"]);
+    assert_eq!(query("NOT (type:SPC Or name:*-tcp)"), vec!["REQ-net"]);
+    assert_eq!(query("not not impl:done"), vec!["SPC-net"]);
+}
+
+#[test]
+fn sanity_query_errors() {
+    assert_eq!(
+        query_err("type:SPC and"),
+        (13, "unexpected end of query".to_string())
+    );
+    assert_eq!(
+        query_err("(type:SPC"),
+        (10, "expected ')' to close the '(' at column 1".to_string())
+    );
+    assert_eq!(query_err("type:SPC)"), (9, "unexpected ')'".to_string()));
+    assert_eq!(
+        query_err("type:SPC foo"),
+        (
+            10,
+            "expected FIELD:VALUE, 'and', 'or' or 'not' but got \"foo\"".to_string()
+        )
+    );
+    assert_eq!(
+        query_err("type:FOO"),
+        (1, "type must be REQ, SPC or TST, got \"FOO\"".to_string())
+    );
+    assert_eq!(
+        query_err("spc~0.5"),
+        (1, "invalid comparison \"~\"".to_string())
+    );
+    assert_eq!(query_err("name:\"abc"), (1, "unclosed quote".to_string()));
+    assert_eq!(query_err("impl:"), (1, "impl: has no value".to_string()));
+    assert!(query_err("spc<2").1.contains("between 0 and 1"));
+    assert!(query_err("color:red")
+        .1
+        .starts_with("unknown field \"color\""));
+    assert!(query_err("name~(").0 == 1);
+}
+
+#[test]
+fn sanity_query_glob() {
+    let re = glob_regex("design/*.md", false);
+    assert!(re.is_match("design/foo.md"));
+    assert!(!re.is_match("design/net/foo.md"));
+    assert!(!re.is_match("DESIGN/foo.md"));
+
+    let re = glob_regex("design/**", false);
+    assert!(re.is_match("design/net/foo.md"));
+
+    let re = glob_regex("req-?", true);
+    assert!(re.is_match("REQ-a"));
+    assert!(!re.is_match("REQ-ab"));
+}
+
+#[test]
+fn sanity_query_ser() {
+    // The web ui queries the serialized artifacts, which must give the same results.
+    let artifacts = artifacts();
+    let ser: IndexMap<Name, ArtifactSer> = artifacts
+        .iter()
+        .map(|(n, a)| {
+            let a: ArtifactSer = expect!(json::from_str(&expect!(json::to_string(a))));
+            (n.clone(), a)
+        })
+        .collect();
+    let base = Path::new("/repo");
+    for q in &[
+        "type:SPC",
+        "file:design/*.md",
+        "impl:done or spc<0.5",
+        "text:\"OF SPC-other\"",
+        "descendant-of:REQ-net and not type:REQ",
+    ] {
+        let query = expect!(Query::from_str(q));
+        assert_eq!(
+            query.select(&ser, base),
+            query.select(&artifacts, base),
+            "{}",
+            q
+        );
+    }
+}
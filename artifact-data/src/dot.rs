@@ -0,0 +1,510 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! A validating parser for the graphviz DOT language.
+//!
+//! This only checks that the graph is syntactically valid so that errors are
+//! caught before the graph is rendered (which happens in the browser).
+//!
+//! See the grammar at https://graphviz.gitlab.io/_pages/doc/info/lang.html
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::dev_prelude::*;
+
+/// An error in a DOT graph. `line` starts at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for DotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+type DotResult<T> = ::std::result::Result<T, DotError>;
+
+/// Validate that `text` is a syntactically valid DOT graph.
+///
+/// Artifact references (`[[ART-name]]` and `[[.sub]]`) are allowed anywhere a
+/// node can be, since they are replaced before rendering.
+pub fn validate_dot(text: &str) -> DotResult<()> {
+    let text = replace_references(text);
+    let tokens = lex(&text)?;
+    let mut parser = DotParser {
+        tokens: tokens,
+        pos: 0,
+        directed: false,
+    };
+    parser.graph()
+}
+
+/// Replace artifact references with an anonymous subgraph of a single node,
+/// which is how they are rendered.
+fn replace_references(text: &str) -> String {
+    let replaced = name::TEXT_REF_RE.replace_all(text, "{\"$0\"}");
+    name::TEXT_SUB_NAME_RE
+        .replace_all(&replaced, "{\"$0\"}")
+        .to_string()
+}
+
+// ------------------------------
+// -- LEXER
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tok {
+    Id(String),
+    /// A quoted or html string, which can never be a keyword.
+    Quoted,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Semi,
+    Comma,
+    Equal,
+    Colon,
+    /// `->`
+    Directed,
+    /// `--`
+    Undirected,
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Tok::Id(ref id) => write!(f, "{:?}", id),
+            Tok::Quoted => write!(f, "string"),
+            Tok::LBrace => write!(f, "'{{'"),
+            Tok::RBrace => write!(f, "'}}'"),
+            Tok::LBracket => write!(f, "'['"),
+            Tok::RBracket => write!(f, "']'"),
+            Tok::Semi => write!(f, "';'"),
+            Tok::Comma => write!(f, "','"),
+            Tok::Equal => write!(f, "'='"),
+            Tok::Colon => write!(f, "':'"),
+            Tok::Directed => write!(f, "'->'"),
+            Tok::Undirected => write!(f, "'--'"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    line: usize,
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    /// Whether only whitespace has been seen on this line.
+    line_start: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        match c {
+            Some('\n') => {
+                self.line += 1;
+                self.line_start = true;
+            }
+            Some(c) if !c.is_whitespace() => self.line_start = false,
+            _ => {}
+        }
+        c
+    }
+
+    fn err<T>(&self, line: usize, msg: String) -> DotResult<T> {
+        Err(DotError {
+            line: line,
+            msg: msg,
+        })
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn skip_block_comment(&mut self, start: usize) -> DotResult<()> {
+        let mut prev = '\0';
+        loop {
+            match self.bump() {
+                Some('/') if prev == '*' => return Ok(()),
+                Some(c) => prev = c,
+                None => return self.err(start, "unclosed comment".into()),
+            }
+        }
+    }
+
+    fn quoted(&mut self, start: usize) -> DotResult<()> {
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    self.bump();
+                }
+                Some('"') => return Ok(()),
+                Some(_) => {}
+                None => return self.err(start, "unclosed string".into()),
+            }
+        }
+    }
+
+    fn html(&mut self, start: usize) -> DotResult<()> {
+        let mut depth = 1;
+        loop {
+            match self.bump() {
+                Some('<') => depth += 1,
+                Some('>') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                Some(_) => {}
+                None => return self.err(start, "unclosed html string".into()),
+            }
+        }
+    }
+
+    fn word(&mut self, first: char) -> String {
+        let mut out = first.to_string();
+        while let Some(&c) = self.chars.peek() {
+            if c == '_' || c.is_alphanumeric() || !c.is_ascii() {
+                out.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        out
+    }
+
+    fn numeral(&mut self, first: char) -> String {
+        let mut out = first.to_string();
+        while let Some(&c) = self.chars.peek() {
+            if c == '.' || c.is_ascii_digit() {
+                out.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        out
+    }
+}
+
+fn lex(text: &str) -> DotResult<Vec<Token>> {
+    let mut lexer = Lexer {
+        chars: text.chars().peekable(),
+        line: 1,
+        line_start: true,
+    };
+    let mut out = Vec::new();
+    loop {
+        let line_start = lexer.line_start;
+        let line = lexer.line;
+        let c = match lexer.bump() {
+            Some(c) => c,
+            None => break,
+        };
+        let tok = match c {
+            c if c.is_whitespace() => continue,
+            '#' if line_start => {
+                // preprocessor output, ignored
+                lexer.skip_line();
+                continue;
+            }
+            '/' => match lexer.chars.peek() {
+                Some('/') => {
+                    lexer.skip_line();
+                    continue;
+                }
+                Some('*') => {
+                    lexer.bump();
+                    lexer.skip_block_comment(line)?;
+                    continue;
+                }
+                _ => return lexer.err(line, "unexpected character '/'".into()),
+            },
+            '{' => Tok::LBrace,
+            '}' => Tok::RBrace,
+            '[' => Tok::LBracket,
+            ']' => Tok::RBracket,
+            ';' => Tok::Semi,
+            ',' => Tok::Comma,
+            '=' => Tok::Equal,
+            ':' => Tok::Colon,
+            '"' => {
+                lexer.quoted(line)?;
+                Tok::Quoted
+            }
+            '<' => {
+                lexer.html(line)?;
+                Tok::Quoted
+            }
+            '-' => match lexer.chars.peek() {
+                Some('>') => {
+                    lexer.bump();
+                    Tok::Directed
+                }
+                Some('-') => {
+                    lexer.bump();
+                    Tok::Undirected
+                }
+                Some(&c) if c == '.' || c.is_ascii_digit() => Tok::Id(lexer.numeral('-')),
+                _ => return lexer.err(line, "unexpected character '-'".into()),
+            },
+            c if c == '.' || c.is_ascii_digit() => Tok::Id(lexer.numeral(c)),
+            c if c == '_' || c.is_alphabetic() || !c.is_ascii() => Tok::Id(lexer.word(c)),
+            c => return lexer.err(line, format!("unexpected character {:?}", c)),
+        };
+        out.push(Token {
+            tok: tok,
+            line: line,
+        });
+    }
+    Ok(out)
+}
+
+// ------------------------------
+// -- PARSER
+
+struct DotParser {
+    tokens: Vec<Token>,
+    pos: usize,
+    directed: bool,
+}
+
+impl DotParser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|t| &t.tok)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Tok> {
+        self.tokens.get(self.pos + offset).map(|t| &t.tok)
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.pos) {
+            Some(t) => t.line,
+            None => self.tokens.last().map(|t| t.line).unwrap_or(1),
+        }
+    }
+
+    fn err<T>(&self, expected: &str) -> DotResult<T> {
+        let got = match self.peek() {
+            Some(t) => t.to_string(),
+            None => "end of graph".to_string(),
+        };
+        self.err_msg(&format!("expected {} but got {}", expected, got))
+    }
+
+    fn err_msg<T>(&self, msg: &str) -> DotResult<T> {
+        Err(DotError {
+            line: self.line(),
+            msg: msg.to_string(),
+        })
+    }
+
+    fn next_is(&self, tok: &Tok) -> bool {
+        self.peek() == Some(tok)
+    }
+
+    fn next_is_keyword(&self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Tok::Id(ref id)) => id.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    fn eat(&mut self, tok: &Tok) -> bool {
+        if self.next_is(tok) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, tok: &Tok) -> DotResult<()> {
+        if self.eat(tok) {
+            Ok(())
+        } else {
+            self.err(&tok.to_string())
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.next_is_keyword(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_id(&mut self) -> bool {
+        match self.peek() {
+            Some(Tok::Id(_)) | Some(Tok::Quoted) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn id(&mut self) -> DotResult<()> {
+        if self.eat_id() {
+            Ok(())
+        } else {
+            self.err("an id")
+        }
+    }
+
+    /// `graph : [ strict ] (graph | digraph) [ ID ] '{' stmt_list '}'`
+    fn graph(&mut self) -> DotResult<()> {
+        self.eat_keyword("strict");
+        if self.eat_keyword("digraph") {
+            self.directed = true;
+        } else if !self.eat_keyword("graph") {
+            return self.err("'graph' or 'digraph'");
+        }
+        self.eat_id();
+        self.expect(&Tok::LBrace)?;
+        self.stmt_list()?;
+        self.expect(&Tok::RBrace)?;
+        if self.peek().is_some() {
+            return self.err("end of graph");
+        }
+        Ok(())
+    }
+
+    /// `stmt_list : [ stmt [ ';' ] stmt_list ]`
+    fn stmt_list(&mut self) -> DotResult<()> {
+        while !self.next_is(&Tok::RBrace) && self.peek().is_some() {
+            self.stmt()?;
+            self.eat(&Tok::Semi);
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self) -> DotResult<()> {
+        if self.next_is_keyword("graph")
+            || self.next_is_keyword("node")
+            || self.next_is_keyword("edge")
+        {
+            // attr_stmt
+            self.pos += 1;
+            return self.attr_list();
+        }
+
+        if self.peek_at(1) == Some(&Tok::Equal) && !self.next_is(&Tok::LBrace) {
+            // ID '=' ID
+            self.id()?;
+            self.expect(&Tok::Equal)?;
+            return self.id();
+        }
+
+        // node_stmt or edge_stmt
+        let is_subgraph = self.node_or_subgraph()?;
+        let mut is_edge = false;
+        loop {
+            match self.peek() {
+                Some(Tok::Directed) if !self.directed => {
+                    return self.err_msg("'->' cannot be used in an undirected graph, use '--'");
+                }
+                Some(Tok::Undirected) if self.directed => {
+                    return self.err_msg("'--' cannot be used in a directed graph, use '->'");
+                }
+                Some(Tok::Directed) | Some(Tok::Undirected) => {
+                    self.pos += 1;
+                    is_edge = true;
+                    self.node_or_subgraph()?;
+                }
+                _ => break,
+            }
+        }
+        if (is_edge || !is_subgraph) && self.next_is(&Tok::LBracket) {
+            self.attr_list()?;
+        }
+        Ok(())
+    }
+
+    /// Parse either a `node_id` or a `subgraph`, returning whether it was a subgraph.
+    fn node_or_subgraph(&mut self) -> DotResult<bool> {
+        if self.next_is_keyword("subgraph") || self.next_is(&Tok::LBrace) {
+            self.subgraph()?;
+            Ok(true)
+        } else {
+            self.node_id()?;
+            Ok(false)
+        }
+    }
+
+    /// `node_id : ID [ ':' ID [ ':' ID ] ]`
+    fn node_id(&mut self) -> DotResult<()> {
+        if !self.eat_id() {
+            return self.err("a node id, subgraph or statement");
+        }
+        if self.eat(&Tok::Colon) {
+            self.id()?;
+            if self.eat(&Tok::Colon) {
+                self.id()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `subgraph : [ subgraph [ ID ] ] '{' stmt_list '}'`
+    fn subgraph(&mut self) -> DotResult<()> {
+        if self.eat_keyword("subgraph") {
+            self.eat_id();
+        }
+        self.expect(&Tok::LBrace)?;
+        self.stmt_list()?;
+        self.expect(&Tok::RBrace)
+    }
+
+    /// `attr_list : '[' [ a_list ] ']' [ attr_list ]`
+    fn attr_list(&mut self) -> DotResult<()> {
+        if !self.next_is(&Tok::LBracket) {
+            return self.err("'['");
+        }
+        while self.eat(&Tok::LBracket) {
+            while !self.eat(&Tok::RBracket) {
+                // a_list : ID '=' ID [ (';' | ',') ] [ a_list ]
+                self.id()?;
+                self.expect(&Tok::Equal)?;
+                self.id()?;
+                if !self.eat(&Tok::Semi) {
+                    self.eat(&Tok::Comma);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Detection of duplicate and near-duplicate artifact text.
+//!
+//! Text is normalized into lowercase words which are hashed as a whole (for
+//! identical text) and as overlapping word "shingles" (for similar text).
+//!
+//! Comparing every pair of artifacts is too slow for large projects, so each
+//! text gets a MinHash signature which is split into bands. Only artifacts
+//! which share at least one band are compared directly.
+
+use rayon::prelude::*;
+use siphasher::sip::SipHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::dev_prelude::*;
+
+/// Number of words in a single shingle.
+const SHINGLE_WORDS: usize = 3;
+/// Number of hashes in a MinHash signature.
+const SIGNATURE_LEN: usize = 64;
+/// Number of signature values in each band.
+const BAND_ROWS: usize = 4;
+
+/// The hashed representation of an artifact's text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Hash of the full normalized text.
+    pub exact: u64,
+    /// Hashes of every shingle in the text.
+    pub shingles: IndexSet<u64>,
+    /// The MinHash signature of `shingles`.
+    pub signature: Vec<u64>,
+}
+
+impl Fingerprint {
+    /// Compute the fingerprint of already normalized words.
+    pub fn from_words(words: &[String]) -> Fingerprint {
+        let shingles: IndexSet<u64> = if words.len() <= SHINGLE_WORDS {
+            Some(hash_one(words)).into_iter().collect()
+        } else {
+            words.windows(SHINGLE_WORDS).map(|w| hash_one(w)).collect()
+        };
+
+        let mut signature = vec![u64::max_value(); SIGNATURE_LEN];
+        for shingle in shingles.iter() {
+            for (i, min) in signature.iter_mut().enumerate() {
+                let h = mix(*shingle ^ seed(i));
+                if h < *min {
+                    *min = h;
+                }
+            }
+        }
+
+        Fingerprint {
+            exact: hash_one(words),
+            shingles: shingles,
+            signature: signature,
+        }
+    }
+
+    /// The Jaccard similarity of the shingles, from `0.0` to `1.0`.
+    pub fn similarity(&self, other: &Fingerprint) -> f64 {
+        if self.exact == other.exact {
+            return 1.0;
+        }
+        let intersection = self
+            .shingles
+            .iter()
+            .filter(|s| other.shingles.contains(*s))
+            .count();
+        let union = self.shingles.len() + other.shingles.len() - intersection;
+        if union == 0 {
+            0.0
+        } else {
+            intersection as f64 / union as f64
+        }
+    }
+
+    /// Hashes of each band of the signature, used for bucketing.
+    fn bands(&self) -> Vec<u64> {
+        self.signature
+            .chunks(BAND_ROWS)
+            .enumerate()
+            .map(|(i, band)| hash_one(&(i, band)))
+            .collect()
+    }
+}
+
+/// Split the text into lowercase words, ignoring all punctuation and whitespace.
+pub fn normalize_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// A pair of artifacts whose text is the same or similar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Duplicate {
+    pub first: Name,
+    pub second: Name,
+    /// The similarity ratio, `1.0` is identical.
+    pub similarity: f64,
+}
+
+/// Find all pairs of artifacts whose text is at least `threshold` similar.
+///
+/// Texts with fewer than `min_words` words are ignored.
+pub fn find_duplicates(
+    artifacts: &IndexMap<Name, Artifact>,
+    threshold: f64,
+    min_words: usize,
+) -> Vec<Duplicate> {
+    let texts: Vec<(&Name, &str)> = artifacts
+        .iter()
+        .map(|(n, a)| (n, a.text.as_str()))
+        .collect();
+
+    let fingerprints: Vec<(&Name, Fingerprint)> = texts
+        .par_iter()
+        .filter_map(|(name, text)| {
+            let words = normalize_words(text);
+            if words.is_empty() || words.len() < min_words {
+                None
+            } else {
+                Some((*name, Fingerprint::from_words(&words)))
+            }
+        })
+        .collect();
+
+    // Bucket by each band. Any two artifacts in the same bucket are candidates.
+    let mut buckets: IndexMap<u64, Vec<usize>> = IndexMap::new();
+    for (i, (_, fp)) in fingerprints.iter().enumerate() {
+        for band in fp.bands() {
+            buckets.entry(band).or_insert_with(Vec::new).push(i);
+        }
+    }
+
+    let mut candidates: IndexSet<(usize, usize)> = IndexSet::new();
+    for members in buckets.values() {
+        for (x, a) in members.iter().enumerate() {
+            for b in members.iter().skip(x + 1) {
+                candidates.insert((*a, *b));
+            }
+        }
+    }
+    let candidates: Vec<(usize, usize)> = candidates.into_iter().collect();
+
+    let mut out: Vec<Duplicate> = candidates
+        .par_iter()
+        .filter_map(|&(a, b)| {
+            let (name_a, ref fp_a) = fingerprints[a];
+            let (name_b, ref fp_b) = fingerprints[b];
+            let similarity = fp_a.similarity(fp_b);
+            if similarity >= threshold {
+                let (first, second) = if name_a < name_b {
+                    (name_a, name_b)
+                } else {
+                    (name_b, name_a)
+                };
+                Some(Duplicate {
+                    first: first.clone(),
+                    second: second.clone(),
+                    similarity: similarity,
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    out.sort_by(|a, b| (&a.first, &a.second).cmp(&(&b.first, &b.second)));
+    out
+}
+
+/// #SPC-read-artifact.lint_duplicate_text
+/// Lint warnings against artifacts which have duplicated text.
+pub(crate) fn lint_duplicate_text(lints: &Sender<lint::Lint>, project: &Project) {
+    let settings = match project.settings.lint.duplicate_text {
+        Some(ref s) => s,
+        None => return,
+    };
+    let threshold = f64::from(settings.similarity.min(100)) / 100.0;
+
+    for dup in find_duplicates(&project.artifacts, threshold, settings.min_words) {
+        let first = &project.artifacts[&dup.first];
+        let second = &project.artifacts[&dup.second];
+        let similar = if first.text == second.text {
+            "identical to".to_string()
+        } else {
+            format!("{:.0}% similar to", dup.similarity * 100.0)
+        };
+        let lint = lint::Lint {
+            level: lint::Level::Warn,
+            path: Some(second.file.to_stfu8()),
+            line: None,
+            category: lint::Category::DuplicateText,
+            msg: format!(
+                "{} ({}) has text {} {} ({})",
+                second.name,
+                second.file.display(),
+                similar,
+                first.name,
+                first.file.display(),
+            ),
+        };
+        ch!(lints <- lint);
+    }
+}
+
+// HELPERS
+
+fn hash_one<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = SipHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The seed for the `i`th hash function of the signature.
+fn seed(i: usize) -> u64 {
+    mix((i as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+}
+
+/// The `splitmix64` finalizer, a cheap way to get a new hash from an old one.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,414 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-modify-edit
+//! High level edits of artifacts, which are resolved into `ArtifactOp`s.
+//!
+//! An `ArtifactOp` needs the exact hash id of the artifact it modifies. An
+//! `EditOp` only needs the artifact's name: the current id is looked up from
+//! the project, or checked against `expect_id` if it is given.
+
+use crate::dev_prelude::*;
+
+/// A high level edit of the project.
+///
+/// Files are either absolute or relative to the project base.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum EditOp {
+    /// Create a new artifact.
+    New {
+        name: Name,
+        file: String,
+        #[serde(default)]
+        partof: IndexSet<Name>,
+        #[serde(default)]
+        done: Option<String>,
+        #[serde(default)]
+        text: String,
+    },
+    /// Add to the `partof` of an artifact.
+    Link {
+        name: Name,
+        partof: IndexSet<Name>,
+        #[serde(default)]
+        expect_id: Option<HashIm>,
+    },
+    /// Remove from the `partof` of an artifact.
+    Unlink {
+        name: Name,
+        partof: IndexSet<Name>,
+        #[serde(default)]
+        expect_id: Option<HashIm>,
+    },
+    /// Set the attributes of an artifact. Attributes which are `None` are
+    /// left unchanged.
+    Set {
+        name: Name,
+        #[serde(default)]
+        text: Option<String>,
+        #[serde(default)]
+        partof: Option<IndexSet<Name>>,
+        #[serde(default)]
+        done: Option<String>,
+        /// Remove the `done` attribute.
+        #[serde(default)]
+        not_done: bool,
+        #[serde(default)]
+        expect_id: Option<HashIm>,
+    },
+    /// Move an artifact to another file.
+    Mv {
+        name: Name,
+        file: String,
+        #[serde(default)]
+        expect_id: Option<HashIm>,
+    },
+    /// Move all artifacts in the `from` files to `file`.
+    Merge { from: Vec<String>, file: String },
+    /// Split a file into one file per top level name prefix, i.e. `REQ-net`
+    /// and `SPC-net-tcp` are moved to `net.md`. The new files are created in
+    /// `dir`, which defaults to the directory of `file`.
+    Split {
+        file: String,
+        #[serde(default)]
+        dir: Option<String>,
+    },
+    /// Delete an artifact and, if `recursive`, all of its parts.
+    ///
+    /// The deleted artifacts are also removed from the `partof` of the
+    /// remaining artifacts.
+    Rm {
+        name: Name,
+        #[serde(default)]
+        recursive: bool,
+        #[serde(default)]
+        expect_id: Option<HashIm>,
+    },
+}
+
+#[derive(Debug, Fail)]
+pub enum EditError {
+    #[fail(display = "{} does not exist", name)]
+    NotFound { name: String },
+
+    #[fail(display = "{} already exists", name)]
+    Exists { name: String },
+
+    #[fail(display = "{} has id {} but {} was expected", name, actual, expected)]
+    IdMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[fail(display = "{} is not explicitly partof {}", name, partof)]
+    NotLinked { name: String, partof: String },
+}
+
+/// The state of the artifacts while the edits are resolved.
+struct Edits<'a> {
+    project: &'a Project,
+    /// Existing artifacts which were changed.
+    changed: IndexMap<Name, ArtifactIm>,
+    created: IndexMap<Name, ArtifactIm>,
+    removed: IndexSet<Name>,
+}
+
+impl<'a> Edits<'a> {
+    /// Get the current state of the artifact for modifying it.
+    fn get_mut(
+        &mut self,
+        name: &Name,
+        expect_id: Option<HashIm>,
+    ) -> result::Result<&mut ArtifactIm, EditError> {
+        if self.created.contains_key(name) {
+            return Ok(&mut self.created[name]);
+        }
+        let art = match self.project.artifacts.get(name) {
+            Some(art) if !self.removed.contains(name) => art,
+            _ => {
+                return Err(EditError::NotFound {
+                    name: name.to_string(),
+                });
+            }
+        };
+        if let Some(expected) = expect_id {
+            if expected != art.id {
+                return Err(EditError::IdMismatch {
+                    name: name.to_string(),
+                    expected: expected.to_string(),
+                    actual: art.id.to_string(),
+                });
+            }
+        }
+        Ok(self
+            .changed
+            .entry(name.clone())
+            .or_insert_with(|| ArtifactIm::from(art.clone())))
+    }
+
+    fn exists(&self, name: &Name) -> bool {
+        self.created.contains_key(name)
+            || (self.project.artifacts.contains_key(name) && !self.removed.contains(name))
+    }
+
+    fn remove(&mut self, name: &Name) {
+        if self.created.remove(name).is_none() {
+            self.changed.remove(name);
+            self.removed.insert(name.clone());
+        }
+    }
+
+    fn apply(&mut self, edit: EditOp) -> result::Result<(), EditError> {
+        match edit {
+            EditOp::New {
+                name,
+                file,
+                partof,
+                done,
+                text,
+            } => {
+                if self.exists(&name) {
+                    return Err(EditError::Exists {
+                        name: name.to_string(),
+                    });
+                }
+                let file = self.resolve_file(&file);
+                let artifact = ArtifactIm {
+                    name: name.clone(),
+                    file: file,
+                    partof: partof,
+                    done: done,
+                    text: text,
+                };
+                self.created.insert(name, artifact);
+            }
+            EditOp::Link {
+                name,
+                partof,
+                expect_id,
+            } => {
+                let art = self.get_mut(&name, expect_id)?;
+                art.partof.extend(partof);
+            }
+            EditOp::Unlink {
+                name,
+                partof,
+                expect_id,
+            } => {
+                let art = self.get_mut(&name, expect_id)?;
+                for p in partof.iter() {
+                    if !art.partof.remove(p) {
+                        return Err(EditError::NotLinked {
+                            name: name.to_string(),
+                            partof: p.to_string(),
+                        });
+                    }
+                }
+            }
+            EditOp::Set {
+                name,
+                text,
+                partof,
+                done,
+                not_done,
+                expect_id,
+            } => {
+                let art = self.get_mut(&name, expect_id)?;
+                if let Some(text) = text {
+                    art.text = text;
+                }
+                if let Some(partof) = partof {
+                    art.partof = partof;
+                }
+                if not_done {
+                    art.done = None;
+                }
+                if done.is_some() {
+                    art.done = done;
+                }
+            }
+            EditOp::Mv {
+                name,
+                file,
+                expect_id,
+            } => {
+                let file = self.resolve_file(&file);
+                self.get_mut(&name, expect_id)?.file = file;
+            }
+            EditOp::Merge { from, file } => {
+                let file = self.resolve_file(&file);
+                let from: Vec<PathSer> = from.iter().map(|f| self.resolve_file(f)).collect();
+                for name in self.names_in(|path| from.iter().any(|f| f == path)) {
+                    self.get_mut(&name, None)?.file = file.clone();
+                }
+            }
+            EditOp::Split { file, dir } => {
+                let file = self.resolve_file(&file);
+                let dir: PathBuf = match dir {
+                    Some(d) => self.resolve_file(&d).to_path_buf(),
+                    None => match file.parent() {
+                        Some(p) => p.to_path_buf(),
+                        None => self.project.settings.base.to_path_buf(),
+                    },
+                };
+                let ext = match file.extension() {
+                    Some(e) => e.to_string_lossy().to_string(),
+                    None => "md".to_string(),
+                };
+                for name in self.names_in(|path| path == &file) {
+                    let to = dir.join(format!("{}.{}", name_prefix(&name), ext));
+                    self.get_mut(&name, None)?.file = PathSer::from(to.as_path());
+                }
+            }
+            EditOp::Rm {
+                name,
+                recursive,
+                expect_id,
+            } => {
+                self.get_mut(&name, expect_id)?;
+                let mut remove = vec![name.clone()];
+                if recursive {
+                    remove.extend(self.descendants(&name));
+                }
+                for n in remove.iter() {
+                    self.remove(n);
+                }
+                self.unlink_removed(&remove)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The current artifacts whose file matches.
+    fn names_in<F>(&self, matches: F) -> Vec<Name>
+    where
+        F: Fn(&PathSer) -> bool,
+    {
+        let current = self
+            .project
+            .artifacts
+            .values()
+            .filter(|a| !self.removed.contains(&a.name) && !self.changed.contains_key(&a.name))
+            .map(|a| (&a.name, &a.file))
+            .chain(self.changed.values().map(|a| (&a.name, &a.file)))
+            .chain(self.created.values().map(|a| (&a.name, &a.file)));
+        current
+            .filter(|(_, file)| matches(*file))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// All existing artifacts which are recursively part of `name`.
+    fn descendants(&self, name: &Name) -> Vec<Name> {
+        let mut seen: IndexSet<Name> = IndexSet::new();
+        let mut todo = vec![name.clone()];
+        while let Some(n) = todo.pop() {
+            if let Some(art) = self.project.artifacts.get(&n) {
+                for part in art.parts.iter() {
+                    if part != name && self.exists(part) && seen.insert(part.clone()) {
+                        todo.push(part.clone());
+                    }
+                }
+            }
+        }
+        seen.into_iter().collect()
+    }
+
+    /// Remove the artifacts from the explicit `partof` of all other artifacts.
+    fn unlink_removed(&mut self, removed: &[Name]) -> result::Result<(), EditError> {
+        let mut linked: Vec<Name> = Vec::new();
+        {
+            let current = self
+                .project
+                .artifacts
+                .values()
+                .filter(|a| !self.removed.contains(&a.name))
+                .map(|a| (&a.name, &a.partof))
+                .chain(self.changed.values().map(|a| (&a.name, &a.partof)))
+                .chain(self.created.values().map(|a| (&a.name, &a.partof)));
+            for (name, partof) in current {
+                if removed.iter().any(|r| partof.contains(r)) {
+                    linked.push(name.clone());
+                }
+            }
+        }
+        for name in linked {
+            let art = self.get_mut(&name, None)?;
+            for r in removed.iter() {
+                art.partof.remove(r);
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_file(&self, file: &str) -> PathSer {
+        let base: &Path = &self.project.settings.base;
+        PathSer::from(base.join(file).as_path())
+    }
+
+    fn into_operations(self) -> Vec<ArtifactOp> {
+        let mut ops = Vec::new();
+        for name in self.removed.iter() {
+            ops.push(ArtifactOp::Delete {
+                name: name.clone(),
+                orig_id: self.project.artifacts[name].id,
+            });
+        }
+        for (name, artifact) in self.changed {
+            let orig_id = self.project.artifacts[&name].id;
+            if artifact.hash_im() == orig_id {
+                // the edits did not change anything
+                continue;
+            }
+            ops.push(ArtifactOp::Update {
+                artifact: artifact,
+                orig_id: orig_id,
+            });
+        }
+        for (_, artifact) in self.created {
+            ops.push(ArtifactOp::Create { artifact: artifact });
+        }
+        ops
+    }
+}
+
+/// The top level prefix of the name, i.e. `net` for `SPC-net-tcp`.
+pub fn name_prefix(name: &Name) -> String {
+    let prefix = name.as_str().split('-').nth(1);
+    expect!(prefix, "names always have a type").to_ascii_lowercase()
+}
+
+/// Resolve the edits into the operations for `modify_project`.
+///
+/// The edits are applied in order, so later edits see the result of earlier
+/// ones. Each artifact gets at most one operation.
+pub fn resolve_edits(
+    project: &Project,
+    edits: Vec<EditOp>,
+) -> result::Result<Vec<ArtifactOp>, EditError> {
+    let mut state = Edits {
+        project: project,
+        changed: IndexMap::new(),
+        created: IndexMap::new(),
+        removed: IndexSet::new(),
+    };
+    for edit in edits {
+        state.apply(edit)?;
+    }
+    Ok(state.into_operations())
+}
@@ -0,0 +1,208 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-cli.serve_git
+//! Run the local `git` binary on a project.
+//!
+//! This is used to read old revisions of a project and to commit the
+//! modifications made through the server. Nothing is ever pushed or fetched.
+use std::process::Command;
+
+use crate::dev_prelude::*;
+
+#[derive(Debug, Fail)]
+pub enum GitError {
+    #[fail(display = "`git {}` failed: {}", args, msg)]
+    Failed { args: String, msg: String },
+
+    #[fail(
+        display = "the artifact files have uncommitted changes, commit or revert them first: {}",
+        files
+    )]
+    Dirty { files: String },
+
+    #[fail(
+        display = "author must be in the form `Name <email>`, got: {:?}",
+        author
+    )]
+    InvalidAuthor { author: String },
+}
+
+/// Run a git command in the repo, returning its stdout.
+///
+/// The `files` (if any) are passed after `--`.
+pub fn git<P: AsRef<Path>>(repo: P, args: &[&str], files: &[PathBuf]) -> Result<Vec<u8>> {
+    let mut cmd = Command::new("git");
+    cmd.args(args).current_dir(repo.as_ref());
+    if !files.is_empty() {
+        cmd.arg("--").args(files);
+    }
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(GitError::Failed {
+            args: args.join(" "),
+            msg: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+        .into());
+    }
+    Ok(output.stdout)
+}
+
+/// The commit hash of git's `HEAD`.
+pub fn head<P: AsRef<Path>>(repo: P) -> Result<String> {
+    let out = git(repo, &["rev-parse", "HEAD"], &[])?;
+    Ok(String::from_utf8_lossy(&out).trim().to_string())
+}
+
+/// Export the directory `dir` as it is at the git revision `rev` into a
+/// temporary directory.
+///
+/// `dir` can be anywhere in the repository. The temporary directory is
+/// returned so that it lives as long as it is needed.
+pub fn export_revision(dir: &PathDir, rev: &str) -> Result<(tempdir::TempDir, PathDir)> {
+    let top = git(dir, &["rev-parse", "--show-toplevel"], &[])?;
+    let top = PathBuf::from(String::from_utf8_lossy(&top).trim());
+    let prefix = git(dir, &["rev-parse", "--show-prefix"], &[])?;
+    let prefix = String::from_utf8_lossy(&prefix).trim().to_string();
+
+    // `git archive` limits the paths to the current directory when it is not
+    // run from the top, so archive the tree of `dir` from the top instead.
+    let tree = format!("{}:{}", rev, prefix);
+    let archive = git(&top, &["archive", "--format=tar", &tree], &[])?;
+
+    let tmp = tempdir::TempDir::new("art-revision")?;
+    tar::Archive::new(&archive[..]).unpack(tmp.path())?;
+    let exported = PathDir::new(tmp.path())?;
+    Ok((tmp, exported))
+}
+
+/// The files a modification can write: the files of the artifacts the
+/// operations replace or delete, and the files of the artifacts being created
+/// or updated.
+pub fn modified_files(project: &Project, ops: &[ArtifactOp]) -> Vec<PathBuf> {
+    let mut files: IndexSet<PathBuf> = IndexSet::new();
+    for op in ops {
+        match *op {
+            ArtifactOp::Create { ref artifact } => {
+                files.insert(artifact.file.to_path_buf());
+            }
+            ArtifactOp::Update {
+                ref artifact,
+                ref orig_id,
+            } => {
+                if let Some(orig) = project.artifacts.values().find(|a| a.id == *orig_id) {
+                    files.insert(orig.file.to_path_buf());
+                }
+                files.insert(artifact.file.to_path_buf());
+            }
+            ArtifactOp::Delete { ref name, .. } => {
+                if let Some(orig) = project.artifacts.get(name) {
+                    files.insert(orig.file.to_path_buf());
+                }
+            }
+        }
+    }
+    files.into_iter().collect()
+}
+
+/// Fail if any of the files have changes which were not committed, so that
+/// the commit of a modification contains only that modification.
+pub fn ensure_clean(repo: &PathDir, files: &[PathBuf]) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let status = git(repo, &["status", "--porcelain"], files)?;
+    let dirty: Vec<_> = String::from_utf8_lossy(&status)
+        .lines()
+        .map(|l| l.get(3..).unwrap_or(l).to_string())
+        .collect();
+    if !dirty.is_empty() {
+        return Err(GitError::Dirty {
+            files: dirty.join(", "),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// The author has to be in the form `Name <email>`.
+pub fn check_author(author: &str) -> Result<()> {
+    if !(author.contains(" <") && author.ends_with('>')) {
+        return Err(GitError::InvalidAuthor {
+            author: author.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Commit only the changed files, leaving anything else which is staged
+/// alone.
+pub fn commit(
+    repo: &PathDir,
+    changed: &[PathBuf],
+    message: &str,
+    author: Option<&str>,
+) -> Result<()> {
+    if changed.is_empty() {
+        return Ok(());
+    }
+    // `-A` also stages the removed files
+    git(repo, &["add", "-A"], changed)?;
+    let mut args = vec!["commit", "-m", message];
+    let author = author.map(|a| format!("--author={}", a));
+    if let Some(ref a) = author {
+        args.push(a);
+    }
+    git(repo, &args, changed)?;
+    Ok(())
+}
+
+/// The commit message of the operations, i.e. `art: update SPC-foo`.
+///
+/// The names are listed by what happened to them in the body.
+pub fn commit_message(ops: &[ArtifactOp]) -> String {
+    let mut created = Vec::new();
+    let mut updated = Vec::new();
+    let mut deleted = Vec::new();
+    for op in ops {
+        match *op {
+            ArtifactOp::Create { ref artifact } => created.push(artifact.name.to_string()),
+            ArtifactOp::Update { ref artifact, .. } => updated.push(artifact.name.to_string()),
+            ArtifactOp::Delete { ref name, .. } => deleted.push(name.to_string()),
+        }
+    }
+
+    let mut out = match (created.len(), updated.len(), deleted.len()) {
+        (1, 0, 0) => format!("art: create {}", created[0]),
+        (0, 1, 0) => format!("art: update {}", updated[0]),
+        (0, 0, 1) => format!("art: delete {}", deleted[0]),
+        _ => format!("art: modify {} artifacts", ops.len()),
+    };
+    if ops.len() > 1 {
+        out.push('\n');
+        for &(what, ref names) in &[
+            ("created", &created),
+            ("updated", &updated),
+            ("deleted", &deleted),
+        ] {
+            if !names.is_empty() {
+                out.push_str(&format!("\n{}: {}", what, names.join(", ")));
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,174 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-history
+//! The completion history of a project.
+//!
+//! Every snapshot is one line of json appended to `.art/history.jsonl`, so
+//! that the file can be committed and merged like any other text file.
+
+use std::io::Write;
+
+use crate::dev_prelude::*;
+use crate::graph;
+use crate::settings::ART_DIR;
+
+/// The name of the history file in the `.art` directory.
+pub const HISTORY_FILE: &str = "history.jsonl";
+
+#[derive(Debug, Fail)]
+pub enum HistoryError {
+    #[fail(display = "{}:{}: invalid snapshot: {}", path, line, msg)]
+    InvalidSnapshot {
+        path: String,
+        line: usize,
+        msg: String,
+    },
+}
+
+/// #SPC-history.snapshot
+/// The completion of a project at a point in time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// When the snapshot was taken, in rfc3339 format.
+    pub timestamp: String,
+    /// The git commit of the project, if known.
+    #[serde(default)]
+    pub commit: Option<String>,
+    pub totals: SnapshotTotals,
+    pub lints: SnapshotLints,
+    /// The completion of every artifact.
+    pub artifacts: IndexMap<Name, Completed>,
+}
+
+/// The project wide totals of a snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotTotals {
+    pub artifacts: usize,
+    /// The mean spc completion of all artifacts.
+    pub spc: f32,
+    /// The mean tst completion of all artifacts.
+    pub tst: f32,
+}
+
+/// The number of lints when the snapshot was taken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotLints {
+    pub error: usize,
+    pub other: usize,
+}
+
+impl Snapshot {
+    /// Take a snapshot of the project.
+    pub fn new(
+        project: &Project,
+        lints: &lint::Categorized,
+        timestamp: String,
+        commit: Option<String>,
+    ) -> Snapshot {
+        let artifacts: IndexMap<Name, Completed> = project
+            .artifacts
+            .iter()
+            .map(|(name, art)| (name.clone(), art.completed))
+            .collect();
+        let (spc, tst) = mean_completed(artifacts.values());
+        Snapshot {
+            timestamp: timestamp,
+            commit: commit,
+            totals: SnapshotTotals {
+                artifacts: artifacts.len(),
+                spc: spc,
+                tst: tst,
+            },
+            lints: SnapshotLints {
+                error: lints.error.len(),
+                other: lints.other.len(),
+            },
+            artifacts: artifacts,
+        }
+    }
+}
+
+/// The current time in rfc3339 format, as used by `Snapshot::timestamp`.
+pub fn timestamp_now() -> String {
+    time::now_utc().rfc3339().to_string()
+}
+
+/// The mean `(spc, tst)` of the completions, or `(0, 0)` if there are none.
+pub fn mean_completed<'a, I>(completed: I) -> (f32, f32)
+where
+    I: IntoIterator<Item = &'a Completed>,
+{
+    let mut count = 0;
+    let (mut spc, mut tst) = (0.0, 0.0);
+    for c in completed {
+        count += 1;
+        spc += f64::from(c.spc);
+        tst += f64::from(c.tst);
+    }
+    if count == 0 {
+        return (0.0, 0.0);
+    }
+    (
+        graph::round_ratio(spc / f64::from(count)),
+        graph::round_ratio(tst / f64::from(count)),
+    )
+}
+
+/// The path to the history file of the project.
+pub fn history_path(base: &PathDir) -> PathBuf {
+    let base: &Path = base;
+    base.join(ART_DIR).join(HISTORY_FILE)
+}
+
+/// Append the snapshot to the history file, creating it if necessary.
+pub fn append_snapshot(base: &PathDir, snapshot: &Snapshot) -> Result<()> {
+    let path = history_path(base);
+    let line = json::to_string(snapshot)?;
+    let mut f = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(f, "{}", line)?;
+    Ok(())
+}
+
+/// #SPC-history.load
+/// Load all snapshots from the history file, oldest first.
+///
+/// A project without a history file has no snapshots.
+pub fn load_history(base: &PathDir) -> Result<Vec<Snapshot>> {
+    let path = history_path(base);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = PathFile::new(&path)?.read_string()?;
+    let mut out = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match json::from_str(line) {
+            Ok(s) => out.push(s),
+            Err(e) => {
+                let e = HistoryError::InvalidSnapshot {
+                    path: path.display().to_string(),
+                    line: i + 1,
+                    msg: e.to_string(),
+                };
+                return Err(e.into());
+            }
+        }
+    }
+    Ok(out)
+}
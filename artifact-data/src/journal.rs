@@ -0,0 +1,319 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-modify.journal
+//! Crash safe saving of artifact files.
+//!
+//! A save is a transaction recorded in `.art/journal.json`. The new contents
+//! of every file are written next to it as `<file>.arttmp` and the original
+//! is copied to `<file>.artbk`. Only once all of them (and their directory
+//! entries) are synced to disk is the journal marked as committed and the
+//! temporary files renamed over their targets. If the process dies part way
+//! through, [`recover`] uses the journal to either finish the save or restore
+//! the original files.
+
+use std::fs;
+use std::io::{self, Write};
+
+use crate::dev_prelude::*;
+use crate::settings::ART_DIR;
+
+/// The name of the journal file in the `.art` directory.
+pub const JOURNAL_FILE: &str = "journal.json";
+
+/// The extension appended to the new contents of a file.
+pub const ART_TMP_EXT: &str = "arttmp";
+
+/// The extension appended to the copy of the original file.
+pub const ART_BK_EXT: &str = "artbk";
+
+#[derive(Debug, Fail)]
+pub enum JournalError {
+    #[fail(display = "{}: invalid journal: {}", path, msg)]
+    InvalidJournal { path: String, msg: String },
+
+    #[fail(display = "{}: {}", path, msg)]
+    File { path: String, msg: String },
+}
+
+/// A save of the project which may still be in flight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Journal {
+    /// Whether all temporary files and backups were written. Before this
+    /// the original files are untouched, so the save can only be rolled back.
+    pub committed: bool,
+    pub files: Vec<JournalFile>,
+}
+
+/// A single file changed by the save.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalFile {
+    /// The artifact file being changed.
+    pub path: PathBuf,
+    /// The new contents to rename over `path`, `None` if it is removed.
+    pub tmp: Option<PathBuf>,
+    /// The copy of the original contents, `None` if the file is new.
+    pub backup: Option<PathBuf>,
+}
+
+/// The direction a transaction was recovered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovered {
+    /// The new contents were saved.
+    Forward,
+    /// The original files were restored.
+    Back,
+}
+
+/// The path to the journal file of the project.
+pub fn journal_path(base: &PathDir) -> PathBuf {
+    let base: &Path = base;
+    base.join(ART_DIR).join(JOURNAL_FILE)
+}
+
+/// Load the journal of an interrupted save, if there is one.
+pub fn load_journal(base: &PathDir) -> Result<Option<Journal>> {
+    let path = journal_path(base);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = PathFile::new(&path)?.read_string()?;
+    match json::from_str(&text) {
+        Ok(j) => Ok(Some(j)),
+        Err(e) => Err(JournalError::InvalidJournal {
+            path: path.display().to_string(),
+            msg: e.to_string(),
+        }
+        .into()),
+    }
+}
+
+/// Finish or undo an interrupted save.
+///
+/// A save which was not yet committed is always rolled back. Returns `None`
+/// if there was nothing to recover.
+pub fn recover(base: &PathDir, rollback: bool) -> Result<Option<Recovered>> {
+    let journal = match load_journal(base)? {
+        Some(j) => j,
+        None => return Ok(None),
+    };
+    if rollback || !journal.committed {
+        roll_back(base, &journal)?;
+        Ok(Some(Recovered::Back))
+    } else {
+        for warning in roll_forward(base, &journal)? {
+            warn!("{}", warning);
+        }
+        Ok(Some(Recovered::Forward))
+    }
+}
+
+/// Write the temporary files and backups of a save and commit the journal.
+///
+/// `writes` are the new contents of files and `removes` are the files which
+/// no longer hold any artifacts. On failure nothing is changed.
+pub(crate) fn begin(
+    base: &PathDir,
+    writes: IndexMap<PathBuf, String>,
+    removes: Vec<PathBuf>,
+) -> result::Result<Journal, JournalError> {
+    let mut journal = Journal {
+        committed: false,
+        files: Vec::with_capacity(writes.len() + removes.len()),
+    };
+    for path in writes.keys() {
+        journal.files.push(JournalFile {
+            tmp: Some(with_ext(path, ART_TMP_EXT)),
+            backup: backup_path(path),
+            path: path.clone(),
+        });
+    }
+    for path in removes {
+        journal.files.push(JournalFile {
+            tmp: None,
+            backup: backup_path(&path),
+            path: path,
+        });
+    }
+
+    write_journal(base, &journal)?;
+    if let Err(err) = prepare(&journal, &writes) {
+        let _ = roll_back(base, &journal);
+        return Err(err);
+    }
+    journal.committed = true;
+    if let Err(err) = write_journal(base, &journal) {
+        let _ = roll_back(base, &journal);
+        return Err(err);
+    }
+    Ok(journal)
+}
+
+/// Rename the temporary files over their targets, remove the files without
+/// artifacts and clean up.
+///
+/// Failing to remove a backup once the journal is gone does not affect the
+/// project, so those errors are returned as warnings.
+pub(crate) fn roll_forward(
+    base: &PathDir,
+    journal: &Journal,
+) -> result::Result<Vec<JournalError>, JournalError> {
+    for file in journal.files.iter() {
+        match file.tmp {
+            // a missing temporary file was already renamed
+            Some(ref tmp) if tmp.exists() => file_err(tmp, fs::rename(tmp, &file.path))?,
+            Some(_) => {}
+            None if file.path.exists() => file_err(&file.path, fs::remove_file(&file.path))?,
+            None => {}
+        }
+    }
+    // the renames must be on disk before the journal is gone
+    sync_parents(journal.files.iter().map(|f| f.path.as_path()))?;
+    remove_journal(base)?;
+
+    let mut warnings = Vec::new();
+    for backup in journal.files.iter().filter_map(|f| f.backup.as_ref()) {
+        if let Err(err) = remove_if_exists(backup) {
+            warnings.push(err);
+        }
+    }
+    Ok(warnings)
+}
+
+/// Restore the original files and remove everything else the save created.
+pub(crate) fn roll_back(base: &PathDir, journal: &Journal) -> result::Result<(), JournalError> {
+    for file in journal.files.iter() {
+        if let Some(ref tmp) = file.tmp {
+            remove_if_exists(tmp)?;
+        }
+        match file.backup {
+            Some(ref backup) if backup.exists() => {
+                // only restore files which may have been changed
+                if journal.committed {
+                    file_err(backup, fs::rename(backup, &file.path))?;
+                } else {
+                    remove_if_exists(backup)?;
+                }
+            }
+            Some(_) => {}
+            None if journal.committed => remove_if_exists(&file.path)?,
+            None => {}
+        }
+    }
+    remove_journal(base)
+}
+
+fn prepare(
+    journal: &Journal,
+    writes: &IndexMap<PathBuf, String>,
+) -> result::Result<(), JournalError> {
+    for file in journal.files.iter() {
+        if let Some(ref backup) = file.backup {
+            file_err(&file.path, fs::copy(&file.path, backup))?;
+            file_err(backup, sync_file(backup))?;
+        }
+        if let Some(ref tmp) = file.tmp {
+            // artifacts can be moved to a file in a new directory
+            if let Some(parent) = tmp.parent() {
+                file_err(parent, fs::create_dir_all(parent))?;
+            }
+            file_err(tmp, write_synced(tmp, writes[&file.path].as_bytes()))?;
+        }
+    }
+    // the new directory entries must be on disk before the journal is
+    // committed
+    sync_parents(journal.files.iter().map(|f| f.path.as_path()))
+}
+
+fn write_journal(base: &PathDir, journal: &Journal) -> result::Result<(), JournalError> {
+    let path = journal_path(base);
+    let tmp = with_ext(&path, ART_TMP_EXT);
+    let text = expect!(json::to_string_pretty(journal));
+    file_err(&tmp, write_synced(&tmp, text.as_bytes()))?;
+    file_err(&path, fs::rename(&tmp, &path))?;
+    sync_parents(Some(path.as_path()))
+}
+
+/// Write the file and wait until it is on disk.
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut f = fs::File::create(path)?;
+    f.write_all(contents)?;
+    f.sync_all()
+}
+
+/// Wait until the contents of an already written file are on disk.
+fn sync_file(path: &Path) -> io::Result<()> {
+    OpenOptions::new().write(true).open(path)?.sync_all()
+}
+
+/// Sync the directories containing the paths, so that files created, renamed
+/// or removed in them survive a power loss.
+fn sync_parents<'a, I>(paths: I) -> result::Result<(), JournalError>
+where
+    I: IntoIterator<Item = &'a Path>,
+{
+    let dirs: IndexSet<&Path> = paths.into_iter().filter_map(|p| p.parent()).collect();
+    for dir in dirs {
+        file_err(dir, sync_dir(dir))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    fs::File::open(dir)?.sync_all()
+}
+
+/// Directories can't be opened (and don't need to be synced) on windows.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn remove_journal(base: &PathDir) -> result::Result<(), JournalError> {
+    remove_if_exists(&journal_path(base))
+}
+
+fn remove_if_exists(path: &Path) -> result::Result<(), JournalError> {
+    match fs::remove_file(path) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        r => file_err(path, r),
+    }
+}
+
+/// The backup of the file, `None` if it does not exist yet.
+fn backup_path(path: &Path) -> Option<PathBuf> {
+    if path.exists() {
+        Some(with_ext(path, ART_BK_EXT))
+    } else {
+        None
+    }
+}
+
+/// Append an extension, so that `a.md` and `a.toml` never collide.
+fn with_ext(path: &Path, ext: &str) -> PathBuf {
+    let mut out = path.as_os_str().to_os_string();
+    out.push(".");
+    out.push(ext);
+    PathBuf::from(out)
+}
+
+fn file_err<T>(path: &Path, r: io::Result<T>) -> result::Result<(), JournalError> {
+    r.map(|_| ()).map_err(|err| JournalError::File {
+        path: path.display().to_string(),
+        msg: err.to_string(),
+    })
+}
@@ -31,6 +31,7 @@ use failure;
 #[macro_use]
 extern crate matches;
 use petgraph;
+use pulldown_cmark;
 use rayon;
 
 #[macro_use]
@@ -39,6 +40,7 @@ extern crate serde_derive;
 #[macro_use]
 extern crate failure_derive;
 use time;
+use toml_edit;
 
 #[macro_use]
 extern crate artifact_lib;
@@ -50,15 +52,26 @@ extern crate log;
 
 pub mod artifact;
 mod dev_prelude;
+pub mod dot;
+pub mod duplicate;
+pub mod edit;
+pub mod git;
 pub mod graph;
+pub mod history;
 pub mod implemented;
 mod intermediate;
+pub mod journal;
+pub mod links;
+pub mod md_lint;
+pub mod merge;
 mod modify;
 mod project;
 pub mod raw;
 #[macro_use]
 pub mod raw_names;
+pub mod rules;
 mod settings;
+pub mod undo;
 
 #[cfg(test)]
 #[macro_use]
@@ -71,6 +84,6 @@ extern crate pretty_assertions;
 // #[cfg(test)]
 // extern crate rand;
 
-pub use crate::modify::modify_project;
+pub use crate::modify::{modify_project, modify_project_changed, validate_modify};
 pub use crate::project::read_project;
 pub use crate::settings::{ART_DIR, SETTINGS_FILE};
@@ -0,0 +1,201 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Checking that links and images in artifact text point to something.
+//!
+//! Local paths are checked on the filesystem. `http(s)` urls are only checked
+//! against a user provided "known urls" file so that the lint works offline
+//! and is deterministic.
+//!
+//! The known urls file has one url per line. Blank lines and lines starting
+//! with `#` are ignored.
+//! - `https://example.com/page` allows exactly that url (ignoring any `#anchor`).
+//! - `https://example.com/docs/*` allows every url starting with the prefix.
+//! - `https://example.com/page 404` records the http status of the url, i.e.
+//!   from a cache of a previous (online) check. A status of 400 or more means
+//!   the url is broken.
+
+use crate::dev_prelude::*;
+use crate::md_lint;
+
+/// The result of looking up a url in the known urls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlStatus {
+    Ok,
+    /// The url is known to be broken with the http status.
+    Broken(u16),
+    /// The url is not in the known urls.
+    Unknown,
+}
+
+/// The parsed contents of a known urls file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct KnownUrls {
+    /// Exact urls with their status (if known).
+    pub exact: IndexMap<String, Option<u16>>,
+    /// Url prefixes (the `*` is stripped).
+    pub prefixes: Vec<String>,
+}
+
+impl KnownUrls {
+    /// Parse the known urls file, returning an error for any invalid lines.
+    pub fn from_text(text: &str) -> ::std::result::Result<KnownUrls, String> {
+        let mut out = KnownUrls::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let url = expect!(parts.next());
+            let status = match parts.next() {
+                Some(s) => Some(
+                    s.parse::<u16>()
+                        .map_err(|_| format!("line {}: invalid http status {:?}", i + 1, s))?,
+                ),
+                None => None,
+            };
+            if let Some(extra) = parts.next() {
+                return Err(format!("line {}: unexpected {:?}", i + 1, extra));
+            }
+            if url.ends_with('*') {
+                if status.is_some() {
+                    return Err(format!("line {}: prefixes cannot have a status", i + 1));
+                }
+                out.prefixes.push(url.trim_end_matches('*').to_string());
+            } else {
+                out.exact.insert(strip_anchor(url).to_string(), status);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Look up the status of the url.
+    pub fn status(&self, url: &str) -> UrlStatus {
+        let url = strip_anchor(url);
+        match self.exact.get(url) {
+            Some(Some(code)) if *code >= 400 => return UrlStatus::Broken(*code),
+            Some(_) => return UrlStatus::Ok,
+            None => {}
+        }
+        if self.prefixes.iter().any(|p| url.starts_with(p.as_str())) {
+            UrlStatus::Ok
+        } else {
+            UrlStatus::Unknown
+        }
+    }
+}
+
+/// Whether the link destination is an `http(s)` url.
+pub fn is_http(dest: &str) -> bool {
+    let lower = dest.trim().to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Whether the target of a local link exists.
+///
+/// Relative paths are resolved against `dir` (the directory of the artifact's
+/// file) and then against the project `base`. Paths starting with `/` are
+/// resolved against only the project `base`.
+///
+/// Returns `None` if the link is not to a local path.
+pub fn local_target_exists(dest: &str, dir: &Path, base: &Path) -> Option<bool> {
+    let dest = dest.trim();
+    if dest.starts_with('/') && !dest.starts_with("//") {
+        let path = md_lint::local_path(dest.trim_start_matches('/'))?;
+        return Some(base.join(path).exists());
+    }
+    let path = md_lint::local_path(dest)?;
+    Some(dir.join(&path).exists() || base.join(&path).exists())
+}
+
+/// #SPC-read-artifact.lint_links
+/// Lint warnings against links and images in artifact text which don't exist.
+pub(crate) fn lint_links(lints: &Sender<lint::Lint>, project: &Project) {
+    let settings = match project.settings.lint.links {
+        Some(ref s) => s,
+        None => return,
+    };
+    let base: &Path = project.settings.base.as_ref();
+
+    let known = match settings.known_urls {
+        Some(ref p) => match load_known_urls(base, p) {
+            Ok(k) => Some(k),
+            Err((path, err)) => {
+                let lint = lint::Lint {
+                    level: lint::Level::Error,
+                    path: Some(path),
+                    line: None,
+                    category: lint::Category::Links,
+                    msg: format!("invalid known urls file: {}", err),
+                };
+                ch!(lints <- lint);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    for (name, art) in project.artifacts.iter() {
+        let dir = art.file.parent().unwrap_or(base);
+        for link in md_lint::find_links(&art.text) {
+            let problem = if is_http(&link.dest) {
+                match known.as_ref().map(|k| k.status(&link.dest)) {
+                    Some(UrlStatus::Unknown) => "url is not in the known urls file".to_string(),
+                    Some(UrlStatus::Broken(code)) => format!("url is broken (status {})", code),
+                    Some(UrlStatus::Ok) | None => continue,
+                }
+            } else {
+                match local_target_exists(&link.dest, dir, base) {
+                    Some(false) => format!("{} target does not exist", link.kind()),
+                    Some(true) | None => continue,
+                }
+            };
+            let lint = lint::Lint {
+                level: lint::Level::Warn,
+                path: Some(art.file.to_stfu8()),
+                line: None,
+                category: lint::Category::Links,
+                msg: format!(
+                    "{} (text line {}): {}: {}",
+                    name, link.line, problem, link.dest
+                ),
+            };
+            ch!(lints <- lint);
+        }
+    }
+}
+
+fn load_known_urls(
+    base: &Path,
+    raw_path: &str,
+) -> ::std::result::Result<KnownUrls, (String, String)> {
+    // Same as the other settings paths, `{repo}/` and `/` are ignored
+    let p = raw_path
+        .trim_start_matches("{repo}")
+        .trim_start_matches('/');
+    let path = base.join(p);
+    let display = path.to_string_lossy().to_string();
+    let text = ::std::fs::read_to_string(&path).map_err(|e| (display.clone(), e.to_string()))?;
+    KnownUrls::from_text(&text).map_err(|e| (display, e))
+}
+
+fn strip_anchor(url: &str) -> &str {
+    match url.find('#') {
+        Some(i) => &url[..i],
+        None => url,
+    }
+}
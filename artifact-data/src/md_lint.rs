@@ -0,0 +1,382 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! Validation of the markdown structure of artifact text.
+//!
+//! The text is parsed with a CommonMark parser to find `dot` code blocks,
+//! links and images. Unclosed code fences and broken tables are not errors in
+//! CommonMark (they are silently closed or become paragraphs) so they are
+//! found by scanning the lines directly.
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+use crate::dev_prelude::*;
+use crate::dot;
+
+/// A problem found in the markdown of an artifact's text.
+///
+/// `line` is the line within the text, starting at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownIssue {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl MarkdownIssue {
+    fn new(line: usize, msg: String) -> MarkdownIssue {
+        MarkdownIssue {
+            line: line,
+            msg: msg,
+        }
+    }
+}
+
+/// A link or image in markdown text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownLink {
+    /// The line within the text, starting at 1.
+    pub line: usize,
+    pub dest: String,
+    pub image: bool,
+}
+
+impl MarkdownLink {
+    pub fn kind(&self) -> &'static str {
+        if self.image {
+            "image"
+        } else {
+            "link"
+        }
+    }
+}
+
+/// Validate the markdown in `text`.
+///
+/// Relative links and images are resolved against `dir` (the directory of the
+/// artifact's file). If `dir` is `None` they are not checked.
+pub fn validate_markdown(text: &str, dir: Option<&Path>) -> Vec<MarkdownIssue> {
+    let mut issues = Vec::new();
+    check_fences(text, &mut issues);
+    check_tables(text, &mut issues);
+    check_dot(text, &mut issues);
+    if let Some(dir) = dir {
+        for link in find_links(text) {
+            let exists = local_path(&link.dest).map(|p| dir.join(p).exists());
+            if exists == Some(false) {
+                issues.push(MarkdownIssue::new(
+                    link.line,
+                    format!("{} target does not exist: {}", link.kind(), link.dest),
+                ));
+            }
+        }
+    }
+    issues.sort_by(|a, b| a.line.cmp(&b.line));
+    issues
+}
+
+/// Find all links and images in the markdown text.
+pub fn find_links(text: &str) -> Vec<MarkdownLink> {
+    let mut out = Vec::new();
+    let mut parser = Parser::new_ext(text, Options::ENABLE_TABLES);
+    loop {
+        let (dest, image) = match parser.next() {
+            Some(Event::Start(Tag::Link(dest, _))) => (dest, false),
+            Some(Event::Start(Tag::Image(dest, _))) => (dest, true),
+            Some(_) => continue,
+            None => break,
+        };
+        out.push(MarkdownLink {
+            line: line_at(text, parser.get_offset()),
+            dest: dest.to_string(),
+            image: image,
+        });
+    }
+    out
+}
+
+/// #SPC-read-artifact.lint_markdown
+/// Lint warnings against artifacts with invalid markdown in their text.
+pub(crate) fn lint_markdown(lints: &Sender<lint::Lint>, project: &Project) {
+    if !project.settings.lint.markdown {
+        return;
+    }
+    // the links lint does a more thorough check of the same links
+    let check_links = project.settings.lint.links.is_none();
+    for (name, art) in project.artifacts.iter() {
+        let dir = if check_links { art.file.parent() } else { None };
+        for issue in validate_markdown(&art.text, dir) {
+            let lint = lint::Lint {
+                level: lint::Level::Warn,
+                path: Some(art.file.to_stfu8()),
+                line: None,
+                category: lint::Category::Markdown,
+                msg: format!("{} (text line {}): {}", name, issue.line, issue.msg),
+            };
+            ch!(lints <- lint);
+        }
+    }
+}
+
+// ------------------------------
+// -- LINE SCANNING
+
+/// An opening code fence: the fence character and its length.
+struct Fence {
+    ch: char,
+    len: usize,
+    line: usize,
+}
+
+/// Parse a line as a code fence, returning the fence char, length and the
+/// rest of the line (the info string).
+fn parse_fence(line: &str) -> Option<(char, usize, &str)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let line = &line[indent..];
+    let ch = match line.chars().next() {
+        Some(c) if c == '`' || c == '~' => c,
+        _ => return None,
+    };
+    let len = line.len() - line.trim_start_matches(ch).len();
+    if len < 3 {
+        return None;
+    }
+    let info = &line[len..];
+    if ch == '`' && info.contains('`') {
+        // backtick fences cannot have backticks in the info string
+        return None;
+    }
+    Some((ch, len, info))
+}
+
+/// Call `f` with the line number (starting at 1) of every line which is not
+/// inside a fenced code block. Returns the fence which is still open at the
+/// end of the text.
+fn scan_lines<F>(text: &str, mut f: F) -> Option<Fence>
+where
+    F: FnMut(usize, &str),
+{
+    let mut open: Option<Fence> = None;
+    for (i, line) in text.lines().enumerate() {
+        let line_num = i + 1;
+        let fence = parse_fence(line);
+        open = match (open, fence) {
+            (None, Some((ch, len, _))) => Some(Fence {
+                ch: ch,
+                len: len,
+                line: line_num,
+            }),
+            (Some(o), Some((ch, len, info))) => {
+                if ch == o.ch && len >= o.len && info.trim().is_empty() {
+                    None
+                } else {
+                    Some(o)
+                }
+            }
+            (Some(o), None) => Some(o),
+            (None, None) => {
+                f(line_num, line);
+                None
+            }
+        };
+    }
+    open
+}
+
+fn check_fences(text: &str, issues: &mut Vec<MarkdownIssue>) {
+    if let Some(open) = scan_lines(text, |_, _| {}) {
+        let fence: String = ::std::iter::repeat(open.ch).take(open.len).collect();
+        issues.push(MarkdownIssue::new(
+            open.line,
+            format!(
+                "code fence {:?} is never closed, it will swallow all following text",
+                fence
+            ),
+        ));
+    }
+}
+
+/// A table is a header row followed by a delimiter row (i.e. `|---|:--:|`).
+/// GFM requires both to have the same number of cells, otherwise the
+/// whole table is rendered as a paragraph.
+fn check_tables(text: &str, issues: &mut Vec<MarkdownIssue>) {
+    let mut prev: Option<(usize, String)> = None;
+    scan_lines(text, |line_num, line| {
+        if is_delimiter_row(line) {
+            if let Some((_, ref header)) = prev {
+                if header.contains('|') {
+                    let header_cells = table_cells(header);
+                    let delim_cells = table_cells(line);
+                    if header_cells != delim_cells {
+                        issues.push(MarkdownIssue::new(
+                            line_num,
+                            format!(
+                                "table header has {} columns but the delimiter row has {}",
+                                header_cells, delim_cells
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        prev = Some((line_num, line.to_string()));
+    });
+}
+
+fn is_delimiter_row(line: &str) -> bool {
+    let line = line.trim();
+    line.contains('|')
+        && line.contains('-')
+        && line
+            .chars()
+            .all(|c| c == '|' || c == '-' || c == ':' || c == ' ' || c == '\t')
+}
+
+/// The number of cells in a table row, ignoring leading/trailing pipes and
+/// escaped pipes.
+fn table_cells(line: &str) -> usize {
+    let line = line.trim();
+    let line = if line.starts_with('|') {
+        &line[1..]
+    } else {
+        line
+    };
+    let line = if line.ends_with('|') && !line.ends_with("\\|") {
+        &line[..line.len() - 1]
+    } else {
+        line
+    };
+    let mut cells = 1;
+    let mut escaped = false;
+    for c in line.chars() {
+        match c {
+            '\\' => {
+                escaped = !escaped;
+                continue;
+            }
+            '|' if !escaped => cells += 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    cells
+}
+
+// ------------------------------
+// -- PARSED CHECKS
+
+fn check_dot(text: &str, issues: &mut Vec<MarkdownIssue>) {
+    // (line, content) of the `dot` block currently being collected
+    let mut dot_block: Option<(usize, String)> = None;
+    let mut parser = Parser::new_ext(text, Options::ENABLE_TABLES);
+    loop {
+        let event = match parser.next() {
+            Some(e) => e,
+            None => break,
+        };
+        match event {
+            Event::Start(Tag::CodeBlock(ref info)) if is_dot_info(info) => {
+                // The offset is just past the opening fence.
+                let line = line_at(text, parser.get_offset());
+                dot_block = Some((line, String::new()));
+            }
+            Event::Text(ref t) => {
+                if let Some((_, ref mut content)) = dot_block {
+                    content.push_str(t);
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                if let Some((start, content)) = dot_block.take() {
+                    if let Err(err) = dot::validate_dot(&content) {
+                        issues.push(MarkdownIssue::new(
+                            start + err.line - 1,
+                            format!("invalid dot graph: {}", err.msg),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_dot_info(info: &str) -> bool {
+    info.split_whitespace().next() == Some("dot")
+}
+
+/// Get the relative file path a link destination refers to, if it is a
+/// relative link.
+///
+/// Returns `None` for urls (anything with a scheme), anchors and absolute
+/// paths.
+pub fn local_path(dest: &str) -> Option<String> {
+    let dest = dest.trim();
+    if dest.is_empty() || dest.starts_with('#') || dest.starts_with('/') || has_scheme(dest) {
+        return None;
+    }
+    let end = dest.find(|c| c == '#' || c == '?').unwrap_or(dest.len());
+    Some(percent_decode(&dest[..end]))
+}
+
+/// Whether the destination starts with a url scheme like `http:` or `mailto:`.
+fn has_scheme(dest: &str) -> bool {
+    match dest.find(':') {
+        Some(i) => {
+            let scheme = &dest[..i];
+            // a single letter is probably a windows drive
+            scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '.' || c == '-')
+        }
+        None => false,
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                out.push(hi * 16 + lo);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+/// The line number (starting at 1) of the byte offset.
+fn line_at(text: &str, offset: usize) -> usize {
+    let offset = offset.min(text.len());
+    text.as_bytes()[..offset]
+        .iter()
+        .filter(|b| **b == b'\n')
+        .count()
+        + 1
+}
@@ -0,0 +1,251 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-modify.merge
+//! Three-way merge of operations made on artifacts which changed since they
+//! were read.
+//!
+//! The artifact as it was read (the base) is compared to the requested
+//! artifact (ours) and the artifact as it is now (theirs). Each field is
+//! merged on its own: `text` by lines, `partof` as a set and the other fields
+//! only if at most one side changed them.
+
+use std::cmp;
+
+use crate::dev_prelude::*;
+
+/// Whether all of the `orig_id`s of the operations are in the project.
+pub fn is_current(project: &Project, ops: &[ArtifactOp]) -> bool {
+    let ids: IndexSet<HashIm> = project.artifacts.values().map(|art| art.id).collect();
+    ops.iter()
+        .all(|op| op.id_pieces().orig_id.map_or(true, |id| ids.contains(&id)))
+}
+
+/// Merge the operations whose `orig_id` is out of date with the changes made
+/// to the project since.
+///
+/// `bases` holds the artifacts which were handed out, by their id. Operations
+/// with an unknown `orig_id` are left alone, so they fail like any other out
+/// of date operation.
+pub fn rebase_ops(
+    project: &Project,
+    bases: &IndexMap<HashIm, ArtifactIm>,
+    ops: Vec<ArtifactOp>,
+) -> ::std::result::Result<Vec<ArtifactOp>, Vec<ArtifactConflict>> {
+    let ids: IndexSet<HashIm> = project.artifacts.values().map(|art| art.id).collect();
+    let mut out = Vec::with_capacity(ops.len());
+    let mut conflicts = Vec::new();
+    for op in ops {
+        let base = match op.id_pieces().orig_id {
+            Some(id) if !ids.contains(&id) => bases.get(&id),
+            _ => None,
+        };
+        let base = match base {
+            Some(b) => b,
+            None => {
+                out.push(op);
+                continue;
+            }
+        };
+
+        let theirs = project.artifacts.get(&base.name);
+        let theirs_id = theirs.map(|art| art.id);
+        let ours = match op {
+            ArtifactOp::Update { artifact, .. } => Some(artifact),
+            ArtifactOp::Delete { .. } => None,
+            ArtifactOp::Create { .. } => unreachable!("create has no orig_id"),
+        };
+        let conflict = |ours, theirs, fields| ArtifactConflict {
+            orig_id: base.hash_im(),
+            base: base.clone(),
+            ours: ours,
+            theirs: theirs,
+            theirs_id: theirs_id,
+            fields: fields,
+        };
+
+        match (ours, theirs) {
+            (Some(ours), Some(theirs)) => {
+                let theirs_im = ArtifactIm::from(theirs.clone());
+                match merge_artifact(base, &ours, &theirs_im) {
+                    Ok(merged) => out.push(ArtifactOp::Update {
+                        artifact: merged,
+                        orig_id: theirs.id,
+                    }),
+                    Err(fields) => conflicts.push(conflict(Some(ours), Some(theirs_im), fields)),
+                }
+            }
+            // both deleted it
+            (None, None) => {}
+            (ours, theirs) => {
+                let theirs = theirs.map(|art| ArtifactIm::from(art.clone()));
+                conflicts.push(conflict(ours, theirs, Vec::new()));
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        Ok(out)
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// Merge the changes of both sides to the artifact, returning the fields
+/// which conflict if it can't be done.
+pub fn merge_artifact(
+    base: &ArtifactIm,
+    ours: &ArtifactIm,
+    theirs: &ArtifactIm,
+) -> ::std::result::Result<ArtifactIm, Vec<String>> {
+    let mut conflicts = Vec::new();
+    let text = match merge_lines(&base.text, &ours.text, &theirs.text) {
+        Some(t) => t,
+        None => {
+            conflicts.push("text".to_string());
+            ours.text.clone()
+        }
+    };
+    let merged = ArtifactIm {
+        name: merge_value("name", &base.name, &ours.name, &theirs.name, &mut conflicts),
+        file: merge_value("file", &base.file, &ours.file, &theirs.file, &mut conflicts),
+        partof: merge_set(&base.partof, &ours.partof, &theirs.partof),
+        done: merge_value("done", &base.done, &ours.done, &theirs.done, &mut conflicts),
+        text: text,
+    };
+    if conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// A value which can only be merged if at most one side changed it.
+fn merge_value<T: Clone + PartialEq>(
+    field: &str,
+    base: &T,
+    ours: &T,
+    theirs: &T,
+    conflicts: &mut Vec<String>,
+) -> T {
+    if ours == base {
+        theirs.clone()
+    } else if theirs == base || ours == theirs {
+        ours.clone()
+    } else {
+        conflicts.push(field.to_string());
+        ours.clone()
+    }
+}
+
+/// Keep what both sides kept, and what either side added.
+///
+/// Removing an item on one side removes it. This never conflicts.
+pub fn merge_set(
+    base: &IndexSet<Name>,
+    ours: &IndexSet<Name>,
+    theirs: &IndexSet<Name>,
+) -> IndexSet<Name> {
+    ours.iter()
+        .chain(theirs.iter())
+        .filter(|n| (ours.contains(*n) && theirs.contains(*n)) || !base.contains(*n))
+        .cloned()
+        .collect()
+}
+
+/// Merge the lines changed by both sides, returning `None` if both changed
+/// the same lines differently.
+///
+/// Changes are separated by lines which neither side changed. Two changes
+/// which touch (with no unchanged line between them) conflict.
+pub fn merge_lines(base: &str, ours: &str, theirs: &str) -> Option<String> {
+    if ours == base {
+        return Some(theirs.to_string());
+    } else if theirs == base || ours == theirs {
+        return Some(ours.to_string());
+    }
+
+    // split on `\n` (not `lines`) so that a trailing newline is an (empty)
+    // last line and is kept by the `join`.
+    let base: Vec<&str> = base.split('\n').collect();
+    let ours: Vec<&str> = ours.split('\n').collect();
+    let theirs: Vec<&str> = theirs.split('\n').collect();
+    let ours_at = matching_lines(&base, &ours);
+    let theirs_at = matching_lines(&base, &theirs);
+
+    let mut out: Vec<&str> = Vec::new();
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    loop {
+        // the next line of the base which both sides kept
+        let stable = (b..base.len()).find(|&i| ours_at[i].is_some() && theirs_at[i].is_some());
+        let (b_end, o_end, t_end) = match stable {
+            Some(i) => (i, expect!(ours_at[i]), expect!(theirs_at[i])),
+            None => (base.len(), ours.len(), theirs.len()),
+        };
+        let (base_chunk, ours_chunk, theirs_chunk) =
+            (&base[b..b_end], &ours[o..o_end], &theirs[t..t_end]);
+        if ours_chunk == base_chunk {
+            out.extend_from_slice(theirs_chunk);
+        } else if theirs_chunk == base_chunk || ours_chunk == theirs_chunk {
+            out.extend_from_slice(ours_chunk);
+        } else {
+            return None;
+        }
+
+        match stable {
+            Some(i) => {
+                out.push(base[i]);
+                b = b_end + 1;
+                o = o_end + 1;
+                t = t_end + 1;
+            }
+            None => break,
+        }
+    }
+    Some(out.join("\n"))
+}
+
+/// The line of `other` matched to each line of `base` by their longest
+/// common subsequence.
+fn matching_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let (n, m) = (base.len(), other.len());
+    // lengths[i][j] is the length of the subsequence of base[i..] and other[j..]
+    let mut lengths = vec![vec![0_usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if base[i] == other[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                cmp::max(lengths[i + 1][j], lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = vec![None; n];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if base[i] == other[j] {
+            out[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    out
+}
@@ -2,21 +2,33 @@
 
 use std::error;
 use std::fmt;
+use std::fs;
+use std::io;
 
 use crate::artifact;
 use crate::dev_prelude::*;
 use crate::intermediate::ArtifactImExt;
+use crate::journal;
 use crate::project::{read_project, ProjectExt};
 use crate::raw;
-use crate::settings;
-
-static ART_BK_EXT: &str = "artbk";
 
 /// Perform a list of modifications to the project
 pub fn modify_project<P: AsRef<Path>>(
     project_path: P,
-    mut operations: Vec<ArtifactOp>,
+    operations: Vec<ArtifactOp>,
 ) -> ::std::result::Result<(lint::Categorized, Project), ModifyError> {
+    let (lints, project, _) = modify_project_changed(project_path, operations)?;
+    Ok((lints, project))
+}
+
+/// Perform a list of modifications to the project, also returning the files
+/// which were written or removed.
+///
+/// Files whose contents did not change are not touched.
+pub fn modify_project_changed<P: AsRef<Path>>(
+    project_path: P,
+    operations: Vec<ArtifactOp>,
+) -> ::std::result::Result<(lint::Categorized, Project, Vec<PathBuf>), ModifyError> {
     macro_rules! check_lints {
         ($lints:ident, $kind:ident) => {
             if !$lints.error.is_empty() {
@@ -38,15 +50,115 @@ pub fn modify_project<P: AsRef<Path>>(
     };
     check_lints!(lints, InvalidFromLoad);
 
+    check_journal(&mut lints, &original_project.settings.base);
+    check_lints!(lints, RecoveryRequired);
+
+    let original_files: IndexSet<PathBuf> = original_project
+        .artifacts
+        .values()
+        .map(|art| art.file.to_path_buf())
+        .collect();
+    let mut project = match apply_operations(&mut lints, original_project, operations) {
+        Ok(p) => p,
+        Err(kind) => {
+            lints.sort();
+            return Err(ModifyError {
+                lints: lints,
+                kind: kind,
+            });
+        }
+    };
+    check_lints!(lints, InvalidFromModify);
+
+    // #SPC-modify.backup
+    let (writes, removes) = project_files(&mut lints, &project, original_files);
+    check_lints!(lints, SaveProject);
+    let changed: Vec<PathBuf> = writes.keys().chain(removes.iter()).cloned().collect();
+    let journal = match journal::begin(&project.settings.base, writes, removes) {
+        Ok(j) => j,
+        Err(err) => {
+            let l = journal_lint(lint::Level::Error, lint::Category::CreateBackups, err);
+            lints.error.push(l);
+            check_lints!(lints, CreateBackups);
+            unreachable!()
+        }
+    };
+
+    match journal::roll_forward(&project.settings.base, &journal) {
+        Ok(warnings) => lints.categorize(
+            warnings
+                .into_iter()
+                .map(|err| journal_lint(lint::Level::Warn, lint::Category::RemoveBackups, err)),
+        ),
+        Err(err) => {
+            let mut l = journal_lint(lint::Level::Error, lint::Category::SaveProject, err);
+            l.msg = format!("{}; run `art recover` to finish or undo the save", l.msg);
+            lints.error.push(l);
+            check_lints!(lints, SaveProject);
+        }
+    }
+    project.sort();
+
+    Ok((lints, project, changed))
+}
+
+/// #SPC-modify.validate
+/// Check what a list of modifications would do to the project, without
+/// touching the disk.
+///
+/// The operations are applied to the artifacts in memory, returning the lints
+/// and the completion the artifacts would have.
+pub fn validate_modify(project: &Project, operations: Vec<ArtifactOp>) -> ModifyValidation {
+    let mut lints = lint::Categorized::default();
+    let (error, completed) = match apply_operations(&mut lints, project.clone(), operations) {
+        Ok(project) => {
+            let error = if lints.error.is_empty() {
+                None
+            } else {
+                Some(ModifyErrorKind::InvalidFromModify)
+            };
+            let completed = project
+                .artifacts
+                .iter()
+                .map(|(name, art)| (name.clone(), art.completed))
+                .collect();
+            (error, completed)
+        }
+        Err(kind) => (Some(kind), IndexMap::new()),
+    };
+    lints.sort();
+    ModifyValidation {
+        error: error,
+        lints: lints,
+        completed: completed,
+    }
+}
+
+/// Apply the operations to the project in memory and lint the result.
+///
+/// The project is returned even if it has lint errors, it is up to the caller
+/// to check them.
+fn apply_operations(
+    lints: &mut lint::Categorized,
+    original_project: Project,
+    mut operations: Vec<ArtifactOp>,
+) -> ::std::result::Result<Project, ModifyErrorKind> {
+    macro_rules! check_lints {
+        ($kind:ident) => {
+            if !lints.error.is_empty() {
+                return Err(ModifyErrorKind::$kind);
+            }
+        };
+    }
+
     // TODO: move this before even reading the project
-    check_overlap(&mut lints, &mut operations);
-    check_lints!(lints, InvalidPaths);
+    check_overlap(lints, &mut operations);
+    check_lints!(InvalidPaths);
 
-    check_paths(&mut lints, &original_project, &operations);
-    check_lints!(lints, InvalidPaths);
+    check_paths(lints, &original_project, &operations);
+    check_lints!(InvalidPaths);
 
     let mut artifacts = original_project.artifacts;
-
     let mut artifact_ims: IndexMap<HashIm, ArtifactIm> = artifacts
         .drain(..)
         .map(|(_, art)| {
@@ -55,8 +167,8 @@ pub fn modify_project<P: AsRef<Path>>(
         })
         .collect();
 
-    perform_operations(operations, &mut lints, &mut artifact_ims);
-    check_lints!(lints, HashMismatch);
+    perform_operations(operations, lints, &mut artifact_ims);
+    check_lints!(HashMismatch);
 
     let artifacts_im = artifact_ims.drain(..).map(|(_, a)| a).collect();
     let (send_errs, recv_errs) = ch::unbounded();
@@ -64,7 +176,7 @@ pub fn modify_project<P: AsRef<Path>>(
     let loaded = artifact::finalize_load_artifact(raw);
     let artifacts = artifact::determine_artifacts(loaded, &original_project.code_impls, &defined);
 
-    let mut project = Project {
+    let project = Project {
         settings: original_project.settings,
         code_impls: original_project.code_impls,
         artifacts: artifacts,
@@ -73,18 +185,7 @@ pub fn modify_project<P: AsRef<Path>>(
 
     drop(send_errs);
     lints.categorize(recv_errs.iter());
-    check_lints!(lints, InvalidFromModify);
-
-    create_backups(&mut lints, project.settings.clone());
-    check_lints!(lints, CreateBackups);
-
-    save_project(&mut lints, &project);
-    check_lints!(lints, SaveProject);
-
-    remove_backups(&mut lints, project.settings.clone());
-    project.sort();
-
-    Ok((lints, project))
+    Ok(project)
 }
 
 /// Make sure that
@@ -229,152 +330,136 @@ fn perform_operations(
     }
 }
 
-/// #SPC-modify.backup
-fn create_backups(lints: &mut lint::Categorized, paths: Settings) {
-    // TODO: figure out how to just use a reference
-    let paths = Arc::new(paths);
-    let recv_lint = {
-        let (send_lint, recv_lint) = ch::bounded(128);
-        let (send_path, recv_path) = ch::bounded(128);
-        take!(=send_lint as errs);
-        spawn(move || {
-            settings::walk_artifact_paths(
-                &send_path,
-                &errs,
-                &paths.artifact_paths,
-                &paths.exclude_artifact_paths,
-            )
+/// Refuse to modify a project with an interrupted save.
+fn check_journal(lints: &mut lint::Categorized, base: &PathDir) {
+    let path = journal::journal_path(base);
+    if path.exists() {
+        lints.error.push(lint::Lint {
+            level: lint::Level::Error,
+            path: Some(path.display().to_string()),
+            line: None,
+            category: lint::Category::SaveProject,
+            msg: "A previous save was interrupted, run `art recover` to finish or undo it"
+                .to_string(),
         });
-
-        for _ in 0..num_cpus::get() {
-            take!(=recv_path, =send_lint);
-            spawn(move || {
-                for path in recv_path {
-                    let bk = path.with_extension(ART_BK_EXT);
-                    if let Err(err) = path.clone().rename(bk) {
-                        let l = lint::Lint {
-                            level: lint::Level::Error,
-                            path: Some(path.to_stfu8()),
-                            line: None,
-                            category: lint::Category::CreateBackups,
-                            msg: err.to_string(),
-                        };
-                        ch!(send_lint <- l);
-                    }
-                }
-            });
-        }
-
-        recv_lint
-    };
-
-    lints.categorize(recv_lint.iter());
-}
-
-fn remove_backups(lints: &mut lint::Categorized, paths: Settings) {
-    // TODO: figure out how to just use a reference
-    let paths = Arc::new(paths);
-    let recv_lint = {
-        let (send_lint, recv_lint) = ch::bounded(128);
-        let (send_path, recv_path) = ch::bounded(128);
-        take!(=send_lint as errs);
-        spawn(move || {
-            settings::walk_paths(&send_path, &errs, &paths.artifact_paths, |path| {
-                let abs: &PathAbs = path.as_ref();
-                if path.is_dir() || path.extension() == Some(OsStr::new(ART_BK_EXT)) {
-                    // only include "backup" files or any directories not in exclude
-                    !paths.exclude_artifact_paths.contains(abs)
-                } else {
-                    false
-                }
-            })
-        });
-
-        for _ in 0..num_cpus::get() {
-            take!(=recv_path, =send_lint);
-            spawn(move || {
-                for path in recv_path {
-                    if let Err(err) = path.clone().remove() {
-                        let l = lint::Lint {
-                            level: lint::Level::Warn,
-                            path: Some(path.to_stfu8()),
-                            line: None,
-                            category: lint::Category::RemoveBackups,
-                            msg: err.to_string(),
-                        };
-                        ch!(send_lint <- l);
-                    }
-                }
-            });
-        }
-
-        recv_lint
-    };
-
-    lints.categorize(recv_lint.iter());
+    }
 }
 
-/// Save the project to disk, recording any lints along the way
-fn save_project(lints: &mut lint::Categorized, project: &Project) {
+/// The serialized contents of the artifact files which changed, and the
+/// original files which no longer hold any artifacts or prose.
+///
+/// An original file which can't be read is an error, since writing it from
+/// scratch would lose its formatting.
+fn project_files(
+    lints: &mut lint::Categorized,
+    project: &Project,
+    original_files: IndexSet<PathBuf>,
+) -> (IndexMap<PathBuf, String>, Vec<PathBuf>) {
     // split up the artifacts into their relevant files
-    let mut files: IndexMap<PathSer, IndexMap<Name, raw::ArtifactRaw>> = IndexMap::new();
+    let mut files: IndexMap<PathBuf, IndexMap<Name, raw::ArtifactRaw>> = IndexMap::new();
     for art in project.artifacts.values() {
         let art = ArtifactIm::from(art.clone());
         let (file, name, raw) = art.into_raw();
-        let entry = files.entry(file).or_insert_with(IndexMap::new);
+        let entry = files
+            .entry(file.to_path_buf())
+            .or_insert_with(IndexMap::new);
         entry.insert(name, raw);
     }
 
-    let formatter = Arc::new(raw::Formatter::from_settings(&project.settings));
-
-    let recv_lint = {
-        let (send_lint, recv_lint) = ch::bounded(128);
-        let (send_arts, recv_arts) = ch::bounded(128);
-
-        for _ in 0..num_cpus::get() {
-            take!(=send_lint, =recv_arts, =formatter);
-            spawn(move || {
-                for (path, arts) in recv_arts {
-                    let path: PathSer = path;
-                    macro_rules! handle_err {
-                        [$result:expr] => {
-                            match $result {
-                                Ok(v) => v,
-                                Err(err) => {
-                                    let l = lint::Lint {
-                                        level: lint::Level::Error,
-                                        path: Some(path.to_stfu8()),
-                                        line: None,
-                                        category: lint::Category::SaveProject,
-                                        msg: err.to_string(),
-                                    };
-                                    ch!(send_lint <- l);
-                                    continue;
-                                }
-                            }
-                        };
-                    }
+    let formatter = raw::Formatter::from_settings(&project.settings);
+    let parser = expect!(raw::Parser::from_settings(&project.settings));
+    // keep what a human wrote that isn't part of an artifact
+    let prose = |orig: &str| match parser.from_markdown_prose(orig.as_bytes()) {
+        Ok((_, prose)) => prose,
+        Err(_) => raw::MarkdownProse::default(),
+    };
 
-                    let file = handle_err!(PathFile::create(&path));
-                    let mut arts: IndexMap<Name, raw::ArtifactRaw> = arts;
-                    arts.sort_keys();
-                    let text = match raw::ArtFileType::from_path(&file) {
-                        Some(raw::ArtFileType::Toml) => expect!(toml::to_string(&arts)),
-                        Some(raw::ArtFileType::Md) => formatter.to_markdown(&arts),
-                        Some(raw::ArtFileType::Json) => expect!(json::to_string(&arts)),
-                        None => unreachable!(),
-                    };
-                    handle_err!(file.write_str(&text));
+    let mut texts: IndexMap<PathBuf, (Option<String>, String)> = files
+        .drain(..)
+        .map(|(path, mut arts)| {
+            arts.sort_keys();
+            let orig = read_original(lints, &path);
+            let text = match (raw::ArtFileType::from_path(&path), &orig) {
+                (Some(raw::ArtFileType::Toml), Some(orig)) => raw::to_toml_preserving(orig, &arts),
+                (Some(raw::ArtFileType::Toml), None) => expect!(toml::to_string(&arts)),
+                (Some(raw::ArtFileType::Md), Some(orig)) => {
+                    formatter.to_markdown_prose(&prose(orig), &arts)
                 }
-            });
+                (Some(raw::ArtFileType::Md), None) => formatter.to_markdown(&arts),
+                (Some(raw::ArtFileType::Json), _) => expect!(json::to_string(&arts)),
+                (None, _) => unreachable!(),
+            };
+            (path, (orig, text))
+        })
+        .collect();
+
+    // files without artifacts are removed, unless they are also a document
+    let mut removes = Vec::new();
+    for path in original_files {
+        if texts.contains_key(&path) {
+            continue;
         }
+        let orig = match raw::ArtFileType::from_path(&path) {
+            Some(raw::ArtFileType::Md) => read_original(lints, &path),
+            _ => None,
+        };
+        let kept = orig
+            .as_ref()
+            .map(|o| prose(o.as_str()))
+            .filter(|p| !p.is_empty());
+        match kept {
+            Some(p) => {
+                let text = formatter.to_markdown_prose(&p, &IndexMap::new());
+                texts.insert(path, (orig, text));
+            }
+            None => removes.push(path),
+        }
+    }
+
+    // only touch the files whose contents changed
+    let writes = texts
+        .into_iter()
+        .filter(|(_, (orig, text))| orig.as_ref() != Some(text))
+        .map(|(path, (_, text))| (path, text))
+        .collect();
+    (writes, removes)
+}
 
-        for arts in files.drain(..) {
-            ch!(send_arts <- arts);
+/// The contents of an artifact file, `None` if it doesn't exist (yet).
+fn read_original(lints: &mut lint::Categorized, path: &Path) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(text) => Some(text),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => {
+            lints.error.push(lint::Lint {
+                level: lint::Level::Error,
+                path: Some(path.display().to_string()),
+                line: None,
+                category: lint::Category::SaveProject,
+                msg: format!(
+                    "Could not read the file to preserve its formatting: {}",
+                    err
+                ),
+            });
+            None
         }
+    }
+}
 
-        recv_lint
+fn journal_lint(
+    level: lint::Level,
+    category: lint::Category,
+    err: journal::JournalError,
+) -> lint::Lint {
+    let (path, msg) = match err {
+        journal::JournalError::InvalidJournal { path, msg }
+        | journal::JournalError::File { path, msg } => (path, msg),
     };
-
-    lints.categorize(recv_lint.iter());
+    lint::Lint {
+        level: level,
+        path: Some(path),
+        line: None,
+        category: category,
+        msg: msg,
+    }
 }
@@ -4,8 +4,12 @@ use time;
 
 use crate::artifact;
 use crate::dev_prelude::*;
+use crate::duplicate;
 use crate::implemented;
+use crate::links;
+use crate::md_lint;
 use crate::raw;
+use crate::rules;
 use crate::settings;
 
 pub trait ProjectExt {
@@ -36,6 +40,11 @@ impl ProjectExt for Project {
         lint_artifact_text_refs(send, self);
         lint_code_impls(send, self);
         lint_settings(send, self);
+        lint_subnames(send, self);
+        duplicate::lint_duplicate_text(send, self);
+        md_lint::lint_markdown(send, self);
+        links::lint_links(send, self);
+        rules::lint_rules(send, self);
     }
 }
 
@@ -411,3 +420,83 @@ pub(crate) fn lint_artifact_text_refs(lints: &Sender<lint::Lint>, project: &Proj
         }
     }
 }
+
+/// #SPC-read-artifact.lint_subnames
+/// Lint warnings against subnames which are unused or look misspelled.
+pub(crate) fn lint_subnames(lints: &Sender<lint::Lint>, project: &Project) {
+    if !project.settings.lint.subnames {
+        return;
+    }
+    let send_lint = |name: &Name, sub: &SubName, file: &PathSer, msg: &str| {
+        let lint = lint::Lint {
+            level: lint::Level::Warn,
+            path: Some(file.to_stfu8()),
+            line: None,
+            category: lint::Category::Subname,
+            msg: format!("{}: subname [[{}]] {}", name, sub, msg),
+        };
+        ch!(lints <- lint);
+    };
+
+    // All `[[ART-name.sub]]` references made from a _different_ artifact.
+    let mut referenced: IndexSet<(Name, SubName)> = IndexSet::new();
+    for (name, art) in project.artifacts.iter() {
+        for captures in name::TEXT_REF_RE.captures_iter(&art.text) {
+            // expect: group "name" always exists in regex
+            let name_mat = expect!(captures.name(name::NAME_RE_KEY));
+            // expect: pre-validated by regex
+            let ref_name = expect!(Name::from_str(name_mat.as_str()));
+            if &ref_name == name {
+                continue;
+            }
+            if let Some(sub_mat) = captures.name(name::NAME_SUB_RE_KEY) {
+                referenced.insert((ref_name, SubName::new_unchecked(sub_mat.as_str())));
+            }
+        }
+    }
+
+    for (name, art) in project.artifacts.iter() {
+        if art.impl_.is_done() {
+            // subnames with `done` is already an error
+            continue;
+        }
+        let implemented: Vec<&SubName> = match project.code_impls.get(name) {
+            Some(code) => code.secondary.keys().collect(),
+            None => Vec::new(),
+        };
+
+        for sub in art.subnames.iter() {
+            let fuzzy = fuzzy_subname(sub);
+            let similar = implemented
+                .iter()
+                .find(|imp| **imp != sub && fuzzy_subname(imp) == fuzzy);
+            if let Some(imp) = similar {
+                send_lint(
+                    name,
+                    sub,
+                    &art.file,
+                    &format!(
+                        "differs only by underscores from implemented subname {}",
+                        imp
+                    ),
+                );
+                continue;
+            }
+
+            let is_implemented = implemented.iter().any(|imp| *imp == sub);
+            if !is_implemented && !referenced.contains(&(name.clone(), sub.clone())) {
+                send_lint(
+                    name,
+                    sub,
+                    &art.file,
+                    "is not implemented and is not referenced by any other artifact",
+                );
+            }
+        }
+    }
+}
+
+/// The subname's key with underscores removed, used to detect misspellings.
+fn fuzzy_subname(sub: &SubName) -> String {
+    sub.key_str().chars().filter(|c| *c != '_').collect()
+}
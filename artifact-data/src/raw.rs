@@ -131,6 +131,24 @@ pub(crate) fn join_artifacts_raw(
 /// Currently only parses markdown
 pub struct Parser {
     pub md_name_line_re: Regex,
+    /// Headings above the level of the artifact names, which start a
+    /// section that isn't part of any artifact.
+    pub md_section_re: Option<Regex>,
+}
+
+/// The parts of a markdown file which are not artifacts.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MarkdownProse {
+    /// The text before the first artifact.
+    pub preamble: String,
+    /// The sections between artifacts, by the artifact they follow.
+    pub sections: IndexMap<Name, String>,
+}
+
+impl MarkdownProse {
+    pub fn is_empty(&self) -> bool {
+        self.preamble.is_empty() && self.sections.is_empty()
+    }
 }
 
 impl Parser {
@@ -140,12 +158,14 @@ impl Parser {
     pub fn new() -> Self {
         Parser {
             md_name_line_re: expect!(Self::get_re(&SettingsMdName::default())),
+            md_section_re: None,
         }
     }
 
     pub fn from_settings(settings: &Settings) -> std::result::Result<Parser, String> {
         Ok(Parser {
             md_name_line_re: Self::get_re(&settings.parse.md_name)?,
+            md_section_re: Self::get_section_re(&settings.parse.md_name),
         })
     }
 
@@ -156,6 +176,17 @@ impl Parser {
         Regex::new(&pat).map_err(|e| format!("name pattern did not work with settings: {}", e))
     }
 
+    /// Only a prefix of `#` can put the names below other headings, i.e.
+    /// `# Chapter` is above `## REQ-foo`.
+    fn get_section_re(set_name: &SettingsMdName) -> Option<Regex> {
+        let prefix = set_name.to_prefix_string();
+        if prefix.is_empty() || prefix.chars().any(|c| c != '#') {
+            return None;
+        }
+        let pat = format!(r"^#{{1,{}}}(\s|$)", prefix.len());
+        Some(expect!(Regex::new(&pat)))
+    }
+
     /// Load artifacts from a file.
     ///
     /// Any Errors are converted into lints.
@@ -200,6 +231,77 @@ impl Parser {
     }
 }
 
+// ------------------------------
+// -- TOML
+
+/// The fields of an artifact table in toml.
+const TOML_FIELDS: &[&str] = &["done", "partof", "text"];
+
+/// #SPC-read-raw.preserve
+/// Serialize the artifacts into toml, preserving the formatting of `orig`.
+///
+/// Only the fields of artifacts which changed are rewritten, so comments, the
+/// order of the tables and blank lines written by a human are kept. Removed
+/// artifacts are dropped and new ones are appended. If `orig` is not valid
+/// this is the same as `toml::to_string`.
+pub fn to_toml_preserving(orig: &str, raw_artifacts: &IndexMap<Name, ArtifactRaw>) -> String {
+    let parsed = (
+        orig.parse::<toml_edit::Document>(),
+        toml::from_str::<IndexMap<Name, ArtifactRaw>>(orig),
+    );
+    let (mut doc, orig_artifacts) = match parsed {
+        (Ok(doc), Ok(arts)) => (doc, arts),
+        _ => return expect!(toml::to_string(raw_artifacts)),
+    };
+
+    // the keys as they are written in the file, which can differ in case
+    let keys: Vec<(String, Option<Name>)> = doc
+        .as_table()
+        .iter()
+        .map(|(key, _)| (key.to_string(), Name::from_str(key).ok()))
+        .collect();
+    for (key, name) in keys.iter() {
+        let keep = match name {
+            Some(n) => raw_artifacts.contains_key(n),
+            None => false,
+        };
+        if !keep {
+            doc.as_table_mut().remove(key);
+        }
+    }
+
+    for (name, raw) in raw_artifacts {
+        if orig_artifacts.get(name) == Some(raw) {
+            continue;
+        }
+        let single = indexmap! { name.clone() => raw.clone() };
+        let fresh: toml_edit::Document = expect!(expect!(toml::to_string(&single)).parse());
+        let fresh = &fresh[name.as_str()];
+        let key = keys
+            .iter()
+            .find(|(_, n)| n.as_ref() == Some(name))
+            .map(|(k, _)| k.as_str())
+            .unwrap_or_else(|| name.as_str());
+
+        // a new artifact, or one written as an inline table
+        if !(doc[key].is_table() && fresh.is_table()) {
+            doc[key] = fresh.clone();
+            continue;
+        }
+        let table = expect!(doc[key].as_table_mut());
+        let fresh = expect!(fresh.as_table());
+        for &field in TOML_FIELDS {
+            match fresh.get(field) {
+                Some(item) => table[field] = item.clone(),
+                None => {
+                    table.remove(field);
+                }
+            }
+        }
+    }
+    doc.to_string()
+}
+
 // ------------------------------
 // -- MARKDOWN
 
@@ -209,16 +311,32 @@ lazy_static! {
     pub static ref ATTRS_END_RE: Regex = Regex::new(r"^###+\s*$").unwrap();
     pub static ref ATTRS_CODE_RE: Regex = Regex::new(r"^```((\s*)|(.*?\s+))art+\s*$").unwrap();
     pub static ref CODE_END_RE: Regex = Regex::new(r"^```+\s*$").unwrap();
+    static ref FENCE_RE: Regex = Regex::new(r"^ {0,3}(`{3,}|~{3,})(.*)$").unwrap();
 }
 
 impl Parser {
     /// #SPC-read-raw-markdown
     /// Load raw artifacts from a markdown stream
     pub fn from_markdown<R: Read>(&self, stream: R) -> Result<IndexMap<Name, ArtifactRaw>> {
+        self.from_markdown_prose(stream).map(|(out, _)| out)
+    }
+
+    /// #SPC-read-raw-markdown.prose
+    /// Load raw artifacts from a markdown stream, also returning the text
+    /// which isn't part of any artifact.
+    pub fn from_markdown_prose<R: Read>(
+        &self,
+        stream: R,
+    ) -> Result<(IndexMap<Name, ArtifactRaw>, MarkdownProse)> {
         let mut out: IndexMap<Name, ArtifactRaw> = IndexMap::new();
+        let mut prose = MarkdownProse::default();
         let mut name: Option<Name> = None;
         let mut attrs: Option<String> = None;
         let mut other: Vec<String> = Vec::new();
+        // the current non-artifact section and the artifact it follows
+        let mut section: Option<(Name, Vec<String>)> = None;
+        // the code fence the artifact text is in, which can't hold headings
+        let mut fence: Option<String> = None;
         let mut line_stream = BufReader::new(stream).lines();
 
         macro_rules! check_attrs_empty {
@@ -251,15 +369,32 @@ impl Parser {
                     // Put a new artifact.
                     // Use `take()` for name and attrs so that they end up empty
                     insert_from_parts(&mut out, &n, attrs.take(), &other)?;
+                } else if let Some((after, lines)) = section.take() {
+                    prose.sections.insert(after, join_prose(&lines));
                 } else {
-                    // Ignore text above the first artifact
+                    // Keep the text above the first artifact
+                    prose.preamble = join_prose(&other);
                     attrs = None;
                 }
                 debug_assert!(name.is_none());
                 debug_assert!(attrs.is_none());
                 other.clear();
+                fence = None;
                 name = Some(Name::from_str(expect!(mat.get(1)).as_str())?);
                 continue;
+            } else if name.is_none() {
+                // the preamble or a section, neither can have attributes
+                match section {
+                    Some((_, ref mut lines)) => lines.push(line),
+                    None => other.push(line),
+                }
+                continue;
+            } else if fence.is_none() && self.is_section_heading(&line) {
+                let n = expect!(name.take());
+                insert_from_parts(&mut out, &n, attrs.take(), &other)?;
+                other.clear();
+                section = Some((n, vec![line]));
+                continue;
             } else if ATTRS_END_RE.is_match(&line) {
                 // the `other` lines we have been collecting are attrs!
                 check_attrs_empty!();
@@ -281,15 +416,57 @@ impl Parser {
                 }
             }
             // Note: the below should be in an `else` block but the borrow checker is bad at this...
+            update_fence(&mut fence, &line);
             other.push(line)
         }
         if let Some(name) = name {
             insert_from_parts(&mut out, &name, attrs, &other)?;
+        } else if let Some((after, lines)) = section {
+            prose.sections.insert(after, join_prose(&lines));
+        } else if out.is_empty() {
+            prose.preamble = join_prose(&other);
+        }
+        Ok((out, prose))
+    }
+
+    fn is_section_heading(&self, line: &str) -> bool {
+        match self.md_section_re {
+            Some(ref re) => re.is_match(line),
+            None => false,
         }
-        Ok(out)
     }
 }
 
+/// Track the code fence (```` ``` ```` or `~~~`) a line opens or closes.
+///
+/// A fence is closed by one of the same character which is at least as long
+/// and has nothing after it.
+fn update_fence(fence: &mut Option<String>, line: &str) {
+    let caps = match FENCE_RE.captures(line) {
+        Some(c) => c,
+        None => return,
+    };
+    let marker = expect!(caps.get(1)).as_str();
+    let closes = match *fence {
+        Some(ref open) => {
+            marker.starts_with(open.as_str()) && expect!(caps.get(2)).as_str().trim().is_empty()
+        }
+        None => false,
+    };
+    if closes {
+        *fence = None;
+    } else if fence.is_none() {
+        *fence = Some(marker.to_string());
+    }
+}
+
+/// Prose is kept verbatim, except for trailing whitespace.
+fn join_prose(lines: &[String]) -> String {
+    let mut out = lines.join("\n");
+    string_trim_right(&mut out);
+    out
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
 /// For deserializing markdown
 struct AttrsRaw {
@@ -355,9 +532,30 @@ impl Formatter {
 
     /// Convert the artifacts to markdown
     pub fn to_markdown(&self, raw_artifacts: &IndexMap<Name, ArtifactRaw>) -> String {
+        self.to_markdown_prose(&MarkdownProse::default(), raw_artifacts)
+    }
+
+    /// Convert the artifacts to markdown, writing back the text which isn't
+    /// part of any artifact.
+    ///
+    /// Sections whose artifact is gone are kept at the end of the file.
+    pub fn to_markdown_prose(
+        &self,
+        prose: &MarkdownProse,
+        raw_artifacts: &IndexMap<Name, ArtifactRaw>,
+    ) -> String {
         let mut out = String::new();
+        push_prose_md(&mut out, &prose.preamble);
         for (name, raw) in raw_artifacts {
             self.push_artifact_md(&mut out, name, raw);
+            if let Some(section) = prose.sections.get(name) {
+                push_prose_md(&mut out, section);
+            }
+        }
+        for (name, section) in prose.sections.iter() {
+            if !raw_artifacts.contains_key(name) {
+                push_prose_md(&mut out, section);
+            }
         }
         // No newlines at end of file.
         string_trim_right(&mut out);
@@ -420,6 +618,15 @@ impl Formatter {
     }
 }
 
+/// Push prose which is followed by EXACTLY two blank lines, like an artifact.
+fn push_prose_md(out: &mut String, prose: &str) {
+    if prose.trim().is_empty() {
+        return;
+    }
+    out.push_str(prose.trim_end());
+    out.push_str("\n\n\n");
+}
+
 fn to_yaml<S: Serialize>(value: &S) -> String {
     let mut s = expect!(yaml::to_string(value));
     s.drain(0..4); // remove the ---\n
@@ -0,0 +1,332 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! User defined rules, evaluated against the project after it is loaded.
+//!
+//! Rules are defined in a toml or yaml file (set with `lint.rules`). Each rule
+//! _selects_ artifacts and then asserts what they must (`require`) or must not
+//! (`forbid`) match. Every violation is a lint with the rule's message.
+//!
+//! ```toml
+//! [[rule]]
+//! name = "safety-tested"
+//! message = "safety requirements must be tested"
+//! level = "error"
+//!
+//! [rule.select]
+//! name = "REQ-safety-*"
+//!
+//! [rule.require]
+//! parts = "TST-*"
+//! ```
+
+use ergo::{toml, yaml};
+
+use crate::dev_prelude::*;
+use artifact_lib::query::glob_regex;
+
+/// The contents of a rules file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RulesRaw {
+    #[serde(default)]
+    pub rule: Vec<RuleRaw>,
+}
+
+/// A single rule as written by the user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleRaw {
+    pub name: String,
+    pub message: String,
+    #[serde(default)]
+    pub level: RuleLevel,
+    /// The artifacts the rule applies to. By default all artifacts are selected.
+    #[serde(default)]
+    pub select: MatcherRaw,
+    /// Selected artifacts must match all of these conditions.
+    #[serde(default)]
+    pub require: MatcherRaw,
+    /// Selected artifacts must NOT match all of these conditions.
+    #[serde(default)]
+    pub forbid: Option<MatcherRaw>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Error,
+    Warn,
+}
+
+impl Default for RuleLevel {
+    fn default() -> Self {
+        RuleLevel::Warn
+    }
+}
+
+/// Conditions on an artifact. An artifact matches when ALL conditions that
+/// are set hold.
+///
+/// Globs support `*` (any characters except `/`), `**` (any characters) and
+/// `?` (a single character except `/`). Name globs are case insensitive.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MatcherRaw {
+    /// Glob of the artifact's name.
+    pub name: Option<String>,
+    /// The artifact's type: `REQ`, `SPC` or `TST`.
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+    /// Glob of the artifact's file, relative to the project base.
+    pub file: Option<String>,
+    /// Glob which at least one `partof` must match.
+    pub partof: Option<String>,
+    /// Glob which at least one of the `parts` must match.
+    pub parts: Option<String>,
+    /// Regex which the text must match.
+    pub text: Option<String>,
+    /// How the artifact is implemented: `done`, `code` or `none`.
+    #[serde(rename = "impl")]
+    pub impl_: Option<String>,
+    /// Minimum and maximum completion percentages (0-100).
+    pub min_spc: Option<f32>,
+    pub max_spc: Option<f32>,
+    pub min_tst: Option<f32>,
+    pub max_tst: Option<f32>,
+}
+
+/// A rule which has been validated and is ready to evaluate.
+#[derive(Debug)]
+pub struct Rule {
+    pub name: String,
+    pub message: String,
+    pub level: lint::Level,
+    select: Matcher,
+    require: Matcher,
+    forbid: Option<Matcher>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImplKind {
+    Done,
+    Code,
+    None,
+}
+
+#[derive(Debug, Default)]
+struct Matcher {
+    name: Option<Regex>,
+    ty: Option<Type>,
+    file: Option<Regex>,
+    partof: Option<Regex>,
+    parts: Option<Regex>,
+    text: Option<Regex>,
+    impl_: Option<ImplKind>,
+    spc: (Option<f32>, Option<f32>),
+    tst: (Option<f32>, Option<f32>),
+}
+
+impl Rule {
+    /// Validate the raw rule, compiling all globs and regexes.
+    pub fn from_raw(raw: &RuleRaw) -> ::std::result::Result<Rule, String> {
+        let compile = |m: &MatcherRaw, section: &str| {
+            Matcher::from_raw(m).map_err(|e| format!("rule {:?} [{}]: {}", raw.name, section, e))
+        };
+        Ok(Rule {
+            name: raw.name.clone(),
+            message: raw.message.clone(),
+            level: match raw.level {
+                RuleLevel::Error => lint::Level::Error,
+                RuleLevel::Warn => lint::Level::Warn,
+            },
+            select: compile(&raw.select, "select")?,
+            require: compile(&raw.require, "require")?,
+            forbid: match raw.forbid {
+                Some(ref f) => Some(compile(f, "forbid")?),
+                None => None,
+            },
+        })
+    }
+
+    /// Whether the rule applies to the artifact.
+    pub fn selects(&self, base: &Path, art: &Artifact) -> bool {
+        self.select.matches(base, art)
+    }
+
+    /// Whether the (selected) artifact violates the rule.
+    pub fn is_violated(&self, base: &Path, art: &Artifact) -> bool {
+        if !self.require.matches(base, art) {
+            return true;
+        }
+        match self.forbid {
+            Some(ref f) => f.matches(base, art),
+            None => false,
+        }
+    }
+}
+
+impl Matcher {
+    fn from_raw(raw: &MatcherRaw) -> ::std::result::Result<Matcher, String> {
+        let ty = match raw.ty {
+            Some(ref t) => Some(match t.to_ascii_uppercase().as_str() {
+                "REQ" => Type::REQ,
+                "SPC" => Type::SPC,
+                "TST" => Type::TST,
+                _ => return Err(format!("type must be REQ, SPC or TST, got {:?}", t)),
+            }),
+            None => None,
+        };
+        let impl_ = match raw.impl_ {
+            Some(ref i) => Some(match i.to_ascii_lowercase().as_str() {
+                "done" => ImplKind::Done,
+                "code" => ImplKind::Code,
+                "none" => ImplKind::None,
+                _ => return Err(format!("impl must be done, code or none, got {:?}", i)),
+            }),
+            None => None,
+        };
+        let text = match raw.text {
+            Some(ref t) => Some(Regex::new(t).map_err(|e| format!("invalid text regex: {}", e))?),
+            None => None,
+        };
+        Ok(Matcher {
+            name: raw.name.as_ref().map(|g| glob_regex(g, true)),
+            ty: ty,
+            file: raw.file.as_ref().map(|g| glob_regex(g, false)),
+            partof: raw.partof.as_ref().map(|g| glob_regex(g, true)),
+            parts: raw.parts.as_ref().map(|g| glob_regex(g, true)),
+            text: text,
+            impl_: impl_,
+            spc: (raw.min_spc, raw.max_spc),
+            tst: (raw.min_tst, raw.max_tst),
+        })
+    }
+
+    fn matches(&self, base: &Path, art: &Artifact) -> bool {
+        if let Some(ref re) = self.name {
+            if !re.is_match(art.name.as_str()) {
+                return false;
+            }
+        }
+        if let Some(ty) = self.ty {
+            if art.name.ty != ty {
+                return false;
+            }
+        }
+        if let Some(ref re) = self.file {
+            if !re.is_match(&relative_path(&art.file, base)) {
+                return false;
+            }
+        }
+        if let Some(ref re) = self.partof {
+            if !art.partof.iter().any(|n| re.is_match(n.as_str())) {
+                return false;
+            }
+        }
+        if let Some(ref re) = self.parts {
+            if !art.parts.iter().any(|n| re.is_match(n.as_str())) {
+                return false;
+            }
+        }
+        if let Some(ref re) = self.text {
+            if !re.is_match(&art.text) {
+                return false;
+            }
+        }
+        if let Some(kind) = self.impl_ {
+            let actual = match art.impl_ {
+                Impl::Done(_) => ImplKind::Done,
+                Impl::Code(_) => ImplKind::Code,
+                Impl::NotImpl => ImplKind::None,
+            };
+            if actual != kind {
+                return false;
+            }
+        }
+        in_range(art.completed.spc, self.spc) && in_range(art.completed.tst, self.tst)
+    }
+}
+
+/// Load and validate the rules file.
+pub fn load_rules(path: &Path) -> ::std::result::Result<Vec<Rule>, String> {
+    let text = ::std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let is_yaml = match path.extension().and_then(OsStr::to_str) {
+        Some("yaml") | Some("yml") => true,
+        _ => false,
+    };
+    let raw: RulesRaw = if is_yaml {
+        yaml::from_str(&text).map_err(|e| e.to_string())?
+    } else {
+        toml::from_str(&text).map_err(|e| e.to_string())?
+    };
+    raw.rule.iter().map(Rule::from_raw).collect()
+}
+
+/// #SPC-read-artifact.lint_rules
+/// Lint the project against the user's rules file.
+pub(crate) fn lint_rules(lints: &Sender<lint::Lint>, project: &Project) {
+    let raw_path = match project.settings.lint.rules {
+        Some(ref p) => p,
+        None => return,
+    };
+    let base: &Path = &project.settings.base;
+    // Same as the other settings paths, `{repo}/` and `/` are ignored
+    let path = base.join(
+        raw_path
+            .trim_start_matches("{repo}")
+            .trim_start_matches('/'),
+    );
+
+    let rules = match load_rules(&path) {
+        Ok(r) => r,
+        Err(err) => {
+            let lint = lint::Lint {
+                level: lint::Level::Error,
+                path: Some(path.to_string_lossy().to_string()),
+                line: None,
+                category: lint::Category::Rules,
+                msg: format!("invalid rules file: {}", err),
+            };
+            ch!(lints <- lint);
+            return;
+        }
+    };
+
+    for rule in rules.iter() {
+        for (name, art) in project.artifacts.iter() {
+            if rule.selects(base, art) && rule.is_violated(base, art) {
+                let lint = lint::Lint {
+                    level: rule.level.clone(),
+                    path: Some(art.file.to_stfu8()),
+                    line: None,
+                    category: lint::Category::Rules,
+                    msg: format!("{} violates rule {:?}: {}", name, rule.name, rule.message),
+                };
+                ch!(lints <- lint);
+            }
+        }
+    }
+}
+
+// HELPERS
+
+/// Whether the completion ratio is within the percentage range.
+fn in_range(ratio: f32, range: (Option<f32>, Option<f32>)) -> bool {
+    let percent = ratio * 100.0;
+    let (min, max) = range;
+    min.map(|m| percent >= m).unwrap_or(true) && max.map(|m| percent <= m).unwrap_or(true)
+}
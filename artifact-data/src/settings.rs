@@ -38,6 +38,12 @@ pub(crate) struct SettingsRaw {
     pub format: SettingsFormat,
     #[serde(default)]
     pub export: SettingsExport,
+    #[serde(default)]
+    pub lint: SettingsLint,
+    #[serde(default)]
+    pub new: SettingsNew,
+    #[serde(default)]
+    pub serve: SettingsServe,
 }
 
 pub(crate) struct FoundPaths {
@@ -178,6 +184,9 @@ pub(crate) fn load_settings<P: AsRef<Path>>(
         parse: raw.parse,
         format: raw.format,
         export: raw.export,
+        lint: raw.lint,
+        new: raw.new,
+        serve: raw.serve,
     };
 
     drop(send_lints);
@@ -0,0 +1,185 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-modify.undo
+//! The undo/redo history of modifications made through the server.
+//!
+//! Every batch of `ArtifactOp`s is stored together with the operations which
+//! reverse it, computed from the artifacts before they were modified. The
+//! history is bounded to `MAX_UNDO` batches and persisted in `.art/undo.json`.
+
+use crate::dev_prelude::*;
+use crate::settings::ART_DIR;
+
+/// The name of the undo history file in the `.art` directory.
+pub const UNDO_FILE: &str = "undo.json";
+
+/// The maximum number of batches which can be undone.
+pub const MAX_UNDO: usize = 100;
+
+#[derive(Debug, Fail)]
+pub enum UndoError {
+    #[fail(display = "{}: invalid undo history: {}", path, msg)]
+    InvalidHistory { path: String, msg: String },
+}
+
+/// The batches of modifications which can be undone and redone.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UndoHistory {
+    /// The batches which can be undone, oldest first.
+    pub undo: Vec<UndoEntry>,
+    /// The batches which were undone, the next one to redo last.
+    pub redo: Vec<UndoEntry>,
+}
+
+/// A batch of modifications and the operations which reverse it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UndoEntry {
+    /// When the batch was applied, in rfc3339 format.
+    pub timestamp: String,
+    pub ops: Vec<ArtifactOp>,
+    pub inverse: Vec<ArtifactOp>,
+}
+
+impl UndoEntry {
+    /// Create the entry for the operations applied to the project.
+    ///
+    /// `project` must be the project _before_ the operations were applied.
+    /// Returns `None` if an `orig_id` is not in the project, in which case
+    /// applying the operations would fail anyway.
+    pub fn new(project: &Project, ops: &[ArtifactOp], timestamp: String) -> Option<UndoEntry> {
+        let ops: Vec<ArtifactOp> = ops
+            .iter()
+            .cloned()
+            .map(|mut op| {
+                op.clean();
+                op
+            })
+            .collect();
+        let inverse = inverse_ops(project, &ops)?;
+        Some(UndoEntry {
+            timestamp: timestamp,
+            ops: ops,
+            inverse: inverse,
+        })
+    }
+
+    /// A short summary of each operation, i.e. `update SPC-foo`.
+    pub fn summary(&self) -> Vec<String> {
+        self.ops
+            .iter()
+            .map(|op| match *op {
+                ArtifactOp::Create { ref artifact } => format!("create {}", artifact.name),
+                ArtifactOp::Update { ref artifact, .. } => format!("update {}", artifact.name),
+                ArtifactOp::Delete { ref name, .. } => format!("delete {}", name),
+            })
+            .collect()
+    }
+}
+
+impl UndoHistory {
+    /// Record a newly applied batch. Nothing can be redone afterwards.
+    pub fn push(&mut self, entry: UndoEntry) {
+        self.undo.push(entry);
+        if self.undo.len() > MAX_UNDO {
+            let extra = self.undo.len() - MAX_UNDO;
+            self.undo.drain(..extra);
+        }
+        self.redo.clear();
+    }
+
+    /// The items of the `ListHistory` method, oldest first.
+    pub fn items(&self) -> Vec<ModifyHistoryItem> {
+        let item = |entry: &UndoEntry, undone| ModifyHistoryItem {
+            timestamp: entry.timestamp.clone(),
+            ops: entry.summary(),
+            undone: undone,
+        };
+        self.undo
+            .iter()
+            .map(|e| item(e, false))
+            .chain(self.redo.iter().rev().map(|e| item(e, true)))
+            .collect()
+    }
+}
+
+/// The operations which reverse the (cleaned) operations, in reverse order.
+///
+/// `project` must be the project _before_ the operations were applied.
+pub fn inverse_ops(project: &Project, ops: &[ArtifactOp]) -> Option<Vec<ArtifactOp>> {
+    let originals: IndexMap<HashIm, &Artifact> = project
+        .artifacts
+        .values()
+        .map(|art| (art.id, art))
+        .collect();
+    let original = |id: &HashIm| -> Option<ArtifactIm> {
+        originals
+            .get(id)
+            .map(|art| ArtifactIm::from((*art).clone()))
+    };
+
+    let mut out = Vec::with_capacity(ops.len());
+    for op in ops.iter().rev() {
+        let inverse = match *op {
+            ArtifactOp::Create { ref artifact } => ArtifactOp::Delete {
+                name: artifact.name.clone(),
+                orig_id: artifact.hash_im(),
+            },
+            ArtifactOp::Update {
+                ref artifact,
+                orig_id,
+            } => ArtifactOp::Update {
+                artifact: original(&orig_id)?,
+                orig_id: artifact.hash_im(),
+            },
+            ArtifactOp::Delete { orig_id, .. } => ArtifactOp::Create {
+                artifact: original(&orig_id)?,
+            },
+        };
+        out.push(inverse);
+    }
+    Some(out)
+}
+
+/// The path to the undo history file of the project.
+pub fn undo_path(base: &PathDir) -> PathBuf {
+    let base: &Path = base;
+    base.join(ART_DIR).join(UNDO_FILE)
+}
+
+/// Load the undo history, which is empty if it was never saved.
+pub fn load_undo(base: &PathDir) -> Result<UndoHistory> {
+    let path = undo_path(base);
+    if !path.exists() {
+        return Ok(UndoHistory::default());
+    }
+    let text = PathFile::new(&path)?.read_string()?;
+    match json::from_str(&text) {
+        Ok(h) => Ok(h),
+        Err(e) => Err(UndoError::InvalidHistory {
+            path: path.display().to_string(),
+            msg: e.to_string(),
+        }
+        .into()),
+    }
+}
+
+/// Save the undo history, overwriting the previous one.
+pub fn save_undo(base: &PathDir, history: &UndoHistory) -> Result<()> {
+    let text = json::to_string(history)?;
+    PathFile::create(undo_path(base))?.write_str(&text)?;
+    Ok(())
+}
@@ -15,6 +15,7 @@
  * be dual licensed as above, without any additional terms or conditions.
  * */
 #![allow(dead_code)]
+pub use artifact_ser::query::{Query, QueryArtifact};
 pub use artifact_ser::*;
 pub use ergo_config::*;
 pub use ergo_std::*;
@@ -24,7 +25,7 @@ pub use yew::services::console::ConsoleService;
 pub use yew::virtual_dom::VNode;
 pub use yew_simple::FetchTask;
 
-use ergo_std::regex::RegexBuilder;
+use std::path::Path;
 
 lazy_static! {
     static ref ATOMIC_ID: AtomicUsize = ATOMIC_USIZE_INIT;
@@ -146,6 +147,7 @@ pub(crate) const FA_INFO_CIRCLE: &str = "fa-info-circle";
 pub(crate) const FA_EDIT: &str = "fa-edit";
 pub(crate) const FA_EYE: &str = "fa-eye";
 pub(crate) const FA_SAVE: &str = "fa-save";
+pub(crate) const FA_CHECK: &str = "fa-check";
 pub(crate) const FA_PLUS_SQUARE: &str = "fa-plus-square";
 pub(crate) const FA_SEARCH: &str = "fa-search";
 pub(crate) const FA_SEARCH_PLUS: &str = "fa-search-plus";
@@ -155,6 +157,9 @@ pub(crate) const FA_EXCLAMATION_CIRCLE: &str = "fa-exclamation-circle";
 pub(crate) const FA_SYNC: &str = "fa-sync";
 pub(crate) const FA_TRASH: &str = "fa-trash";
 pub(crate) const FA_TIMES: &str = "fa-times";
+pub(crate) const FA_UNDO: &str = "fa-undo";
+pub(crate) const FA_REDO: &str = "fa-redo";
+pub(crate) const FA_HISTORY: &str = "fa-history";
 
 // Custom
 pub(crate) const ART_INFO: &str = "art-info";
@@ -213,6 +218,14 @@ pub(crate) struct Model {
     /// Maps the json rpc request to the ids of what is being updated.
     /// When an OK is received, these items are deleted from `editing`.
     pub(crate) updating: IndexMap<::jrpc::Id, Vec<usize>>,
+
+    /// The modifications which can be undone/redone, oldest first.
+    pub(crate) history: Vec<ModifyHistoryItem>,
+
+    /// The result of checking an edit (by its id) with `ValidateModify`.
+    ///
+    /// Removed when the edit changes.
+    pub(crate) validated: IndexMap<usize, ModifyValidation>,
 }
 
 impl Model {
@@ -249,6 +262,7 @@ impl Model {
     /// - If the (edited view) of the item is currently being viewed, changing the router to route to
     ///   the saved item.
     pub(crate) fn complete_editing(&mut self, id: usize) {
+        self.validated.remove(&id);
         let edit = if let Some(e) = self.editing.remove(&id) {
             e
         } else {
@@ -295,6 +309,8 @@ pub(crate) enum Msg {
     ToggleSearch,
     /// Toggle whether we are searching for editing artifacts.
     ToggleEditing,
+    /// Toggle whether we are viewing the modification history.
+    ToggleHistory,
     /// Set the nav bar `search` string.
     SetNavSearch(String),
     /// Set the nav bar `editing` search string.
@@ -310,10 +326,22 @@ pub(crate) enum Msg {
     },
     /// Send an HTTP update to server with the specified edit ids.
     SendUpdate(Vec<usize>),
+    /// Send an HTTP request to check the edit without saving it.
+    SendValidate(usize),
+    /// Received the result of checking the edit.
+    RecvValidation(usize, ModifyValidation),
     /// Received an OK HTTP response with the project.
     RecvProject(::jrpc::Id, Arc<ProjectSer>),
     /// Received an ERR HTTP response.
     RecvError(Vec<Log>),
+    /// Received a `MergeConflict` for the edits of the request.
+    RecvConflicts(::jrpc::Id, Vec<ArtifactConflictSer>),
+    /// Send an HTTP request to undo (`true`) or redo (`false`) a modification.
+    SendUndo(bool),
+    /// Send an HTTP request to get the modification history.
+    FetchHistory,
+    /// Received the modification history.
+    RecvHistory(Vec<ModifyHistoryItem>),
 
     /// Push some logs into the UI.
     PushLogs(Vec<Log>),
@@ -377,6 +405,7 @@ pub(crate) enum LogLevel {
 pub(crate) struct Nav {
     pub(crate) search: Search,
     pub(crate) editing: Search,
+    pub(crate) history: bool,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -416,6 +445,8 @@ pub(crate) struct ArtifactEdit {
     pub partof: Vec<String>,
     pub done: String,
     pub text: String,
+    /// The conflict of the last save, displayed until it is saved.
+    pub conflict: Option<ArtifactConflictSer>,
 }
 
 impl ArtifactEdit {
@@ -431,6 +462,7 @@ impl ArtifactEdit {
                 .map(String::from)
                 .unwrap_or_else(String::new),
             text: art.text.clone(),
+            conflict: None,
         }
     }
 
@@ -488,21 +520,33 @@ pub(crate) fn fa_icon(icon: &str) -> HtmlApp {
     VNode::VRef(icon)
 }
 
-/// Parse the regex. If it is invalid, return the html error
-/// message to display to the user.
-pub(crate) fn parse_regex(s: &str) -> Result<Regex, HtmlApp> {
-    RegexBuilder::new(s)
-        .case_insensitive(true)
-        .build()
-        .map_err(|e| {
-            html![
-                <a
-                 href="https://docs.rs/regex/0.2.10/regex/#syntax",
-                 title="See syntax definition.",
-                 class=(RED, BTN, BOLD),
-                >
-                { format!("INVALID REGEX: {}", e) }
-                </a>
-            ]
-        })
+/// Parse the search query, an empty query selects every artifact. If it is invalid, return the
+/// html error message to display to the user.
+pub(crate) fn parse_query(s: &str) -> Result<Option<Query>, HtmlApp> {
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    Query::from_str(s).map(Some).map_err(|e| {
+        html![
+            <span
+             title="See `art ls -h` for the query syntax.",
+             class=(RED, BOLD),
+            >
+            { format!("INVALID QUERY: {}", e) }
+            </span>
+        ]
+    })
+}
+
+/// Select the names of the `artifacts` matching the (parsed) search query, in the same order as
+/// `artifacts`.
+pub(crate) fn select_query<A: QueryArtifact>(
+    model: &Model,
+    query: &Option<Query>,
+    artifacts: &IndexMap<Name, A>,
+) -> IndexSet<Name> {
+    match *query {
+        Some(ref q) => q.select(artifacts, Path::new(&model.shared.settings.base)),
+        None => artifacts.keys().cloned().collect(),
+    }
 }
@@ -25,6 +25,8 @@ pub(crate) fn view_edit(model: &Model, id: usize) -> ViewResult {
 }
 
 pub(crate) fn handle_edit_artifact(model: &mut Model, id: usize, field: Field) {
+    // the check no longer applies
+    model.validated.remove(&id);
     let artifact = match model.editing.get_mut(&id) {
         Some(a) => a,
         None => panic!("TODO: got invalid editing artifact"),
@@ -94,8 +96,13 @@ fn view_edit_page(model: &Model, id: usize) -> HtmlApp {
                 { fa_icon(FA_TRASH) }
                 <span class=ML1,>{ "Cancel" }</span>
             </button></span>
+
+            { check_button(model, id) }
         </div>
 
+        { view_validation(model, id, art) }
+        { view_conflict(id, art) }
+
         // NAME
         // TODO: to the right of name/partof put a "relationship" graph that dynamically updates
         <div><h1 class=H1,>
@@ -169,6 +176,152 @@ fn view_edit_page(model: &Model, id: usize) -> HtmlApp {
     ]
 }
 
+/// Check the edit with the server without saving it.
+fn check_button(model: &Model, id: usize) -> HtmlApp {
+    if model.web_type != WebType::Editable {
+        return html![<span></span>];
+    }
+    html![
+        <span><button
+            class=(BTN, ACE_WHITE, ACE_BG_BLACK),
+            id="edit-check",
+            onclick=|_| Msg::SendValidate(id),
+            title="check for errors without saving",
+        >
+            { fa_icon(FA_CHECK) }
+            <span class=ML1,>{ "Check" }</span>
+        </button></span>
+    ]
+}
+
+/// The result of the last check: the lints saving would cause and the
+/// completion the artifact would have.
+fn view_validation(model: &Model, id: usize, art: &ArtifactEdit) -> HtmlApp {
+    let validation = match model.validated.get(&id) {
+        Some(v) => v,
+        None => return html![<div></div>],
+    };
+    let summary = match validation.error {
+        Some(ref kind) => format!("Saving would fail: {}", kind),
+        None => "Saving would succeed.".to_string(),
+    };
+    let completed = Name::from_str(&art.name)
+        .ok()
+        .and_then(|name| validation.completed.get(&name));
+    let view_lint = |lint: &lint::Lint| {
+        let at = match (&lint.path, lint.line) {
+            (Some(path), Some(line)) => format!("{}[{}]: ", path, line),
+            (Some(path), None) => format!("{}: ", path),
+            _ => String::new(),
+        };
+        html![
+            <li>{ format!("{}{}", at, lint.msg) }</li>
+        ]
+    };
+
+    html![
+        <div class=(BORDER, P1, MY1),>
+            <div class=BOLD,>{ summary }</div>
+            {
+                match completed {
+                    Some(c) => html![
+                        <div>
+                            <span class=MR1,>{ "Completed:" }</span>
+                            { c.spc_html() }
+                            <span class=MX1,>{ "spc" }</span>
+                            { c.tst_html() }
+                            <span class=ML1,>{ "tst" }</span>
+                        </div>
+                    ],
+                    None => html![<div></div>],
+                }
+            }
+            <ul class=ACE_RED,>
+                { for validation.lints.error.iter().map(view_lint) }
+            </ul>
+            <ul>
+                { for validation.lints.other.iter().map(view_lint) }
+            </ul>
+        </div>
+    ]
+}
+
+/// The conflict of the last save: your value and the current value of each
+/// field which was changed differently.
+fn view_conflict(id: usize, art: &ArtifactEdit) -> HtmlApp {
+    let conflict = match art.conflict {
+        Some(ref c) => c,
+        None => return html![<div></div>],
+    };
+    let theirs = match conflict.theirs {
+        Some(ref t) => t,
+        None => {
+            return html![
+                <div class=(BORDER, P1, MY1, ACE_RED),>
+                    { "This artifact was deleted since you read it, saving creates it again." }
+                </div>
+            ];
+        }
+    };
+    let ours = conflict.ours.as_ref().unwrap_or(&conflict.base);
+
+    let view_field = |field: &String| {
+        let (yours, current) = match field.as_str() {
+            "name" => (ours.name.to_string(), theirs.name.to_string()),
+            "file" => (ours.file.clone(), theirs.file.clone()),
+            "done" => (
+                ours.done.clone().unwrap_or_default(),
+                theirs.done.clone().unwrap_or_default(),
+            ),
+            _ => (ours.text.clone(), theirs.text.clone()),
+        };
+        let field = field.clone();
+        let value = current.clone();
+        html![
+            <div class=MY1,>
+                <span class=BOLD,>{ format!("{}:", field) }</span>
+                <button
+                    class=(BTN, ML1),
+                    onclick=|_| Msg::EditArtifact(id, conflict_field(&field, value.clone())),
+                    title="Replace your value with the current one.",
+                >
+                    { "Use theirs" }
+                </button>
+                <div class=CLEARFIX,>
+                    <div class=(SM_COL, SM_COL_12, MD_COL_6, LG_COL_6),>
+                        <div class=ITALIC,>{ "Yours" }</div>
+                        <pre>{ yours }</pre>
+                    </div>
+                    <div class=(SM_COL, SM_COL_12, MD_COL_6, LG_COL_6),>
+                        <div class=ITALIC,>{ "Theirs" }</div>
+                        <pre>{ current }</pre>
+                    </div>
+                </div>
+            </div>
+        ]
+    };
+
+    html![
+        <div class=(BORDER, P1, MY1),>
+            <div class=(BOLD, ACE_RED),>
+                { "This artifact was changed since you read it. Resolve the conflicts \
+                   and save again to overwrite it." }
+            </div>
+            { for conflict.fields.iter().map(view_field) }
+        </div>
+    ]
+}
+
+/// The edit of a conflicting field, the fields of a `MergeConflict`.
+fn conflict_field(field: &str, value: String) -> Field {
+    match field {
+        "name" => Field::Name(value),
+        "file" => Field::File(value),
+        "done" => Field::Done(value),
+        _ => Field::Text(value),
+    }
+}
+
 fn view_partof(_: &Model, id: usize, artifact: &ArtifactEdit) -> HtmlApp {
     let view_part = |(index, name): (usize, &String)| {
         let id_str = format!("edit-partof-{}", index);
@@ -99,16 +99,7 @@ pub(crate) fn start_send_update(
         .iter()
         .map(|edit_id| {
             let edit = expect!(model.editing.get(edit_id), "FIXME: log msg if dne");
-            if let Some(orig_id) = edit.original_id {
-                ArtifactOpSer::Update {
-                    artifact: edit.to_im(),
-                    orig_id,
-                }
-            } else {
-                ArtifactOpSer::Create {
-                    artifact: edit.to_im(),
-                }
-            }
+            edit_op(edit)
         })
         .collect();
 
@@ -119,6 +110,92 @@ pub(crate) fn start_send_update(
     true
 }
 
+/// Send a request to check the edit with `ValidateModify`, which never saves.
+pub(crate) fn start_send_validate(
+    model: &mut Model,
+    context: &mut Env<'_, Context, Model>,
+    id: usize,
+) -> bool {
+    if model.web_type != WebType::Editable {
+        return false;
+    }
+
+    if model.fetch_task.is_some() {
+        push_logs_fetch_in_progress(model);
+        return false;
+    }
+
+    let edit = expect!(model.editing.get(&id), "FIXME: log msg if dne");
+    let request =
+        jrpc::Request::with_params(new_rpc_id(), Method::ValidateModify, vec![edit_op(edit)]);
+    let callback = context.send_back(move |response| handle_response_validate(id, response));
+    let body = json::to_string(&request).expect("request-ser");
+    let request = http::Request::post("/json-rpc")
+        .body(Value::String(body))
+        .expect("create request");
+    model.fetch_task = Some(FetchTask::new(request, callback));
+    false
+}
+
+/// The operation which saves the edit.
+fn edit_op(edit: &ArtifactEdit) -> ArtifactOpSer {
+    if let Some(orig_id) = edit.original_id {
+        ArtifactOpSer::Update {
+            artifact: edit.to_im(),
+            orig_id,
+        }
+    } else {
+        ArtifactOpSer::Create {
+            artifact: edit.to_im(),
+        }
+    }
+}
+
+/// Send a request to undo (or redo) the most recent modification.
+pub(crate) fn start_send_undo(
+    model: &mut Model,
+    context: &mut Env<'_, Context, Model>,
+    undo: bool,
+) -> bool {
+    if model.web_type != WebType::Editable {
+        return false;
+    }
+
+    if model.fetch_task.is_some() {
+        push_logs_fetch_in_progress(model);
+        return false;
+    }
+
+    let method = if undo { Method::Undo } else { Method::Redo };
+    let request = jrpc::Request::new(new_rpc_id(), method);
+    model.fetch_task = Some(create_fetch_task!(context, request));
+    true
+}
+
+/// Send a request to get the modification history.
+pub(crate) fn start_fetch_history(
+    model: &mut Model,
+    context: &mut Env<'_, Context, Model>,
+) -> bool {
+    if model.web_type != WebType::Editable {
+        return false;
+    }
+
+    if model.fetch_task.is_some() {
+        push_logs_fetch_in_progress(model);
+        return false;
+    }
+
+    let request = jrpc::Request::new(new_rpc_id(), Method::ListHistory);
+    let callback = context.send_back(handle_response_history);
+    let body = json::to_string(&request).expect("request-ser");
+    let request = http::Request::post("/json-rpc")
+        .body(Value::String(body))
+        .expect("create request");
+    model.fetch_task = Some(FetchTask::new(request, callback));
+    false
+}
+
 /// Handle the receiving of the project.
 pub(crate) fn handle_recv_project(model: &mut Model, jid: &jrpc::Id, project: Arc<ProjectSer>) {
     model.shared = project;
@@ -161,17 +238,111 @@ fn handle_response(response: http::Response<String>) -> Msg {
 
     let result = match response {
         jrpc::Response::Ok(r) => r,
-        jrpc::Response::Err(err) => {
-            return Msg::RecvError(vec![Log::error(format!(
-                "<div>received jrpc Error: {:?}</div>",
-                err
-            ))]);
-        }
+        jrpc::Response::Err(err) => return error_msg(err),
     };
 
     Msg::RecvProject(result.id, Arc::new(result.result.project))
 }
 
+/// The message of an error response. The conflicts of a `MergeConflict` are
+/// handed to the edits which caused them.
+fn error_msg(err: jrpc::Error<json::Value>) -> Msg {
+    let is_conflict = err.error.message == ModifyErrorKind::MergeConflict.as_str();
+    let conflicts: Option<Vec<ArtifactConflictSer>> = match err.error.data {
+        Some(ref data) if is_conflict => json::from_value(data.clone()).ok(),
+        _ => None,
+    };
+    match conflicts {
+        Some(c) => Msg::RecvConflicts(err.id, c),
+        None => Msg::RecvError(vec![Log::error(format!(
+            "<div>received jrpc Error: {:?}</div>",
+            err
+        ))]),
+    }
+}
+
+/// Handle the conflicts of an update.
+///
+/// Nothing was saved, so the edits are kept with their conflict. Saving an
+/// edit again overwrites the artifact as it is now.
+pub(crate) fn handle_recv_conflicts(
+    model: &mut Model,
+    jid: &jrpc::Id,
+    conflicts: Vec<ArtifactConflictSer>,
+) {
+    model.fetch_task = None;
+    let ids = model.updating.remove(jid).unwrap_or_default();
+    let mut logs = Vec::new();
+    for conflict in conflicts {
+        logs.push(conflict_log(&conflict));
+        let edit_id = ids.iter().cloned().find(|id| match model.editing.get(id) {
+            Some(edit) => edit.original_id == Some(conflict.orig_id),
+            None => false,
+        });
+        if let Some(edit) = edit_id.and_then(|id| model.editing.get_mut(&id)) {
+            edit.original_id = conflict.theirs_id;
+            edit.conflict = Some(conflict);
+        }
+    }
+    model.push_logs(logs);
+}
+
+fn conflict_log(c: &ArtifactConflictSer) -> Log {
+    let what = match (&c.ours, &c.theirs) {
+        (_, None) => "it was deleted since you read it".to_string(),
+        (None, Some(_)) => "it was changed since you read it".to_string(),
+        (Some(_), Some(_)) => format!(
+            "it was changed differently since you read it: {}",
+            c.fields.join(", ")
+        ),
+    };
+    Log::error(format!(
+        "<div>Could not save {}, {}. Your edit is kept, resolve the conflict \
+         and save it again.</div>",
+        c.base.name, what,
+    ))
+}
+
+/// Handle response of checking the edit `id`
+fn handle_response_validate(id: usize, response: http::Response<String>) -> Msg {
+    let response = match handle_status(response) {
+        Ok(r) => r,
+        Err(msg) => return msg,
+    };
+
+    let body = response.into_body();
+    let response: jrpc::Response<ModifyValidation> =
+        expect!(json::from_str(&body), "response-serde");
+
+    match response {
+        jrpc::Response::Ok(r) => Msg::RecvValidation(id, r.result),
+        jrpc::Response::Err(err) => Msg::RecvError(vec![Log::error(format!(
+            "<div>received jrpc Error: {:?}</div>",
+            err
+        ))]),
+    }
+}
+
+/// Handle response of the history fetch
+fn handle_response_history(response: http::Response<String>) -> Msg {
+    let response = match handle_status(response) {
+        Ok(r) => r,
+        Err(msg) => return msg,
+    };
+
+    let body = response.into_body();
+    let response: jrpc::Response<Vec<ModifyHistoryItem>> =
+        expect!(json::from_str(&body), "response-serde");
+
+    match response {
+        jrpc::Response::Ok(r) => Msg::RecvHistory(r.result),
+        jrpc::Response::Err(err) => Msg::RecvError(vec![Log::error(format!(
+            "<div>received jrpc Error: {:?}</div>",
+            err
+        ))]),
+    }
+}
+
 fn handle_status(response: http::Response<String>) -> Result<http::Response<String>, Msg> {
     let status = response.status();
     if !status.is_success() {
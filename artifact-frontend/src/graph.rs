@@ -85,17 +85,18 @@ pub(crate) fn dot_html(dot: &str) -> HtmlApp {
 fn graph_html_results(model: &Model) -> HtmlApp {
     let md = ser_markdown(model);
 
-    let re = match parse_regex(&model.graph.search) {
-        Ok(r) => r,
+    let query = match parse_query(&model.graph.search) {
+        Ok(q) => q,
         Err(e) => return e,
     };
+    let selected = select_query(model, &query, &model.shared.artifacts);
     let mut dot = String::new();
 
     let focus: HashMap<&Name, &ArtifactSer> = model
         .shared
         .artifacts
         .iter()
-        .filter(|(n, _)| re.is_match(n.as_str()))
+        .filter(|(n, _)| selected.contains(*n))
         .collect();
 
     for (name, art) in &focus {
@@ -61,6 +61,8 @@ impl Component<Context> for Model {
             window: ::stdweb::web::window(),
             editing: IndexMap::new(),
             updating: IndexMap::new(),
+            history: Vec::new(),
+            validated: IndexMap::new(),
         };
         model.nav.search.on = true;
         model.nav.editing.on = true;
@@ -93,6 +95,12 @@ fn update_model(
 
         Msg::ToggleSearch => model.nav.search.on = !model.nav.search.on,
         Msg::ToggleEditing => model.nav.editing.on = !model.nav.editing.on,
+        Msg::ToggleHistory => {
+            model.nav.history = !model.nav.history;
+            if model.nav.history {
+                fetch::start_fetch_history(model, context);
+            }
+        }
         Msg::SetNavSearch(v) => model.nav.search.value = v,
         Msg::SetNavEditing(v) => model.nav.editing.value = v,
 
@@ -111,11 +119,28 @@ fn update_model(
         }
         Msg::FetchProject { reload } => return fetch::start_fetch_project(model, context, reload),
         Msg::SendUpdate(ids) => return fetch::start_send_update(model, context, ids),
-        Msg::RecvProject(jid, project) => fetch::handle_recv_project(model, &jid, project),
+        Msg::SendValidate(id) => return fetch::start_send_validate(model, context, id),
+        Msg::RecvValidation(id, validation) => {
+            model.validated.insert(id, validation);
+            model.fetch_task = None;
+        }
+        Msg::RecvProject(jid, project) => {
+            fetch::handle_recv_project(model, &jid, project);
+            if model.nav.history {
+                fetch::start_fetch_history(model, context);
+            }
+        }
         Msg::RecvError(logs) => {
             model.push_logs(logs);
             model.fetch_task = None;
         }
+        Msg::RecvConflicts(jid, conflicts) => fetch::handle_recv_conflicts(model, &jid, conflicts),
+        Msg::SendUndo(undo) => return fetch::start_send_undo(model, context, undo),
+        Msg::FetchHistory => return fetch::start_fetch_history(model, context),
+        Msg::RecvHistory(history) => {
+            model.history = history;
+            model.fetch_task = None;
+        }
 
         Msg::PushLogs(logs) => model.push_logs(logs),
         Msg::ClearLogs(clear) => clear_logs(model, clear),
@@ -68,6 +68,8 @@ pub(crate) fn view_nav(model: &Model, view: ViewResult) -> HtmlApp {
                 <span class=ML1,>{ "Sync" }</span>
             </button>
 
+            { undo_buttons(model) }
+
             <button class=(BTN, REGULAR), id="graph",
              onclick=|_| {
                 router2.push_hash(Some(HASH_GRAPH))
@@ -101,6 +103,7 @@ pub(crate) fn view_nav(model: &Model, view: ViewResult) -> HtmlApp {
                 // viewing panes
                 { error_pane(model) }
                 { editing_pane(model) }
+                { history_pane(model) }
                 { search_pane(model) }
             </div>
 
@@ -112,6 +115,37 @@ pub(crate) fn view_nav(model: &Model, view: ViewResult) -> HtmlApp {
     </div>]
 }
 
+/// The undo, redo and history buttons, which need a server that can modify
+/// the project.
+fn undo_buttons(model: &Model) -> HtmlApp {
+    if model.web_type != WebType::Editable {
+        return html![<></>];
+    }
+
+    html![<>
+        <button class=(BTN, REGULAR), id="undo",
+         onclick=|_| Msg::SendUndo(true),
+         title="Undo the last modification.",>
+            { fa_icon(FA_UNDO) }
+            <span class=ML1,>{ "Undo" }</span>
+        </button>
+
+        <button class=(BTN, REGULAR), id="redo",
+         onclick=|_| Msg::SendUndo(false),
+         title="Redo the last undone modification.",>
+            { fa_icon(FA_REDO) }
+            <span class=ML1,>{ "Redo" }</span>
+        </button>
+
+        <button class=(BTN, REGULAR), id="history",
+         onclick=|_| Msg::ToggleHistory,
+         title="View the modification history.",>
+            { fa_icon(FA_HISTORY) }
+            <span class=ML1,>{ "History" }</span>
+        </button>
+    </>]
+}
+
 fn error_pane(model: &Model) -> HtmlApp {
     if model.logs.error.is_empty() {
         return html![<span></span>];
@@ -176,14 +210,30 @@ fn editing_pane(model: &Model) -> HtmlApp {
         </div>]
     }
 
-    let names = match parse_regex(&model.nav.editing.value) {
-        Ok(re) => html![<div>
-            { for model.editing
-                .iter()
-                .filter(|(_, a)| re.is_match(&a.name))
-                .map(|(id, art)| editing_name_html(*id, &art.name))
-            }
-        </div>],
+    let names = match parse_query(&model.nav.editing.value) {
+        Ok(query) => {
+            let edits: IndexMap<Name, ArtifactSer> = model
+                .editing
+                .values()
+                .filter_map(|edit| edit_ser(model, edit))
+                .map(|art| (art.name.clone(), art))
+                .collect();
+            let selected = select_query(model, &query, &edits);
+            // edits without a valid name can only be matched by the empty query
+            let is_selected = |edit: &ArtifactEdit| {
+                query.is_none()
+                    || Name::from_str(&edit.name)
+                        .ok()
+                        .map_or(false, |n| selected.contains(&n))
+            };
+            html![<div>
+                { for model.editing
+                    .iter()
+                    .filter(|(_, a)| is_selected(a))
+                    .map(|(id, art)| editing_name_html(*id, &art.name))
+                }
+            </div>]
+        }
         Err(err) => err,
     };
 
@@ -200,16 +250,68 @@ fn editing_pane(model: &Model) -> HtmlApp {
     </div>]
 }
 
+/// The edit as an artifact so that it can be searched with the query.
+///
+/// The calculated fields (`parts`, `completed` and the code implementation) are taken from the
+/// saved artifact with the same name.
+fn edit_ser(model: &Model, edit: &ArtifactEdit) -> Option<ArtifactSer> {
+    let name = Name::from_str(&edit.name).ok()?;
+    let saved = model.shared.artifacts.get(&name);
+    let impl_ = if !edit.done.is_empty() {
+        ImplSer::Done(edit.done.clone())
+    } else {
+        match saved.map(|art| &art.impl_) {
+            Some(ImplSer::Code(code)) => ImplSer::Code(code.clone()),
+            _ => ImplSer::NotImpl,
+        }
+    };
+    Some(ArtifactSer {
+        id: edit.original_id.unwrap_or_default(),
+        name,
+        file: edit.file.clone(),
+        partof: edit
+            .partof
+            .iter()
+            .filter_map(|n| Name::from_str(n).ok())
+            .collect(),
+        parts: saved.map(|art| art.parts.clone()).unwrap_or_default(),
+        completed: saved.map(|art| art.completed).unwrap_or_default(),
+        text: edit.text.clone(),
+        impl_,
+        subnames: parse_subnames(&edit.text),
+    })
+}
+
+fn history_pane(model: &Model) -> HtmlApp {
+    if !model.nav.history {
+        return html![<div></div>];
+    }
+
+    fn history_item_html(item: &ModifyHistoryItem) -> HtmlApp {
+        let color = if item.undone { ACE_GRAY } else { REGULAR };
+        html![<div class=color,
+             title=item.timestamp.clone(),>
+            { for item.ops.iter().map(|op| html![<div>{ op }</div>]) }
+        </div>]
+    }
+
+    html![<div class=(BORDER, MR1),>
+        <div><h2 class=H2,>
+            { "History" }
+        </h2></div>
+        { for model.history.iter().rev().map(history_item_html) }
+    </div>]
+}
+
 fn search_pane(model: &Model) -> HtmlApp {
     if !model.nav.search.on {
         return html![<div></div>];
     }
 
-    let names = match parse_regex(&model.nav.search.value) {
-        Ok(re) => html![<div>
-            { for model.shared.artifacts
-                .keys()
-                .filter(|n| re.is_match(n.as_str()))
+    let names = match parse_query(&model.nav.search.value) {
+        Ok(query) => html![<div>
+            { for select_query(model, &query, &model.shared.artifacts)
+                .iter()
                 .map(|n| name::name_html(model, n))
             }
         </div>],
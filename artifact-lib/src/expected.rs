@@ -70,6 +70,12 @@ pub struct SettingsAssert {
     pub format: SettingsFormat,
     #[serde(default)]
     pub export: SettingsExport,
+    #[serde(default)]
+    pub lint: SettingsLint,
+    #[serde(default)]
+    pub new: SettingsNew,
+    #[serde(default)]
+    pub serve: SettingsServe,
 }
 
 fn default_settings() -> String {
@@ -165,6 +171,9 @@ impl SettingsAssert {
             parse: self.parse,
             format: self.format,
             export: self.export,
+            lint: self.lint,
+            new: self.new,
+            serve: self.serve,
         }
     }
 }
@@ -33,6 +33,7 @@ pub use artifact_ser::*;
 mod dev_prelude;
 #[macro_use]
 pub mod expected;
+pub mod query;
 
 use siphasher::sip128::{Hasher128, SipHasher};
 use std::error;
@@ -224,7 +225,7 @@ impl fmt::Debug for CodeLoc {
 
 // ----- INTERMEDIATE -----
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 /// #SPC-structs.artifact_im
 pub struct ArtifactIm {
     pub name: Name,
@@ -293,7 +294,7 @@ impl Hash for ArtifactIm {
 
 // ------ OPERATIONS -----
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 /// #SPC-structs.artifact_op
 /// Used for specifying operations to perform.
 #[serde(tag = "op", rename_all = "lowercase")]
@@ -311,6 +312,40 @@ pub enum ArtifactOp {
     },
 }
 
+/// An operation on an artifact which changed since it was read, where the
+/// changes could not be merged.
+///
+/// The data of the `MergeConflict` error.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArtifactConflict {
+    /// The `orig_id` of the operation.
+    pub orig_id: HashIm,
+    /// The artifact when the operation was requested.
+    pub base: ArtifactIm,
+    /// The requested artifact, `None` if it is deleted.
+    pub ours: Option<ArtifactIm>,
+    /// The artifact as it is now, `None` if it no longer exists.
+    pub theirs: Option<ArtifactIm>,
+    /// The id of `theirs`, the `orig_id` of an operation which resolves the
+    /// conflict.
+    pub theirs_id: Option<HashIm>,
+    /// The fields which were changed differently, i.e. `text`. Empty if the
+    /// artifact was deleted by one side.
+    pub fields: Vec<String>,
+}
+
+/// Parameters for the `ModifyProject` method.
+///
+/// A plain list of operations is also accepted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ParamsModifyProject {
+    pub ops: Vec<ArtifactOp>,
+    /// Who made the modification, in the form `Name <email>`. Used as the
+    /// author of the commit when the `serve.git_commit` setting is on.
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
 pub struct IdPieces {
     pub name: Name,
     pub orig_id: Option<HashIm>,
@@ -375,6 +410,12 @@ pub struct Settings {
     pub format: SettingsFormat,
     #[serde(default)]
     pub export: SettingsExport,
+    #[serde(default)]
+    pub lint: SettingsLint,
+    #[serde(default)]
+    pub new: SettingsNew,
+    #[serde(default)]
+    pub serve: SettingsServe,
 }
 
 // ------ PROJECT ------
@@ -385,9 +426,12 @@ pub struct Settings {
 pub struct ProjectResult {
     pub project: Project,
     pub lints: Categorized,
+    /// The files written or removed by the request.
+    #[serde(default)]
+    pub changed: Vec<PathSer>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Project {
     pub settings: Settings,
     pub code_impls: IndexMap<Name, ImplCode>,
@@ -414,6 +458,14 @@ impl Project {
 
 // ------ HELPERS ------
 
+/// The path relative to `base` (i.e. the project's base) with `/` separators.
+///
+/// Paths which are not within `base` are kept whole.
+pub fn relative_path(path: &Path, base: &Path) -> String {
+    let rel = path.strip_prefix(base).unwrap_or(path);
+    rel.to_string_lossy().replace('\\', "/")
+}
+
 /// Join a path to an absolute path. Panic if it doesn't exist.
 pub fn join_abs<P: AsRef<Path>>(path: &PathAbs, end: P) -> PathFile {
     expect!(
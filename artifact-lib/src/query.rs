@@ -0,0 +1,75 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! The query language, see `artifact_ser::query`.
+//!
+//! This implements the query traits for `Artifact` and `Project` so the cli can query the
+//! full project.
+
+pub use artifact_ser::query::*;
+
+use crate::dev_prelude::*;
+use crate::{relative_path, Artifact, Completed, Impl, Name, Project, SubName};
+
+impl QueryArtifact for Artifact {
+    fn name(&self) -> &Name {
+        &self.name
+    }
+
+    fn relative_file(&self, base: &Path) -> String {
+        relative_path(&self.file, base)
+    }
+
+    fn partof(&self) -> &IndexSet<Name> {
+        &self.partof
+    }
+
+    fn parts(&self) -> &IndexSet<Name> {
+        &self.parts
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn subnames(&self) -> &IndexSet<SubName> {
+        &self.subnames
+    }
+
+    fn impl_kind(&self) -> ImplKind {
+        match self.impl_ {
+            Impl::Done(_) => ImplKind::Done,
+            Impl::Code(_) => ImplKind::Code,
+            Impl::NotImpl => ImplKind::None,
+        }
+    }
+
+    fn completed(&self) -> &Completed {
+        &self.completed
+    }
+}
+
+impl QueryProject for Project {
+    type Artifact = Artifact;
+
+    fn artifacts(&self) -> &IndexMap<Name, Artifact> {
+        &self.artifacts
+    }
+
+    fn base(&self) -> &Path {
+        &self.settings.base
+    }
+}
@@ -47,14 +47,15 @@ mod family;
 mod expand_names;
 pub mod markdown;
 pub mod md_graph;
+pub mod query;
 
 pub use crate::expand_names::expand_names;
 pub use crate::family::{auto_partofs, Names};
 pub use crate::lint::Categorized;
 pub use crate::name::{parse_subnames, InternalSubName, Name, SubName, Type, NAME_VALID_STR};
 pub use crate::ser::{
-    ArtifactImSer, ArtifactOpSer, ArtifactSer, CodeLocSer, ImplCodeSer, ImplSer, ProjectInitialSer,
-    ProjectResultSer, ProjectSer, SettingsSer, WebType,
+    ArtifactConflictSer, ArtifactImSer, ArtifactOpSer, ArtifactSer, CodeLocSer, ImplCodeSer,
+    ImplSer, ProjectInitialSer, ProjectResultSer, ProjectSer, SettingsSer, WebType,
 };
 
 use crate::dev_prelude::*;
@@ -112,6 +113,92 @@ fn return_true() -> bool {
     true
 }
 
+/// Settings related to creating artifacts with `art new`.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SettingsNew {
+    /// The initial text of new artifacts, by type (`REQ`, `SPC` or `TST`).
+    #[serde(default)]
+    pub templates: IndexMap<String, String>,
+}
+
+/// Settings related to `art serve`.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SettingsServe {
+    /// Commit the files changed by each modification made through the
+    /// server to the git repository of the project.
+    #[serde(default)]
+    pub git_commit: bool,
+}
+
+/// Settings related to optional lints.
+///
+/// All of these are disabled by default.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SettingsLint {
+    /// Warn when artifacts have identical or near-identical text.
+    #[serde(default)]
+    pub duplicate_text: Option<SettingsLintDuplicate>,
+
+    /// Warn about subnames which are neither implemented nor referenced, or
+    /// which look like a misspelling of an implemented subname.
+    #[serde(default)]
+    pub subnames: bool,
+
+    /// Validate the markdown of artifact text: unclosed code fences, broken
+    /// tables, invalid `dot` graphs and missing relative links.
+    #[serde(default)]
+    pub markdown: bool,
+
+    /// Warn about links and images in artifact text whose target doesn't exist.
+    #[serde(default)]
+    pub links: Option<SettingsLintLinks>,
+
+    /// A toml or yaml file (relative to the project base) of user defined
+    /// rules which artifacts must follow.
+    #[serde(default)]
+    pub rules: Option<String>,
+}
+
+/// Settings for the duplicate text lint.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SettingsLintDuplicate {
+    /// Percent similarity (0-100) at which two texts are considered duplicates.
+    #[serde(default = "default_similarity")]
+    pub similarity: u8,
+
+    /// Texts with fewer words than this are ignored.
+    #[serde(default = "default_min_words")]
+    pub min_words: usize,
+}
+
+impl Default for SettingsLintDuplicate {
+    fn default() -> Self {
+        SettingsLintDuplicate {
+            similarity: default_similarity(),
+            min_words: default_min_words(),
+        }
+    }
+}
+
+fn default_similarity() -> u8 {
+    90
+}
+
+fn default_min_words() -> usize {
+    10
+}
+
+/// Settings for the links lint.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SettingsLintLinks {
+    /// A file (relative to the project base) listing known `http(s)` urls.
+    ///
+    /// If set, every url in artifact text must be listed in it. The network
+    /// is never accessed. If not set, urls are not checked.
+    #[serde(default)]
+    pub known_urls: Option<String>,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase", tag = "type")]
 pub enum SettingsMdFamily {
@@ -355,6 +442,13 @@ pub enum ModifyErrorKind {
 
     /// Failure while saving the project, recovery required.
     SaveProject,
+
+    /// A previous save was interrupted and must be recovered first.
+    RecoveryRequired,
+
+    /// Some of the artifacts changed since they were read and the changes
+    /// could not be merged.
+    MergeConflict,
 }
 
 impl ModifyErrorKind {
@@ -366,6 +460,8 @@ impl ModifyErrorKind {
             "InvalidFromModify" => ModifyErrorKind::InvalidFromModify,
             "CreateBackups" => ModifyErrorKind::CreateBackups,
             "SaveProject" => ModifyErrorKind::SaveProject,
+            "RecoveryRequired" => ModifyErrorKind::RecoveryRequired,
+            "MergeConflict" => ModifyErrorKind::MergeConflict,
             _ => return None,
         };
         Some(out)
@@ -379,6 +475,8 @@ impl ModifyErrorKind {
             ModifyErrorKind::InvalidFromModify => "InvalidFromModify",
             ModifyErrorKind::CreateBackups => "CreateBackups",
             ModifyErrorKind::SaveProject => "SaveProject",
+            ModifyErrorKind::RecoveryRequired => "RecoveryRequired",
+            ModifyErrorKind::MergeConflict => "MergeConflict",
         }
     }
 }
@@ -396,6 +494,11 @@ impl fmt::Display for ModifyErrorKind {
 pub enum Method {
     ReadProject,
     ModifyProject,
+    QueryArtifacts,
+    Undo,
+    Redo,
+    ListHistory,
+    ValidateModify,
 }
 
 impl Method {
@@ -403,6 +506,11 @@ impl Method {
         let out = match s {
             "ReadProject" => Method::ReadProject,
             "ModifyProject" => Method::ModifyProject,
+            "QueryArtifacts" => Method::QueryArtifacts,
+            "Undo" => Method::Undo,
+            "Redo" => Method::Redo,
+            "ListHistory" => Method::ListHistory,
+            "ValidateModify" => Method::ValidateModify,
             _ => return None,
         };
         Some(out)
@@ -412,6 +520,11 @@ impl Method {
         match *self {
             Method::ReadProject => "ReadProject",
             Method::ModifyProject => "ModifyProject",
+            Method::QueryArtifacts => "QueryArtifacts",
+            Method::Undo => "Undo",
+            Method::Redo => "Redo",
+            Method::ListHistory => "ListHistory",
+            Method::ValidateModify => "ValidateModify",
         }
     }
 }
@@ -423,6 +536,42 @@ pub struct ParamsReadProject {
     pub reload: bool,
 }
 
+/// Parameters for the `QueryArtifacts` method.
+///
+/// The result is the list of artifact names matching the query.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParamsQueryArtifacts {
+    /// The query, i.e. `type:SPC and spc<0.5`.
+    pub query: String,
+}
+
+/// An item of the result of the `ListHistory` method, oldest first.
+///
+/// `Undo` and `Redo` take no parameters and return the project like
+/// `ModifyProject`.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ModifyHistoryItem {
+    /// When the modification was made, in rfc3339 format.
+    pub timestamp: String,
+    /// A summary of each operation, i.e. `update SPC-foo`.
+    pub ops: Vec<String>,
+    /// Whether the modification was undone and can be redone.
+    pub undone: bool,
+}
+
+/// The result of the `ValidateModify` method, which takes the same
+/// parameters as `ModifyProject` but never saves anything.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModifyValidation {
+    /// The error `ModifyProject` would fail with, if any.
+    pub error: Option<ModifyErrorKind>,
+    /// The lints caused by the modification.
+    pub lints: lint::Categorized,
+    /// The completion of every artifact after the modification. Empty if the
+    /// operations themselves are invalid.
+    pub completed: IndexMap<Name, Completed>,
+}
+
 // ------ HELPERS ------
 
 /// Inplace trim is annoyingly not in the stdlib
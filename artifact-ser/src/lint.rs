@@ -68,6 +68,11 @@ pub enum Category {
     CreateBackups,
     SaveProject,
     RemoveBackups,
+    DuplicateText,
+    Subname,
+    Markdown,
+    Links,
+    Rules,
 }
 
 impl Categorized {
@@ -423,7 +423,8 @@ impl CssFont {
     }
 }
 
-fn completed_color(c: &Completed) -> &'static str {
+/// The color of a name with the completion, see `name_color`.
+pub fn completed_color(c: &Completed) -> &'static str {
     match c.spc_points() + c.tst_points() {
         0 => RED,
         1 | 2 => ORANGE,
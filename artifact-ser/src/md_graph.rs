@@ -36,6 +36,16 @@ pub fn connect_names_dot(from: &Name, to: &Name) -> String {
     format!("        \"{}\" -> \"{}\"\n", from.key_str(), to.key_str())
 }
 
+/// Connect an artifact to one of its subnames, see `artifact_subname_dot`.
+pub fn connect_subname_dot(name: &Name, sub: &SubName) -> String {
+    format!(
+        "        \"{}\" -> \"{}{}\"\n",
+        name.key_str(),
+        name.key_str(),
+        sub.key_str()
+    )
+}
+
 pub fn push_connections(out: &mut String, art: &ArtifactSer) {
     for part in &art.parts {
         out.push_str(&connect_names_dot(&art.name, part));
@@ -47,12 +57,7 @@ pub fn push_connections(out: &mut String, art: &ArtifactSer) {
 
 /// Put a bunch of dot stuff into the standard graph format.
 pub fn wrap_dot(dot: &str, lr: bool) -> String {
-    let attrs = if lr {
-        // FIXME: randir isn't working anymore
-        "randir=LR;"
-    } else {
-        ""
-    };
+    let attrs = if lr { "rankdir=LR;" } else { "" };
 
     format!(
         r##"
@@ -87,20 +92,35 @@ pub fn subname_dot(md: &SerMarkdown<'_>, name: &str, sub: &SubName) -> String {
         Err(_) => return subname_raw(sub, None),
     };
 
-    let color = if md
-        .project
-        .get_impl(name.as_str(), Some(sub.as_str()))
-        .is_ok()
-    {
+    let color = subname_color(md.project, &name, sub);
+    subname_raw(sub, Some(&format!("penwidth=1.5; fontcolor=\"{}\"", color)))
+}
+
+/// A subname node which is unique to the artifact, so that the subnames of
+/// many artifacts can be in the same graph.
+pub fn artifact_subname_dot(md: &SerMarkdown<'_>, name: &Name, sub: &SubName) -> String {
+    let color = subname_color(md.project, name, sub);
+    subname_raw_key(
+        &format!("{}{}", name.key_str(), sub.key_str()),
+        sub,
+        Some(&format!("penwidth=1.5; fontcolor=\"{}\"", color)),
+    )
+}
+
+/// The color of a subname: blue if it is implemented, otherwise red.
+pub fn subname_color(project: &ProjectSer, name: &Name, sub: &SubName) -> &'static str {
+    if project.get_impl(name.as_str(), Some(sub.as_str())).is_ok() {
         BLUE
     } else {
         RED
-    };
-
-    subname_raw(sub, Some(&format!("penwidth=1.5; fontcolor=\"{}\"", color)))
+    }
 }
 
 fn subname_raw(sub: &SubName, attrs: Option<&str>) -> String {
+    subname_raw_key(sub.key_str(), sub, attrs)
+}
+
+fn subname_raw_key(key: &str, sub: &SubName, attrs: Option<&str>) -> String {
     let attrs = attrs.unwrap_or("style=filled; fillcolor=\"#DCDEE2\"");
     format!(
         r##"
@@ -113,7 +133,7 @@ fn subname_raw(sub: &SubName, attrs: Option<&str>) -> String {
             ]
         }}
         "##,
-        sub_key = sub.key_str(),
+        sub_key = key,
         sub = sub.as_str(),
         attrs = attrs,
     )
@@ -0,0 +1,702 @@
+/*  artifact: the requirements tracking tool made for developers
+ * Copyright (C) 2018 Rett Berg <@vitiral, vitiral@gmail.com>
+ *
+ * The source code is Licensed under either of
+ *
+ * * Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or
+ *   http://www.apache.org/licenses/LICENSE-2.0)
+ * * MIT license ([LICENSE-MIT](LICENSE-MIT) or
+ *   http://opensource.org/licenses/MIT)
+ *
+ * at your option.
+ *
+ * Unless you explicitly state otherwise, any contribution intentionally submitted
+ * for inclusion in the work by you, as defined in the Apache-2.0 license, shall
+ * be dual licensed as above, without any additional terms or conditions.
+ * */
+//! #SPC-query
+//! A small expression language for selecting artifacts.
+//!
+//! ```text
+//! type:SPC and spc<0.5 and partof~REQ-net and not impl:done and file:design/net/**
+//! ```
+//!
+//! A query is made of predicates of the form `FIELD OP VALUE`, combined with
+//! `and`, `or`, `not` and parentheses. Predicates next to each other without
+//! an operator are combined with `and`. `VALUE` can be quoted with `"` to
+//! include whitespace or parentheses.
+//!
+//! The operators are:
+//! - `:` matches a glob, where `*` matches anything except `/`, `**` matches
+//!   anything and `?` matches a single character. For `text` it matches a
+//!   (case insensitive) substring.
+//! - `~` searches with a regular expression.
+//! - `<`, `<=`, `>`, `>=` and `=` compare numbers (only `spc` and `tst`).
+//!
+//! Names, types and subnames are always matched case insensitively.
+
+use ergo_std::regex::RegexBuilder;
+use std::error;
+use std::fmt;
+use std::path::Path;
+use std::result;
+use std::str::FromStr;
+
+use crate::dev_prelude::*;
+use crate::name::{Name, SubName, Type};
+use crate::{ArtifactSer, Completed, ImplSer, ProjectSer};
+
+/// An error parsing a query. `column` starts at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub column: usize,
+    pub msg: String,
+}
+
+impl error::Error for QueryError {
+    fn description(&self) -> &str {
+        "invalid query"
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query at column {}: {}", self.column, self.msg)
+    }
+}
+
+/// The fields of an artifact which can be queried.
+///
+/// This is implemented for both the full artifact in `artifact_lib` and for `ArtifactSer`, so
+/// the same queries work in the cli and in the web ui.
+pub trait QueryArtifact {
+    fn name(&self) -> &Name;
+    /// The file the artifact is defined in, relative to `base` and with `/` separators.
+    fn relative_file(&self, base: &Path) -> String;
+    fn partof(&self) -> &IndexSet<Name>;
+    fn parts(&self) -> &IndexSet<Name>;
+    fn text(&self) -> &str;
+    fn subnames(&self) -> &IndexSet<SubName>;
+    fn impl_kind(&self) -> ImplKind;
+    fn completed(&self) -> &Completed;
+}
+
+/// A project whose artifacts can be queried.
+pub trait QueryProject {
+    type Artifact: QueryArtifact;
+    fn artifacts(&self) -> &IndexMap<Name, Self::Artifact>;
+    /// The base directory of the project, which files are matched relative to.
+    fn base(&self) -> &Path;
+}
+
+/// A parsed query.
+#[derive(Debug, Clone)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Pred(Predicate),
+}
+
+/// A single condition on an artifact.
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    Name(Regex),
+    Type(Type),
+    File(Regex),
+    Partof(Regex),
+    Parts(Regex),
+    Text(Regex),
+    Subname(Regex),
+    Impl(ImplKind),
+    Spc(Cmp, f32),
+    Tst(Cmp, f32),
+    /// Artifacts which are (recursively) part of an artifact matching the regex.
+    DescendantOf(Regex),
+    /// Artifacts which an artifact matching the regex is (recursively) part of.
+    AncestorOf(Regex),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImplKind {
+    Done,
+    Code,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Cmp {
+    fn compare(self, left: f32, right: f32) -> bool {
+        // completion is only displayed with 3 significant digits
+        const EPSILON: f32 = 0.0005;
+        match self {
+            Cmp::Lt => left < right - EPSILON,
+            Cmp::Le => left < right + EPSILON,
+            Cmp::Gt => left > right + EPSILON,
+            Cmp::Ge => left > right - EPSILON,
+            Cmp::Eq => (left - right).abs() < EPSILON,
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> result::Result<Query, QueryError> {
+        let tokens = tokenize(s)?;
+        let mut parser = QueryParser {
+            tokens: tokens,
+            pos: 0,
+            end: s.chars().count() + 1,
+        };
+        let query = parser.or()?;
+        if let Some(tok) = parser.tokens.get(parser.pos) {
+            return Err(QueryError {
+                column: tok.column,
+                msg: format!("unexpected {}", tok.kind),
+            });
+        }
+        Ok(query)
+    }
+}
+
+impl Query {
+    /// Return the names of the artifacts in the project which match the query.
+    pub fn filter<P: QueryProject>(&self, project: &P) -> IndexSet<Name> {
+        self.select(project.artifacts(), project.base())
+    }
+
+    /// Return the names of the `artifacts` which match the query. Files are
+    /// matched relative to `base`.
+    ///
+    /// The names are in the same order as `artifacts`.
+    pub fn select<A: QueryArtifact>(
+        &self,
+        artifacts: &IndexMap<Name, A>,
+        base: &Path,
+    ) -> IndexSet<Name> {
+        let selected = self.select_unordered(artifacts, base);
+        artifacts
+            .keys()
+            .filter(|n| selected.contains(*n))
+            .cloned()
+            .collect()
+    }
+
+    fn select_unordered<A: QueryArtifact>(
+        &self,
+        artifacts: &IndexMap<Name, A>,
+        base: &Path,
+    ) -> IndexSet<Name> {
+        match *self {
+            Query::And(ref a, ref b) => {
+                let a = a.select_unordered(artifacts, base);
+                let b = b.select_unordered(artifacts, base);
+                a.intersection(&b).cloned().collect()
+            }
+            Query::Or(ref a, ref b) => {
+                let mut a = a.select_unordered(artifacts, base);
+                a.extend(b.select_unordered(artifacts, base));
+                a
+            }
+            Query::Not(ref q) => {
+                let exclude = q.select_unordered(artifacts, base);
+                artifacts
+                    .keys()
+                    .filter(|n| !exclude.contains(*n))
+                    .cloned()
+                    .collect()
+            }
+            Query::Pred(ref pred) => pred.select(artifacts, base),
+        }
+    }
+}
+
+impl Predicate {
+    fn select<A: QueryArtifact>(
+        &self,
+        artifacts: &IndexMap<Name, A>,
+        base: &Path,
+    ) -> IndexSet<Name> {
+        match *self {
+            Predicate::DescendantOf(ref re) => walk_family(artifacts, re, A::parts),
+            Predicate::AncestorOf(ref re) => walk_family(artifacts, re, A::partof),
+            _ => artifacts
+                .iter()
+                .filter(|(_, art)| self.matches(art, base))
+                .map(|(name, _)| name.clone())
+                .collect(),
+        }
+    }
+
+    /// Whether the artifact matches. Graph predicates always return false.
+    ///
+    /// Files are matched relative to `base`.
+    fn matches<A: QueryArtifact>(&self, art: &A, base: &Path) -> bool {
+        let any =
+            |names: &IndexSet<Name>, re: &Regex| names.iter().any(|n| re.is_match(n.as_str()));
+        match *self {
+            Predicate::Name(ref re) => re.is_match(art.name().as_str()),
+            Predicate::Type(ty) => art.name().ty == ty,
+            Predicate::File(ref re) => re.is_match(&art.relative_file(base)),
+            Predicate::Partof(ref re) => any(art.partof(), re),
+            Predicate::Parts(ref re) => any(art.parts(), re),
+            Predicate::Text(ref re) => re.is_match(art.text()),
+            Predicate::Subname(ref re) => art.subnames().iter().any(|s| re.is_match(s.as_str())),
+            Predicate::Impl(kind) => art.impl_kind() == kind,
+            Predicate::Spc(cmp, v) => cmp.compare(art.completed().spc, v),
+            Predicate::Tst(cmp, v) => cmp.compare(art.completed().tst, v),
+            Predicate::DescendantOf(_) | Predicate::AncestorOf(_) => false,
+        }
+    }
+}
+
+/// Walk the family from every artifact matching `re`, returning all the
+/// artifacts that were reached (not including the starting ones, unless
+/// they are also reached).
+fn walk_family<A, F>(artifacts: &IndexMap<Name, A>, re: &Regex, next: F) -> IndexSet<Name>
+where
+    F: Fn(&A) -> &IndexSet<Name>,
+{
+    let mut out = IndexSet::new();
+    let mut stack: Vec<&Name> = artifacts
+        .iter()
+        .filter(|(name, _)| re.is_match(name.as_str()))
+        .flat_map(|(_, art)| next(art).iter())
+        .collect();
+    while let Some(name) = stack.pop() {
+        if out.contains(name) {
+            continue;
+        }
+        if let Some(art) = artifacts.get(name) {
+            out.insert(name.clone());
+            stack.extend(next(art).iter());
+        }
+    }
+    out
+}
+
+// ------------------------------
+// -- SER IMPLS
+
+impl QueryArtifact for ArtifactSer {
+    fn name(&self) -> &Name {
+        &self.name
+    }
+
+    fn relative_file(&self, base: &Path) -> String {
+        let file = Path::new(&self.file);
+        let rel = file.strip_prefix(base).unwrap_or(file);
+        rel.to_string_lossy().replace('\\', "/")
+    }
+
+    fn partof(&self) -> &IndexSet<Name> {
+        &self.partof
+    }
+
+    fn parts(&self) -> &IndexSet<Name> {
+        &self.parts
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn subnames(&self) -> &IndexSet<SubName> {
+        &self.subnames
+    }
+
+    fn impl_kind(&self) -> ImplKind {
+        match self.impl_ {
+            ImplSer::Done(_) => ImplKind::Done,
+            ImplSer::Code(_) => ImplKind::Code,
+            ImplSer::NotImpl => ImplKind::None,
+        }
+    }
+
+    fn completed(&self) -> &Completed {
+        &self.completed
+    }
+}
+
+impl QueryProject for ProjectSer {
+    type Artifact = ArtifactSer;
+
+    fn artifacts(&self) -> &IndexMap<Name, ArtifactSer> {
+        &self.artifacts
+    }
+
+    fn base(&self) -> &Path {
+        Path::new(&self.settings.base)
+    }
+}
+
+// ------------------------------
+// -- TOKENIZER
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Pred {
+        field: String,
+        op: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+            TokenKind::And => write!(f, "'and'"),
+            TokenKind::Or => write!(f, "'or'"),
+            TokenKind::Not => write!(f, "'not'"),
+            TokenKind::Pred {
+                ref field,
+                ref op,
+                ref value,
+            } => write!(f, "'{}{}{}'", field, op, value),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+
+fn tokenize(s: &str) -> result::Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    let err = |column: usize, msg: String| QueryError {
+        column: column,
+        msg: msg,
+    };
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '(' || c == ')' {
+            out.push(Token {
+                kind: if c == '(' {
+                    TokenKind::LParen
+                } else {
+                    TokenKind::RParen
+                },
+                column: column,
+            });
+            i += 1;
+            continue;
+        }
+
+        // field or keyword
+        let start = i;
+        while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '-' || chars[i] == '_')
+        {
+            i += 1;
+        }
+        let word: String = chars[start..i].iter().collect();
+        if word.is_empty() {
+            return Err(err(column, format!("unexpected {:?}", c)));
+        }
+
+        // operator
+        let op_start = i;
+        while i < chars.len() && ":~<>=".contains(chars[i]) {
+            i += 1;
+        }
+        let op: String = chars[op_start..i].iter().collect();
+        if op.is_empty() {
+            let kind = match word.to_ascii_lowercase().as_str() {
+                "and" => TokenKind::And,
+                "or" => TokenKind::Or,
+                "not" => TokenKind::Not,
+                _ => {
+                    return Err(err(
+                        column,
+                        format!(
+                            "expected FIELD:VALUE, 'and', 'or' or 'not' but got {:?}",
+                            word
+                        ),
+                    ));
+                }
+            };
+            out.push(Token {
+                kind: kind,
+                column: column,
+            });
+            continue;
+        }
+
+        // value
+        let mut value = String::new();
+        if i < chars.len() && chars[i] == '"' {
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    Some('\\') if i + 1 < chars.len() => {
+                        value.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some('"') => {
+                        i += 1;
+                        break;
+                    }
+                    Some(c) => {
+                        value.push(*c);
+                        i += 1;
+                    }
+                    None => return Err(err(column, "unclosed quote".into())),
+                }
+            }
+        } else {
+            while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != ')'
+            {
+                value.push(chars[i]);
+                i += 1;
+            }
+        }
+        if value.is_empty() {
+            return Err(err(column, format!("{}{} has no value", word, op)));
+        }
+        out.push(Token {
+            kind: TokenKind::Pred {
+                field: word.to_ascii_lowercase(),
+                op: op,
+                value: value,
+            },
+            column: column,
+        });
+    }
+    Ok(out)
+}
+
+// ------------------------------
+// -- PARSER
+
+struct QueryParser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// The column of the end of the query.
+    end: usize,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.pos).map(|t| &t.kind)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|t| t.column)
+            .unwrap_or(self.end)
+    }
+
+    /// `or := and ('or' and)*`
+    fn or(&mut self) -> result::Result<Query, QueryError> {
+        let mut out = self.and()?;
+        while self.peek() == Some(&TokenKind::Or) {
+            self.pos += 1;
+            out = Query::Or(Box::new(out), Box::new(self.and()?));
+        }
+        Ok(out)
+    }
+
+    /// `and := unary ('and'? unary)*`
+    fn and(&mut self) -> result::Result<Query, QueryError> {
+        let mut out = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(TokenKind::And) => self.pos += 1,
+                Some(TokenKind::Not) | Some(TokenKind::LParen) | Some(TokenKind::Pred { .. }) => {}
+                _ => return Ok(out),
+            }
+            out = Query::And(Box::new(out), Box::new(self.unary()?));
+        }
+    }
+
+    /// `unary := 'not' unary | '(' or ')' | predicate`
+    fn unary(&mut self) -> result::Result<Query, QueryError> {
+        let column = self.column();
+        let token = match self.tokens.get(self.pos) {
+            Some(t) => t.kind.clone(),
+            None => {
+                return Err(QueryError {
+                    column: column,
+                    msg: "unexpected end of query".into(),
+                });
+            }
+        };
+        self.pos += 1;
+        match token {
+            TokenKind::Not => Ok(Query::Not(Box::new(self.unary()?))),
+            TokenKind::LParen => {
+                let out = self.or()?;
+                if self.peek() != Some(&TokenKind::RParen) {
+                    return Err(QueryError {
+                        column: self.column(),
+                        msg: format!("expected ')' to close the '(' at column {}", column),
+                    });
+                }
+                self.pos += 1;
+                Ok(out)
+            }
+            TokenKind::Pred { field, op, value } => parse_predicate(&field, &op, &value)
+                .map(Query::Pred)
+                .map_err(|msg| QueryError {
+                    column: column,
+                    msg: msg,
+                }),
+            other => Err(QueryError {
+                column: column,
+                msg: format!("unexpected {}", other),
+            }),
+        }
+    }
+}
+
+fn parse_predicate(field: &str, op: &str, value: &str) -> result::Result<Predicate, String> {
+    // Fields which are matched against names are case insensitive
+    let names = |op: &str| match op {
+        ":" => Ok(glob_regex(value, true)),
+        "~" => regex(value, true),
+        _ => Err(format!("{} only supports ':' and '~', got {:?}", field, op)),
+    };
+    let pred = match field {
+        "name" => Predicate::Name(names(op)?),
+        "partof" => Predicate::Partof(names(op)?),
+        "parts" => Predicate::Parts(names(op)?),
+        "subname" => Predicate::Subname(names(op)?),
+        "descendant-of" => Predicate::DescendantOf(names(op)?),
+        "ancestor-of" => Predicate::AncestorOf(names(op)?),
+        "file" => Predicate::File(match op {
+            ":" => glob_regex(value, false),
+            "~" => regex(value, false)?,
+            _ => return Err(format!("file only supports ':' and '~', got {:?}", op)),
+        }),
+        "text" => Predicate::Text(match op {
+            ":" => regex(&ergo_std::regex::escape(value), true)?,
+            "~" => regex(value, false)?,
+            _ => return Err(format!("text only supports ':' and '~', got {:?}", op)),
+        }),
+        "type" => {
+            if op != ":" {
+                return Err(format!("type only supports ':', got {:?}", op));
+            }
+            Predicate::Type(match value.to_ascii_uppercase().as_str() {
+                "REQ" => Type::REQ,
+                "SPC" => Type::SPC,
+                "TST" => Type::TST,
+                _ => return Err(format!("type must be REQ, SPC or TST, got {:?}", value)),
+            })
+        }
+        "impl" => {
+            if op != ":" {
+                return Err(format!("impl only supports ':', got {:?}", op));
+            }
+            Predicate::Impl(match value.to_ascii_lowercase().as_str() {
+                "done" => ImplKind::Done,
+                "code" => ImplKind::Code,
+                "none" => ImplKind::None,
+                _ => return Err(format!("impl must be done, code or none, got {:?}", value)),
+            })
+        }
+        "spc" | "tst" => {
+            let cmp = match op {
+                "<" => Cmp::Lt,
+                "<=" => Cmp::Le,
+                ">" => Cmp::Gt,
+                ">=" => Cmp::Ge,
+                "=" | ":" => Cmp::Eq,
+                _ => return Err(format!("invalid comparison {:?}", op)),
+            };
+            let ratio = parse_ratio(value)?;
+            if field == "spc" {
+                Predicate::Spc(cmp, ratio)
+            } else {
+                Predicate::Tst(cmp, ratio)
+            }
+        }
+        _ => {
+            return Err(format!(
+                "unknown field {:?}, expected one of name, type, file, partof, parts, text, \
+                 subname, impl, spc, tst, descendant-of or ancestor-of",
+                field
+            ));
+        }
+    };
+    Ok(pred)
+}
+
+/// Parse a completion ratio, either `0.5` or `50%`.
+fn parse_ratio(value: &str) -> result::Result<f32, String> {
+    let (v, scale) = if value.ends_with('%') {
+        (&value[..value.len() - 1], 100.0)
+    } else {
+        (value, 1.0)
+    };
+    let ratio = match v.parse::<f32>() {
+        Ok(r) => r / scale,
+        Err(_) => return Err(format!("invalid number {:?}", value)),
+    };
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err(format!(
+            "completion must be between 0 and 1 (or 0% and 100%), got {:?}",
+            value
+        ));
+    }
+    Ok(ratio)
+}
+
+fn regex(re: &str, case_insensitive: bool) -> result::Result<Regex, String> {
+    RegexBuilder::new(re)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Convert a glob into an anchored regex.
+///
+/// `*` matches anything except `/`, `**` matches anything and `?` matches any
+/// single character except `/`.
+pub fn glob_regex(glob: &str, case_insensitive: bool) -> Regex {
+    let mut re = String::with_capacity(glob.len() * 2 + 2);
+    re.push('^');
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    re.push_str(".*");
+                } else {
+                    re.push_str("[^/]*");
+                }
+            }
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&ergo_std::regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    // expect: all special characters are escaped
+    expect!(regex(&re, case_insensitive))
+}
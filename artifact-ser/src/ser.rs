@@ -18,7 +18,10 @@
 
 use crate::fmt;
 
-use super::{Completed, HashIm, SettingsExport, SettingsFormat, SettingsParse};
+use super::{
+    Completed, HashIm, SettingsExport, SettingsFormat, SettingsLint, SettingsNew, SettingsParse,
+    SettingsServe,
+};
 use crate::dev_prelude::*;
 use crate::lint;
 use crate::name::{Name, SubName};
@@ -45,6 +48,9 @@ pub enum WebType {
 pub struct ProjectResultSer {
     pub project: ProjectSer,
     pub lints: lint::Categorized,
+    /// The files written or removed by the request.
+    #[serde(default)]
+    pub changed: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -63,6 +69,20 @@ pub enum ArtifactOpSer {
     },
 }
 
+/// The data of the `MergeConflict` error, one for each operation which could
+/// not be merged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactConflictSer {
+    pub orig_id: HashIm,
+    pub base: ArtifactImSer,
+    pub ours: Option<ArtifactImSer>,
+    pub theirs: Option<ArtifactImSer>,
+    pub theirs_id: Option<HashIm>,
+    /// The fields which were changed differently, empty if the artifact was
+    /// deleted by one side.
+    pub fields: Vec<String>,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct ProjectSer {
     pub settings: SettingsSer,
@@ -111,6 +131,12 @@ pub struct SettingsSer {
     pub format: SettingsFormat,
     #[serde(default)]
     pub export: SettingsExport,
+    #[serde(default)]
+    pub lint: SettingsLint,
+    #[serde(default)]
+    pub new: SettingsNew,
+    #[serde(default)]
+    pub serve: SettingsServe,
 }
 
 impl SettingsSer {
@@ -228,7 +254,7 @@ impl fmt::Debug for CodeLocSer {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ArtifactImSer {
     pub name: Name,
     pub file: String,
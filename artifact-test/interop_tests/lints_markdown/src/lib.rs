@@ -0,0 +1,2 @@
+// #SPC-md
+// #SPC-other
@@ -0,0 +1 @@
+// #SPC-documented
@@ -0,0 +1,4 @@
+// #SPC-subs
+// #SPC-subs.done_in_code
+// #SPC-subs.misspelled
+// #SPC-subs.casing
@@ -49,3 +49,26 @@ pub fn arb_rel_file_paths(size: usize) -> BoxedStrategy<HashSet<String>> {
 // fn get_settings(raw: BTreeMap<Name, ArtifactRaw>) -> (Settings, BTreeMap<Name, Path>) {
 //     unimplemented!();
 // }
+
+/// A fixture artifact with no parts, text or implementation.
+///
+/// Use struct update syntax to set any other fields:
+/// `Artifact { text: "...".into(), ..art("REQ-a", "/fake", &[], 0.0, 0.0) }`
+pub fn art(name: &str, file: &str, partof: &[&str], spc: f32, tst: f32) -> Artifact {
+    Artifact {
+        id: HashIm::default(),
+        name: name!(name),
+        file: PathSer::from(file),
+        partof: names(partof),
+        parts: indexset! {},
+        completed: Completed { spc: spc, tst: tst },
+        text: String::new(),
+        impl_: Impl::NotImpl,
+        subnames: indexset! {},
+    }
+}
+
+/// Collect the names into a set, e.g. for `Artifact::parts`.
+pub fn names(raw: &[&str]) -> IndexSet<Name> {
+    raw.iter().map(|n| name!(*n)).collect()
+}